@@ -6,7 +6,7 @@ use log::warn;
 use simplelog::{Config, ConfigBuilder, LevelFilter, SimpleLogger};
 
 use pueue::daemon::cli::CliArguments;
-use pueue::daemon::run;
+use pueue::daemon::{export_state, import_state, run};
 
 #[tokio::main(flavor = "multi_thread", worker_threads = 4)]
 async fn main() -> Result<()> {
@@ -37,6 +37,13 @@ async fn main() -> Result<()> {
 
     SimpleLogger::init(level, logger_config).unwrap();
 
+    if let Some(path) = &opt.export_state {
+        return export_state(opt.config, opt.profile, path);
+    }
+    if let Some(path) = &opt.import_state {
+        return import_state(opt.config, opt.profile, path);
+    }
+
     run(opt.config, opt.profile, false).await
 }
 
@@ -7,10 +7,13 @@ use clap::ArgAction;
 use clap::{Parser, ValueEnum, ValueHint};
 
 use pueue_lib::network::message::Signal;
+use pueue_lib::resources::parse_resource;
 
 use super::commands::WaitTargetStatus;
 
 #[derive(Parser, Debug)]
+// The subcommand is only created once, so the size of the `add` options doesn't matter.
+#[allow(clippy::large_enum_variant)]
 pub enum SubCommand {
     #[command(
         about = "Enqueue a task for execution.\n\
@@ -24,7 +27,11 @@ pub enum SubCommand {
     )]
     Add {
         /// The command to be added.
-        #[arg(required = true, num_args(1..), value_hint = ValueHint::CommandWithArguments)]
+        #[arg(
+            required_unless_present_any = ["from_file", "from_stdin"],
+            num_args(1..),
+            value_hint = ValueHint::CommandWithArguments
+        )]
         command: Vec<String>,
 
         /// Specify current working directory.
@@ -57,9 +64,25 @@ pub enum SubCommand {
 
         /// Start the task once all specified tasks have successfully finished.
         /// As soon as one of the dependencies fails, this task will fail as well.
-        #[arg(name = "after", short, long, num_args(1..))]
+        #[arg(name = "after", short, long, visible_alias = "after-success", num_args(1..))]
         dependencies: Vec<usize>,
 
+        /// Start the task once all specified tasks have failed.
+        /// As soon as one of them succeeds, this task will fail.
+        #[arg(long, value_name = "TASK_ID", num_args(1..))]
+        after_failure: Vec<usize>,
+
+        /// Start the task once all specified tasks have finished, no matter their result.
+        /// This is useful for cleanup tasks.
+        #[arg(long, value_name = "TASK_ID", num_args(1..))]
+        after_any: Vec<usize>,
+
+        /// Start the task once the specified task has exited with the given exit code, e.g. "3:2".
+        /// As soon as it finishes in any other way, this task will fail.
+        /// Can be passed multiple times.
+        #[arg(long, value_name = "TASK_ID:CODE", value_parser = parse_exit_code_dependency)]
+        after_exit_code: Vec<(usize, i32)>,
+
         /// Start this task with a higher priority.
         /// The higher the number, the faster it will be processed.
         #[arg(short='o', long, num_args(1..))]
@@ -71,8 +94,70 @@ pub enum SubCommand {
         #[arg(short, long)]
         label: Option<String>,
 
+        /// Automatically re-run the task up to <retries> times, if it fails or errors.
+        #[arg(long)]
+        retries: Option<usize>,
+
+        /// Wait this long before a failed task is retried, e.g. "30s", "5m" or "1h".
+        /// Plain numbers are interpreted as seconds.
+        #[arg(long, requires = "retries", value_parser = parse_duration)]
+        retry_delay: Option<u64>,
+
+        /// Double the retry delay after each failed attempt.
+        #[arg(long, requires = "retries")]
+        exponential_backoff: bool,
+
+        /// Terminate the task if it runs longer than this, e.g. "30s", "5m" or "1h".
+        /// The task first receives a SIGTERM and is killed, if it's still alive after the
+        /// configured grace period.
+        #[arg(long, value_parser = parse_duration)]
+        timeout: Option<u64>,
+
+        /// Declare a resource the task needs while running, e.g. "cpu=2" or "mem=4G".
+        /// The task is only started, if its group and the daemon still have enough of it left.
+        /// Can be passed multiple times.
+        #[arg(long = "resource", value_name = "NAME=AMOUNT", value_parser = parse_resource)]
+        resources: Vec<(String, u64)>,
+
+        /// Write stdout and stderr of the task to separate log files.
+        /// They can then be viewed separately via `log --stdout/--stderr`.
+        #[arg(long)]
+        separate_output: bool,
+
+        /// Add a task array with one task per parameter instead of a single task.
+        /// Parameters are either an inclusive range, e.g. "1..10" or "0..100:10" with a step,
+        /// or a comma separated list, e.g. "a,b,c".
+        /// The command and the label may use the parameter via "{{value}}" and its position
+        /// via "{{index}}".
+        #[arg(long, value_name = "PARAMETERS", conflicts_with = "array_file")]
+        array: Option<String>,
+
+        /// Like `--array`, but use the lines of a file as parameters.
+        /// Pass "-" to read them from stdin.
+        #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+        array_file: Option<PathBuf>,
+
+        /// Add many tasks at once, which are read from a file with one JSON object per line, e.g.
+        /// {"command": "ls", "cwd": "/tmp", "label": "list", "group": "io", "priority": 1,
+        /// "env": {"KEY": "value"}, "after": [3], "after_entry": [0]}.
+        /// Only the command is required, all other options of this command serve as defaults.
+        /// `after` takes the ids of existing tasks, `after_entry` the positions of earlier
+        /// entries of the same file, starting at 0 and ignoring empty lines.
+        #[arg(
+            long,
+            value_name = "FILE",
+            value_hint = ValueHint::FilePath,
+            conflicts_with_all = ["command", "array", "array_file", "from_stdin"]
+        )]
+        from_file: Option<PathBuf>,
+
+        /// Like `--from-file`, but read the tasks from stdin.
+        #[arg(long, conflicts_with_all = ["command", "array", "array_file"])]
+        from_stdin: bool,
+
         /// Only return the task id instead of a text.
         /// This is useful when working with dependencies.
+        /// For task arrays, the ids of all tasks are returned.
         #[arg(short, long)]
         print_task_id: bool,
     },
@@ -177,6 +262,10 @@ pub enum SubCommand {
         #[arg(short = 'g', long, conflicts_with = "all_failed")]
         failed_in_group: Option<String>,
 
+        /// Restart all tasks of a task array.
+        #[arg(long, value_name = "ARRAY_ID", conflicts_with_all = ["all_failed", "failed_in_group"])]
+        array: Option<usize>,
+
         /// Immediately start the tasks, no matter how many open slots there are.
         /// This will ignore any dependencies tasks may have.
         #[arg(short = 'k', long, conflicts_with = "stashed")]
@@ -208,6 +297,11 @@ pub enum SubCommand {
         /// Edit the tasks' labels before restarting.
         #[arg(short = 'l', long)]
         edit_label: bool,
+
+        /// Set a new timeout for the restarted tasks, e.g. "30s", "5m" or "1h".
+        /// Otherwise, the previous timeout of the tasks is kept.
+        #[arg(long, value_parser = parse_duration)]
+        timeout: Option<u64>,
     },
 
     #[command(about = "Either pause running tasks or specific groups of tasks.\n\
@@ -249,6 +343,10 @@ pub enum SubCommand {
         #[arg(short, long)]
         all: bool,
 
+        /// Kill all running tasks of a task array.
+        #[arg(long, value_name = "ARRAY_ID", conflicts_with_all = ["group", "all"])]
+        array: Option<usize>,
+
         /// Deprecated: this switch no longer has any effect.
         #[arg(short, long)]
         children: bool,
@@ -319,7 +417,7 @@ where:
   - column := `id | status | command | label | path | enqueue_at | dependencies | start | end`
   - filter := `[filter_column] [filter_op] [filter_value]`
     (note: not all columns support all operators, see \"Filter columns\" below.)
-  - filter_column := `start | end | enqueue_at | status | label`
+  - filter_column := `start | end | enqueue_at | status | label | command | exit_code`
   - filter_op := `= | != | < | > | %=`
     (`%=` means 'contains', as in the test value is a substring of the column value)
   - order_by := `order_by [column] [order_direction]`
@@ -329,6 +427,9 @@ where:
   - limit_count := a positive integer
 
 Filter columns:
+  - `label` and `command` support the operators `=`, `!=` and `%=`.
+    Their filter value is the rest of the query.
+  - `exit_code` supports the operators `=` and `!=` against a number.
   - `start`, `end`, `enqueue_at` contain a datetime
     which support the operators `=`, `!=`, `<`, `>`
     against test values that are:
@@ -374,6 +475,41 @@ https://github.com/Nukesor/pueue/issues/350#issue-1359083118"
         group: Option<String>,
     },
 
+    /// Search the archive of finished tasks, that have been removed.
+    /// This requires the `archive_removed_tasks` option of the daemon.
+    History {
+        /// A query to filter, order or limit the archived tasks.
+        /// This uses the same syntax as `status`, see `pueue status --help`.
+        /// The shown ids are the ids of the tasks in the archive.
+        query: Vec<String>,
+
+        /// Print the archived tasks as json to stdout.
+        #[arg(short, long)]
+        json: bool,
+
+        #[arg(short, long)]
+        /// Only show archived tasks of a specific group
+        group: Option<String>,
+
+        /// Only fetch the newest X archived tasks.
+        /// The query is applied to these tasks afterwards.
+        #[arg(long)]
+        limit: Option<usize>,
+
+        /// Only fetch archived tasks with an id lower than this.
+        /// Together with `--limit`, this allows to page through the archive.
+        #[arg(long)]
+        before: Option<usize>,
+
+        /// Show the output of the archived task with this id instead.
+        #[arg(long, conflicts_with_all = ["query", "json", "group", "limit", "before"])]
+        log: Option<usize>,
+
+        /// Only print the last X lines of the archived task's output.
+        #[arg(short, long, requires = "log")]
+        lines: Option<usize>,
+    },
+
     #[command(about = "Display the log output of finished tasks.\n\
             Only the last few lines will be shown by default.\n\
             If you want to follow the output of a task, please use the \"follow\" subcommand.")]
@@ -396,6 +532,19 @@ https://github.com/Nukesor/pueue/issues/350#issue-1359083118"
         /// Show the whole output.
         #[arg(short, long)]
         full: bool,
+
+        /// Only show stdout of tasks that write stdout and stderr to separate files.
+        #[arg(long, conflicts_with = "stderr")]
+        stdout: bool,
+
+        /// Only show stderr of tasks that write stdout and stderr to separate files.
+        #[arg(long)]
+        stderr: bool,
+
+        /// Prefix each line with the time it has been written.
+        /// This requires the `log_timestamps` option of the daemon.
+        #[arg(short, long)]
+        timestamps: bool,
     },
 
     /// Follow the output of a currently running task.
@@ -410,6 +559,19 @@ https://github.com/Nukesor/pueue/issues/350#issue-1359083118"
         /// Only print the last X lines of the output before following
         #[arg(short, long)]
         lines: Option<usize>,
+
+        /// Only follow stdout of a task that writes stdout and stderr to separate files.
+        #[arg(long, conflicts_with = "stderr")]
+        stdout: bool,
+
+        /// Only follow stderr of a task that writes stdout and stderr to separate files.
+        #[arg(long)]
+        stderr: bool,
+
+        /// Prefix each line with the time it has been written.
+        /// This requires the `log_timestamps` option of the daemon.
+        #[arg(short, long)]
+        timestamps: bool,
     },
 
     #[command(about = "Wait until tasks are finished.\n\
@@ -428,6 +590,10 @@ https://github.com/Nukesor/pueue/issues/350#issue-1359083118"
         #[arg(short, long)]
         all: bool,
 
+        /// Wait for all tasks of a task array.
+        #[arg(long, value_name = "ARRAY_ID", conflicts_with_all = ["group", "all"])]
+        array: Option<usize>,
+
         /// Don't show any log output while waiting
         #[arg(short, long)]
         quiet: bool,
@@ -469,12 +635,36 @@ https://github.com/Nukesor/pueue/issues/350#issue-1359083118"
             This limit is only considered when tasks are scheduled.")]
     Parallel {
         /// The amount of allowed parallel tasks.
-        #[arg(value_parser = min_one)]
+        /// `0` removes the daemon-wide limit, if `--global` is passed.
         parallel_tasks: Option<usize>,
 
         /// Set the amount for a specific group.
         #[arg(name = "group", short, long)]
         group: Option<String>,
+
+        /// Set the daemon-wide limit across all groups instead.
+        /// Groups with a higher priority get the next free slot first.
+        #[arg(long, conflicts_with = "group")]
+        global: bool,
+    },
+
+    #[command(
+        about = "Manage recurring tasks, which are created by the daemon on a schedule.\n\
+        By default, this will simply display all known schedules."
+    )]
+    Schedule {
+        /// Print the list of schedules as json.
+        #[arg(short, long)]
+        json: bool,
+
+        #[command(subcommand)]
+        cmd: Option<ScheduleCommand>,
+    },
+
+    /// Run workflows, which consist of named steps that depend on each other.
+    Workflow {
+        #[command(subcommand)]
+        cmd: WorkflowCommand,
     },
 
     /// Generates shell completion files.
@@ -498,6 +688,36 @@ pub enum GroupCommand {
         /// Set the amount of parallel tasks this group can have.
         #[arg(short, long, value_parser = min_one)]
         parallel: Option<usize>,
+
+        /// Declare a resource the tasks of this group can use, e.g. "cpu=8" or "mem=32G".
+        /// Can be passed multiple times.
+        #[arg(long = "resource", value_name = "NAME=AMOUNT", value_parser = parse_resource)]
+        resources: Vec<(String, u64)>,
+
+        /// Groups with a higher priority start their tasks first.
+        /// This decides which group gets the next free slot of the daemon-wide limit.
+        #[arg(long, allow_negative_numbers = true)]
+        priority: Option<i32>,
+    },
+
+    /// Replace the resources the tasks of a group can use.
+    /// Passing no resources removes all resource limits of the group.
+    Resources {
+        name: String,
+
+        /// A resource of the group, e.g. "cpu=8" or "mem=32G".
+        /// Can be passed multiple times.
+        #[arg(long = "resource", value_name = "NAME=AMOUNT", value_parser = parse_resource)]
+        resources: Vec<(String, u64)>,
+    },
+
+    /// Change the priority of a group.
+    /// Groups with a higher priority get the next free slot of the daemon-wide limit first.
+    Priority {
+        name: String,
+
+        #[arg(allow_negative_numbers = true)]
+        priority: i32,
     },
 
     /// Remove a group by name.
@@ -505,6 +725,85 @@ pub enum GroupCommand {
     Remove { name: String },
 }
 
+#[derive(Parser, Debug)]
+pub enum ScheduleCommand {
+    /// Add a schedule, which periodically creates a new task with the given command.
+    #[command(trailing_var_arg = true)]
+    Add {
+        /// The command of the tasks that are created by this schedule.
+        #[arg(required = true, num_args(1..), value_hint = ValueHint::CommandWithArguments)]
+        command: Vec<String>,
+
+        /// A cron expression with five fields, e.g. "*/15 * * * *" or "0 3 * * mon-fri".
+        #[arg(long, required_unless_present = "every", conflicts_with = "every")]
+        cron: Option<String>,
+
+        /// Create a task in a fixed interval, e.g. "30s", "5m" or "1h".
+        #[arg(long, value_parser = parse_duration)]
+        every: Option<u64>,
+
+        /// Specify current working directory.
+        #[arg(name = "working-directory", short = 'w', long, value_hint = ValueHint::DirPath)]
+        working_directory: Option<PathBuf>,
+
+        /// Escape any special shell characters (" ", "&", "!", etc.).
+        /// Beware: This implicitly disables nearly all shell specific syntax ("&&", "&>").
+        #[arg(short, long)]
+        escape: bool,
+
+        /// Assign the created tasks to a group.
+        #[arg(short, long)]
+        group: Option<String>,
+
+        /// Add a label to the created tasks.
+        #[arg(short, long)]
+        label: Option<String>,
+
+        /// Don't create a new task, if the task of the previous run is still queued or running.
+        #[arg(long)]
+        skip_if_running: bool,
+
+        /// If runs were missed while the daemon was down, run once as soon as it's up again.
+        #[arg(long)]
+        catch_up: bool,
+    },
+
+    /// Remove a schedule. Tasks that have already been created aren't touched.
+    Remove { schedule_id: usize },
+
+    /// Pause a schedule. It won't create any tasks until it's resumed.
+    Pause { schedule_id: usize },
+
+    /// Resume a paused schedule. Runs that have been missed in the meantime are skipped.
+    Resume { schedule_id: usize },
+}
+
+#[derive(Parser, Debug)]
+pub enum WorkflowCommand {
+    /// Create the tasks of all steps of a workflow file at once.
+    /// Each step's task depends on the tasks of the steps it `needs`.
+    Run {
+        /// The YAML file that describes the workflow.
+        #[arg(value_hint = ValueHint::FilePath)]
+        file: PathBuf,
+
+        /// Stash all tasks of the workflow instead of queueing them.
+        #[arg(short, long)]
+        stashed: bool,
+    },
+
+    /// Show the progress of workflow runs.
+    /// By default, all runs are shown.
+    Status {
+        /// Only show a single run.
+        run: Option<usize>,
+
+        /// Print the steps of the runs as json.
+        #[arg(short, long)]
+        json: bool,
+    },
+}
+
 #[derive(Parser, ValueEnum, Debug, Clone, PartialEq, Eq)]
 pub enum ColorChoice {
     Auto,
@@ -566,6 +865,38 @@ fn parse_delay_until(src: &str) -> Result<DateTime<Local>, String> {
     ))
 }
 
+/// Parse a duration such as "90", "30s", "5m", "2h" or "1d" into seconds.
+/// Plain numbers are interpreted as seconds.
+fn parse_duration(src: &str) -> Result<u64, String> {
+    let src = src.trim();
+    let (number, multiplier) = match src.char_indices().last() {
+        Some((index, 's')) => (&src[..index], 1),
+        Some((index, 'm')) => (&src[..index], 60),
+        Some((index, 'h')) => (&src[..index], 60 * 60),
+        Some((index, 'd')) => (&src[..index], 60 * 60 * 24),
+        _ => (src, 1),
+    };
+
+    number
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .and_then(|number| number.checked_mul(multiplier))
+        .ok_or_else(|| {
+            format!("could not parse \"{src}\" as duration, e.g. \"30s\", \"5m\" or \"1h\"")
+        })
+}
+
+/// Parse a dependency on a specific exit code, such as "3:2", into the task id and the exit code.
+fn parse_exit_code_dependency(src: &str) -> Result<(usize, i32), String> {
+    let error = || String::from("expected a task id and an exit code, e.g. \"3:2\"");
+    let (task_id, code) = src.split_once(':').ok_or_else(error)?;
+    let task_id = task_id.trim().parse::<usize>().map_err(|_| error())?;
+    let code = code.trim().parse::<i32>().map_err(|_| error())?;
+
+    Ok((task_id, code))
+}
+
 /// Validator function. The input string has to be parsable as int and bigger than 0
 fn min_one(value: &str) -> Result<usize, String> {
     match value.parse::<usize>() {
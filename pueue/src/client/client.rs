@@ -1,19 +1,28 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
 use std::env::{current_dir, vars};
+use std::fs::read_to_string;
 use std::io::{self, stdout, Write};
-use std::{borrow::Cow, collections::HashMap};
+use std::path::{Path, PathBuf};
 
 use anyhow::{bail, Context, Result};
 use clap::crate_version;
 use crossterm::tty::IsTty;
 use log::error;
 
+use pueue_lib::log::LogStream;
 use pueue_lib::network::message::*;
 use pueue_lib::network::protocol::*;
-use pueue_lib::network::secret::read_shared_secret;
+use pueue_lib::network::secret::read_client_secret;
+use pueue_lib::schedule::ScheduleTrigger;
 use pueue_lib::settings::Settings;
 use pueue_lib::state::PUEUE_DEFAULT_GROUP;
+use pueue_lib::task::{DependencyCondition, RetryBackoff, RetryPolicy};
+use pueue_lib::workflow::Workflow;
 
-use crate::client::cli::{CliArguments, ColorChoice, GroupCommand, SubCommand};
+use crate::client::cli::{
+    CliArguments, ColorChoice, GroupCommand, ScheduleCommand, SubCommand, WorkflowCommand,
+};
 use crate::client::commands::*;
 use crate::client::display::*;
 
@@ -60,6 +69,96 @@ pub fn selection_from_params(
     }
 }
 
+/// Get the parameters of a task array, which are passed either via `--array` or `--array-file`.
+///
+/// `--array` takes an inclusive range with an optional step, e.g. "1..10" or "0..100:10",
+/// or a comma separated list, e.g. "a,b,c".
+/// `--array-file` takes a file, whose non-empty lines are the parameters, or "-" for stdin.
+/// Arrays with more than [MAX_ARRAY_SIZE] parameters are rejected.
+pub fn array_parameters(
+    array: &Option<String>,
+    array_file: &Option<PathBuf>,
+) -> Result<Option<Vec<String>>> {
+    let parameters: Vec<String> = if let Some(path) = array_file {
+        let content = if path == Path::new("-") {
+            io::read_to_string(io::stdin()).context("Failed to read array parameters from stdin")?
+        } else {
+            read_to_string(path)
+                .with_context(|| format!("Failed to read array parameters from {path:?}"))?
+        };
+        content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(ToString::to_string)
+            .collect()
+    } else if let Some(array) = array {
+        match parse_array_range(array)? {
+            Some(parameters) => parameters,
+            None => array
+                .split(',')
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(ToString::to_string)
+                .collect(),
+        }
+    } else {
+        return Ok(None);
+    };
+
+    if parameters.len() > MAX_ARRAY_SIZE {
+        bail!(
+            "The task array has {} parameters, but at most {MAX_ARRAY_SIZE} are allowed",
+            parameters.len()
+        );
+    }
+
+    Ok(Some(parameters))
+}
+
+/// Try to interpret the parameters of a task array as a range.
+/// The size of the range is checked before it's expanded.
+fn parse_array_range(array: &str) -> Result<Option<Vec<String>>> {
+    let Some((start, end)) = array.split_once("..") else {
+        return Ok(None);
+    };
+    let (end, step) = end.split_once(':').unwrap_or((end, "1"));
+    let (Ok(start), Ok(end), Ok(step)) = (
+        start.trim().parse::<i64>(),
+        end.trim().parse::<i64>(),
+        step.trim().parse::<usize>(),
+    ) else {
+        return Ok(None);
+    };
+
+    if step == 0 || start > end {
+        bail!("The range {array} doesn't contain any parameters");
+    }
+    let size = (i128::from(end) - i128::from(start)) / step as i128 + 1;
+    if size > MAX_ARRAY_SIZE as i128 {
+        bail!("The range {array} has {size} parameters, but at most {MAX_ARRAY_SIZE} are allowed");
+    }
+
+    let parameters = (start..=end)
+        .step_by(step)
+        .map(|value| value.to_string())
+        .collect();
+    Ok(Some(parameters))
+}
+
+/// This is a small helper which determines the selected output stream of the
+/// `--stdout` and `--stderr` commandline parameters.
+/// If neither is given, all output is selected.
+pub fn selected_log_stream(stdout: bool, stderr: bool) -> Option<LogStream> {
+    if stdout {
+        Some(LogStream::Stdout)
+    } else if stderr {
+        Some(LogStream::Stderr)
+    } else {
+        None
+    }
+}
+
 impl Client {
     /// Initialize a new client.
     /// This includes establishing a connection to the daemon:
@@ -75,7 +174,7 @@ impl Client {
         // Next we do a handshake with the daemon
         // 1. Client sends the secret to the daemon.
         // 2. If successful, the daemon responds with their version.
-        let secret = read_shared_secret(&settings.shared.shared_secret_path())?;
+        let secret = read_client_secret(&settings.shared)?;
         send_bytes(&secret, &mut stream)
             .await
             .context("Failed to send secret.")?;
@@ -102,7 +201,12 @@ impl Client {
                 match subcommand {
                     SubCommand::Status { json, .. } => !json,
                     SubCommand::Log { json, .. } => !json,
+                    SubCommand::History { json, .. } => !json,
                     SubCommand::Group { json, .. } => !json,
+                    SubCommand::Schedule { json, .. } => !json,
+                    SubCommand::Workflow {
+                        cmd: WorkflowCommand::Status { json, .. },
+                    } => !json,
                     _ => true,
                 }
             } else {
@@ -147,6 +251,8 @@ impl Client {
     ///
     /// The command handling is splitted into "simple" and "complex" commands.
     pub async fn start(&mut self) -> Result<()> {
+        self.resolve_task_array().await?;
+
         // Return early, if the command has already been handled.
         if self.handle_complex_command().await? {
             return Ok(());
@@ -158,6 +264,50 @@ impl Client {
         Ok(())
     }
 
+    /// Commands that can target a task array by its id, target all tasks of the array.
+    /// Add those tasks to the command's task ids, so it can be handled like any other command.
+    async fn resolve_task_array(&mut self) -> Result<()> {
+        let array_id = match &self.subcommand {
+            SubCommand::Kill {
+                array: Some(array_id),
+                ..
+            }
+            | SubCommand::Restart {
+                array: Some(array_id),
+                ..
+            }
+            | SubCommand::Wait {
+                array: Some(array_id),
+                ..
+            } => *array_id,
+            _ => return Ok(()),
+        };
+
+        let state = get_state(&mut self.stream).await?;
+        let array_task_ids: Vec<usize> = state
+            .tasks
+            .values()
+            .filter(|task| {
+                task.array
+                    .as_ref()
+                    .map_or(false, |array| array.id == array_id)
+            })
+            .map(|task| task.id)
+            .collect();
+        if array_task_ids.is_empty() {
+            bail!("There's no task array with id {array_id}");
+        }
+
+        if let SubCommand::Kill { task_ids, .. }
+        | SubCommand::Restart { task_ids, .. }
+        | SubCommand::Wait { task_ids, .. } = &mut self.subcommand
+        {
+            task_ids.extend(array_task_ids);
+        }
+
+        Ok(())
+    }
+
     /// Handle all complex client-side functionalities.
     /// Some functionalities need special handling and are contained in their own functions
     /// with their own communication code.
@@ -217,6 +367,7 @@ impl Client {
                 all,
                 quiet,
                 status,
+                ..
             } => {
                 let selection = selection_from_params(*all, group, task_ids);
                 wait(&mut self.stream, &self.style, selection, *quiet, status).await?;
@@ -233,6 +384,8 @@ impl Client {
                 edit,
                 edit_path,
                 edit_label,
+                timeout,
+                ..
             } => {
                 // `not_in_place` superseeds both other configs
                 let in_place =
@@ -249,11 +402,18 @@ impl Client {
                     *edit,
                     *edit_path,
                     *edit_label,
+                    *timeout,
                 )
                 .await?;
                 Ok(true)
             }
-            SubCommand::Follow { task_id, lines } => {
+            SubCommand::Follow {
+                task_id,
+                lines,
+                stdout,
+                stderr,
+                timestamps,
+            } => {
                 // If we're supposed to read the log files from the local system, we don't have to
                 // do any communication with the daemon.
                 // Thereby we handle this in a separate function.
@@ -263,6 +423,8 @@ impl Client {
                         &self.settings.shared.pueue_directory(),
                         task_id,
                         *lines,
+                        selected_log_stream(*stdout, *stderr),
+                        *timestamps,
                     )
                     .await?;
                     return Ok(true);
@@ -323,6 +485,12 @@ impl Client {
                 print_error(&self.style, &text);
                 std::process::exit(1);
             }
+            Message::StatusResponse(state)
+                if matches!(self.subcommand, SubCommand::Workflow { .. }) =>
+            {
+                let workflow_text = format_workflows(&state, &self.subcommand, &self.style);
+                println!("{workflow_text}");
+            }
             Message::StatusResponse(state) => {
                 let tasks = state.tasks.values().cloned().collect();
                 let output =
@@ -330,12 +498,25 @@ impl Client {
                 println!("{output}");
             }
             Message::LogResponse(task_logs) => {
-                print_logs(task_logs, &self.subcommand, &self.style, &self.settings)
+                if let SubCommand::History { lines, .. } = &self.subcommand {
+                    print_archived_log(task_logs, &self.style, *lines);
+                } else {
+                    print_logs(task_logs, &self.subcommand, &self.style, &self.settings)
+                }
+            }
+            Message::HistoryResponse(tasks) => {
+                let output = print_history(tasks, &self.subcommand, &self.style, &self.settings)?;
+                println!("{output}");
             }
             Message::GroupResponse(groups) => {
                 let group_text = format_groups(groups, &self.subcommand, &self.style);
                 println!("{group_text}");
             }
+            Message::ScheduleResponse(schedules) => {
+                let schedule_text =
+                    format_schedules(schedules, &self.subcommand, &self.style, &self.settings);
+                println!("{schedule_text}");
+            }
             Message::Stream(text) => {
                 print!("{text}");
                 io::stdout().flush().unwrap();
@@ -400,8 +581,21 @@ impl Client {
                 group,
                 delay_until,
                 dependencies,
+                after_failure,
+                after_any,
+                after_exit_code,
                 priority,
                 label,
+                retries,
+                retry_delay,
+                exponential_backoff,
+                timeout,
+                resources,
+                separate_output,
+                array,
+                array_file,
+                from_file,
+                from_stdin,
                 print_task_id,
             } => {
                 // Either take the user-specified path or default to the current working directory.
@@ -410,6 +604,43 @@ impl Client {
                     .map(|path| Ok(path.clone()))
                     .unwrap_or_else(current_dir)?;
 
+                // Only build a retry policy, if the user actually requested retries.
+                let retry_policy = retries.map(|max_retries| RetryPolicy {
+                    max_retries,
+                    delay: retry_delay.unwrap_or(0),
+                    backoff: if *exponential_backoff {
+                        RetryBackoff::Exponential
+                    } else {
+                        RetryBackoff::Fixed
+                    },
+                });
+
+                // Dependencies that don't have to succeed are sent along with their condition.
+                // Each dependency may only have a single condition.
+                let conditions =
+                    after_failure
+                        .iter()
+                        .map(|task_id| (*task_id, DependencyCondition::Failure))
+                        .chain(
+                            after_any
+                                .iter()
+                                .map(|task_id| (*task_id, DependencyCondition::Any)),
+                        )
+                        .chain(after_exit_code.iter().map(|(task_id, code)| {
+                            (*task_id, DependencyCondition::ExitCode(*code))
+                        }));
+                let mut dependency_conditions = BTreeMap::new();
+                for (task_id, condition) in conditions {
+                    if dependencies.contains(&task_id)
+                        || dependency_conditions.insert(task_id, condition).is_some()
+                    {
+                        bail!(
+                            "Task {task_id} is passed to more than one of --after, \
+                            --after-failure, --after-any and --after-exit-code"
+                        );
+                    }
+                }
+
                 let mut command = command.clone();
                 // The user can request to escape any special shell characters in all parameter strings before
                 // we concatenated them to a single string.
@@ -420,7 +651,7 @@ impl Client {
                         .collect();
                 }
 
-                AddMessage {
+                let message = AddMessage {
                     command: command.join(" "),
                     path,
                     // Catch the current environment for later injection into the task's process.
@@ -433,8 +664,22 @@ impl Client {
                     priority: priority.to_owned(),
                     label: label.clone(),
                     print_task_id: *print_task_id,
+                    retry_policy,
+                    timeout: *timeout,
+                    resources: resources.iter().cloned().collect(),
+                    separate_output: *separate_output,
+                    dependency_conditions,
+                    array: array_parameters(array, array_file)?,
+                    array_member: None,
+                    batch_dependencies: Vec::new(),
+                };
+
+                // The options of the command serve as defaults for all tasks of a batch.
+                if *from_stdin || from_file.is_some() {
+                    return Ok(read_batch(from_file.as_deref(), message)?.into());
                 }
-                .into()
+
+                message.into()
             }
             SubCommand::Remove { task_ids } => {
                 if self.settings.client.show_confirmation_questions {
@@ -501,19 +746,126 @@ impl Client {
             }
             .into(),
             SubCommand::Group { cmd, .. } => match cmd {
-                Some(GroupCommand::Add { name, parallel }) => GroupMessage::Add {
+                Some(GroupCommand::Add {
+                    name,
+                    parallel,
+                    resources,
+                    priority,
+                }) => GroupMessage::Add {
                     name: name.to_owned(),
                     parallel_tasks: parallel.to_owned(),
+                    resources: resources.iter().cloned().collect(),
+                    priority: priority.unwrap_or_default(),
+                },
+                Some(GroupCommand::Resources { name, resources }) => GroupMessage::SetResources {
+                    name: name.to_owned(),
+                    resources: resources.iter().cloned().collect(),
+                },
+                Some(GroupCommand::Priority { name, priority }) => GroupMessage::SetPriority {
+                    name: name.to_owned(),
+                    priority: *priority,
                 },
                 Some(GroupCommand::Remove { name }) => GroupMessage::Remove(name.to_owned()),
                 None => GroupMessage::List,
             }
             .into(),
+            SubCommand::Schedule { cmd, .. } => match cmd {
+                Some(ScheduleCommand::Add {
+                    command,
+                    cron,
+                    every,
+                    working_directory,
+                    escape,
+                    group,
+                    label,
+                    skip_if_running,
+                    catch_up,
+                }) => {
+                    let trigger = match (cron, every) {
+                        (Some(cron), _) => ScheduleTrigger::Cron(cron.clone()),
+                        (None, Some(every)) => ScheduleTrigger::Interval(*every),
+                        (None, None) => bail!("Either --cron or --every has to be specified"),
+                    };
+                    // Validate the schedule locally, to get early feedback on typos.
+                    trigger.validate()?;
+
+                    let path = working_directory
+                        .as_ref()
+                        .map(|path| Ok(path.clone()))
+                        .unwrap_or_else(current_dir)?;
+
+                    let mut command = command.clone();
+                    if *escape {
+                        command = command
+                            .iter()
+                            .map(|parameter| {
+                                shell_escape::escape(Cow::from(parameter)).into_owned()
+                            })
+                            .collect();
+                    }
+
+                    ScheduleMessage::Add(AddScheduleMessage {
+                        trigger,
+                        command: command.join(" "),
+                        path,
+                        envs: HashMap::from_iter(vars()),
+                        group: group_or_default(group),
+                        label: label.clone(),
+                        skip_if_running: *skip_if_running,
+                        catch_up: *catch_up,
+                    })
+                }
+                Some(ScheduleCommand::Remove { schedule_id }) => {
+                    ScheduleMessage::Remove(*schedule_id)
+                }
+                Some(ScheduleCommand::Pause { schedule_id }) => {
+                    ScheduleMessage::Pause(*schedule_id)
+                }
+                Some(ScheduleCommand::Resume { schedule_id }) => {
+                    ScheduleMessage::Resume(*schedule_id)
+                }
+                None => ScheduleMessage::List,
+            }
+            .into(),
+            SubCommand::Workflow { cmd } => match cmd {
+                WorkflowCommand::Run { file, stashed } => {
+                    let workflow = Workflow::read(file)?;
+                    WorkflowMessage {
+                        workflow,
+                        path: current_dir()?,
+                        envs: HashMap::from_iter(vars()),
+                        stashed: *stashed,
+                    }
+                    .into()
+                }
+                WorkflowCommand::Status { .. } => Message::Status,
+            },
             SubCommand::Status { .. } => Message::Status,
+            SubCommand::History {
+                log,
+                lines,
+                before,
+                limit,
+                ..
+            } => match log {
+                Some(archive_id) => HistoryLogRequestMessage {
+                    archive_id: *archive_id,
+                    lines: *lines,
+                }
+                .into(),
+                None => HistoryRequestMessage {
+                    before: *before,
+                    limit: *limit,
+                }
+                .into(),
+            },
             SubCommand::Log {
                 task_ids,
                 lines,
                 full,
+                stdout,
+                stderr,
+                timestamps,
                 ..
             } => {
                 let lines = determine_log_line_amount(*full, lines);
@@ -522,12 +874,22 @@ impl Client {
                     task_ids: task_ids.clone(),
                     send_logs: !self.settings.client.read_local_logs,
                     lines,
+                    stream: selected_log_stream(*stdout, *stderr),
+                    timestamps: *timestamps,
                 };
                 Message::Log(message)
             }
-            SubCommand::Follow { task_id, lines } => StreamRequestMessage {
+            SubCommand::Follow {
+                task_id,
+                lines,
+                stdout,
+                stderr,
+                timestamps,
+            } => StreamRequestMessage {
                 task_id: *task_id,
                 lines: *lines,
+                stream: selected_log_stream(*stdout, *stderr),
+                timestamps: *timestamps,
             }
             .into(),
             SubCommand::Clean {
@@ -549,12 +911,17 @@ impl Client {
             SubCommand::Parallel {
                 parallel_tasks,
                 group,
+                global,
             } => match parallel_tasks {
                 Some(parallel_tasks) => {
+                    if *parallel_tasks == 0 && !*global {
+                        bail!("You must provide a value that's bigger than 0");
+                    }
                     let group = group_or_default(group);
                     ParallelMessage {
                         parallel_tasks: *parallel_tasks,
                         group,
+                        global: *global,
                     }
                     .into()
                 }
@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+use std::fs::read_to_string;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde_derive::Deserialize;
+
+use pueue_lib::network::message::{AddBatchMessage, AddMessage};
+
+/// A single task of a batch file.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BatchEntry {
+    command: String,
+    /// The working directory. Relative paths are relative to the client's working directory.
+    cwd: Option<PathBuf>,
+    label: Option<String>,
+    group: Option<String>,
+    priority: Option<i32>,
+    /// Additional environment variables, which overwrite those of the client.
+    #[serde(default)]
+    env: HashMap<String, String>,
+    /// The ids of existing tasks this task depends on.
+    #[serde(default)]
+    after: Vec<usize>,
+    /// The positions of earlier entries of the batch this task depends on.
+    #[serde(default)]
+    after_entry: Vec<usize>,
+}
+
+/// Read the tasks of a batch from a file with one JSON object per line.
+/// If no path is given, the tasks are read from stdin.
+///
+/// Each entry is based on the given message, which contains the options that have been passed
+/// to `add`. The environment variables of that message are shared by all entries and are thereby
+/// only sent once.
+pub fn read_batch(path: Option<&Path>, mut base: AddMessage) -> Result<AddBatchMessage> {
+    let content = match path {
+        Some(path) => read_to_string(path)
+            .with_context(|| format!("Failed to read the batch file at {path:?}"))?,
+        None => io::read_to_string(io::stdin()).context("Failed to read the batch from stdin")?,
+    };
+
+    let envs = std::mem::take(&mut base.envs);
+    let mut messages = Vec::new();
+    for (line_number, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: BatchEntry = serde_json::from_str(line)
+            .with_context(|| format!("Invalid task in line {}", line_number + 1))?;
+
+        let mut message = base.clone();
+        message.command = entry.command;
+        if let Some(cwd) = entry.cwd {
+            message.path = base.path.join(cwd);
+        }
+        message.label = entry.label.or(message.label);
+        message.group = entry.group.unwrap_or(message.group);
+        message.priority = entry.priority.or(message.priority);
+        message.envs = entry.env;
+        message.dependencies.extend(entry.after);
+        message.batch_dependencies = entry.after_entry;
+        messages.push(message);
+    }
+
+    Ok(AddBatchMessage {
+        envs,
+        tasks: messages,
+    })
+}
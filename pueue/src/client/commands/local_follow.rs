@@ -2,6 +2,7 @@ use std::path::Path;
 
 use anyhow::{bail, Result};
 
+use pueue_lib::log::LogStream;
 use pueue_lib::network::protocol::GenericStream;
 
 use crate::client::commands::get_state;
@@ -19,6 +20,8 @@ pub async fn local_follow(
     pueue_directory: &Path,
     task_id: &Option<usize>,
     lines: Option<usize>,
+    log_stream: Option<LogStream>,
+    timestamps: bool,
 ) -> Result<()> {
     let task_id = match task_id {
         Some(task_id) => *task_id,
@@ -51,7 +54,15 @@ pub async fn local_follow(
         }
     };
 
-    follow_local_task_logs(stream, pueue_directory, task_id, lines).await?;
+    follow_local_task_logs(
+        stream,
+        pueue_directory,
+        task_id,
+        lines,
+        log_stream,
+        timestamps,
+    )
+    .await?;
 
     Ok(())
 }
@@ -10,12 +10,14 @@ use pueue_lib::network::protocol::*;
 use pueue_lib::state::State;
 use pueue_lib::{network::message::Message, task::Task};
 
+mod add_batch;
 mod edit;
 mod format_state;
 mod local_follow;
 mod restart;
 mod wait;
 
+pub use add_batch::read_batch;
 pub use edit::edit;
 pub use format_state::format_state;
 pub use local_follow::local_follow;
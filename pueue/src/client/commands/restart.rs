@@ -27,6 +27,7 @@ pub async fn restart(
     edit_command: bool,
     edit_path: bool,
     edit_label: bool,
+    timeout: Option<u64>,
 ) -> Result<()> {
     let new_status = if stashed {
         TaskStatus::Stashed { enqueue_at: None }
@@ -105,6 +106,7 @@ pub async fn restart(
                 path: edited_props.path,
                 label: edited_props.label,
                 delete_label: edited_props.delete_label,
+                timeout,
             });
 
             continue;
@@ -124,6 +126,14 @@ pub async fn restart(
             priority: Some(task.priority),
             label: edited_props.label.or_else(|| task.label.clone()),
             print_task_id: false,
+            retry_policy: task.retry_policy.clone(),
+            timeout: timeout.or(task.timeout),
+            resources: task.resources.clone(),
+            separate_output: task.separate_output,
+            dependency_conditions: Default::default(),
+            array: None,
+            array_member: task.array.clone(),
+            batch_dependencies: Vec::new(),
         };
 
         // Send the cloned task to the daemon and abort on any failure messages.
@@ -228,6 +228,10 @@ fn log_status_change(previous_status: TaskStatus, task: &Task, style: &OutputSty
                 let status = style.style_text("killed", Some(Color::Red), None);
                 format!("Task {task_id} has been {status}")
             }
+            TaskResult::TimedOut => {
+                let status = style.style_text("timed out", Some(Color::Red), None);
+                format!("Task {task_id} {status}")
+            }
         };
         println!("{current_time} - {text}");
 
@@ -0,0 +1,70 @@
+use std::collections::BTreeMap;
+
+use crossterm::style::{Attribute, Color};
+
+use pueue_lib::task::{Task, TaskResult, TaskStatus};
+
+use super::OutputStyle;
+
+/// The aggregated status of the tasks of a single task array.
+#[derive(Default)]
+struct ArraySummary {
+    total: usize,
+    succeeded: usize,
+    failed: usize,
+    running: usize,
+    waiting: usize,
+}
+
+/// Summarize the status of all task arrays, that have tasks in the given list.
+/// Returns `None`, if none of the tasks is part of an array.
+pub fn format_array_summaries<'a>(
+    tasks: impl Iterator<Item = &'a Task>,
+    style: &OutputStyle,
+) -> Option<String> {
+    let mut summaries: BTreeMap<usize, ArraySummary> = BTreeMap::new();
+    for task in tasks {
+        let Some(array) = &task.array else {
+            continue;
+        };
+
+        let summary = summaries.entry(array.id).or_default();
+        summary.total += 1;
+        match &task.status {
+            TaskStatus::Done(TaskResult::Success) => summary.succeeded += 1,
+            TaskStatus::Done(_) => summary.failed += 1,
+            TaskStatus::Running | TaskStatus::Paused => summary.running += 1,
+            TaskStatus::Queued | TaskStatus::Stashed { .. } | TaskStatus::Locked => {
+                summary.waiting += 1
+            }
+        }
+    }
+
+    if summaries.is_empty() {
+        return None;
+    }
+
+    let mut lines = vec![style.style_text("Task arrays", None, Some(Attribute::Bold))];
+    for (array_id, summary) in summaries {
+        let mut line = format!(
+            "Array {array_id}: {}/{} succeeded",
+            summary.succeeded, summary.total
+        );
+        if summary.failed > 0 {
+            let failed = format!("{} failed", summary.failed);
+            line.push_str(&format!(
+                ", {}",
+                style.style_text(failed, Some(Color::Red), None)
+            ));
+        }
+        if summary.running > 0 {
+            line.push_str(&format!(", {} running", summary.running));
+        }
+        if summary.waiting > 0 {
+            line.push_str(&format!(", {} waiting", summary.waiting));
+        }
+        lines.push(line);
+    }
+
+    Some(lines.join("\n"))
+}
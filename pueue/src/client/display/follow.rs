@@ -6,7 +6,7 @@ use anyhow::Result;
 use tokio::time::sleep;
 
 use pueue_lib::{
-    log::{get_log_file_handle, get_log_path, seek_to_last_lines},
+    log::{LogFile, LogFollower, LogStream},
     network::protocol::GenericStream,
 };
 
@@ -24,44 +24,37 @@ pub async fn follow_local_task_logs(
     pueue_directory: &Path,
     task_id: usize,
     lines: Option<usize>,
+    log_stream: Option<LogStream>,
+    timestamps: bool,
 ) -> Result<()> {
     // It might be that the task is not yet running.
     // Ensure that it exists and is started.
-    loop {
+    let files = loop {
         let Some(task) = get_task(stream, task_id).await? else {
             println!("Pueue: The task to be followed doesn't exist.");
             std::process::exit(1);
         };
         // Task started up, we can start to follow.
         if task.is_running() || task.is_done() {
-            break;
+            break LogFile::for_task(&task, log_stream);
         }
         sleep(Duration::from_millis(1000)).await;
-    }
-
-    let mut handle = match get_log_file_handle(task_id, pueue_directory) {
-        Ok(stdout) => stdout,
-        Err(err) => {
-            println!("Failed to get log file handles: {err}");
-            return Ok(());
-        }
     };
-    let path = get_log_path(task_id, pueue_directory);
-
-    // Stdout handle to directly stream log file output to `io::stdout`.
-    // This prevents us from allocating any large amounts of memory.
-    let mut stdout = io::stdout();
 
     // If `lines` is passed as an option, we only want to show the last `X` lines.
-    // To achieve this, we seek the file handle to the start of the `Xth` line
-    // from the end of the file.
-    // The loop following this section will then only copy those last lines to stdout.
-    if let Some(lines) = lines {
-        if let Err(err) = seek_to_last_lines(&mut handle, lines) {
-            println!("Error seeking to last lines from log: {err}");
-        }
+    let mut followers = Vec::new();
+    for file in files {
+        match LogFollower::new(task_id, pueue_directory, file, lines, timestamps) {
+            Ok(follower) => followers.push(follower),
+            Err(err) => {
+                println!("Failed to get log file handles: {err}");
+                return Ok(());
+            }
+        };
     }
 
+    let mut stdout = io::stdout();
+
     // The interval at which the task log is checked and streamed to stdout.
     let log_check_interval = 250;
 
@@ -71,16 +64,25 @@ pub async fn follow_local_task_logs(
     let task_check_interval = log_check_interval * 2;
     let mut last_check = 0;
     loop {
-        // Check whether the file still exists. Exit if it doesn't.
-        if !path.exists() {
-            println!("Pueue: Log file has gone away. Has the task been removed?");
-            return Ok(());
+        for follower in followers.iter_mut() {
+            // Check whether the file still exists. Exit if it doesn't.
+            if !follower.exists() {
+                println!("Pueue: Log file has gone away. Has the task been removed?");
+                return Ok(());
+            }
+            // Read the next chunk of text from the last position.
+            let output = match follower.read() {
+                Ok(output) => output,
+                Err(err) => {
+                    println!("Pueue: Error while reading file: {err}");
+                    return Ok(());
+                }
+            };
+            if let Err(err) = stdout.write_all(&output) {
+                println!("Pueue: Error while writing to stdout: {err}");
+                return Ok(());
+            };
         }
-        // Read the next chunk of text from the last position.
-        if let Err(err) = io::copy(&mut handle, &mut stdout) {
-            println!("Pueue: Error while reading file: {err}");
-            return Ok(());
-        };
         // Flush the stdout buffer to actually print the output.
         if let Err(err) = stdout.flush() {
             println!("Pueue: Error while flushing stdout: {err}");
@@ -98,7 +100,14 @@ pub async fn follow_local_task_logs(
                 std::process::exit(1);
             };
             // Task exited by itself. We can stop follwing.
+            // Print any remaining incomplete lines first.
             if !task.is_running() {
+                for follower in followers.iter_mut() {
+                    if let Ok(output) = follower.finish() {
+                        let _ = stdout.write_all(&output);
+                    }
+                }
+                let _ = stdout.flush();
                 return Ok(());
             }
         }
@@ -2,6 +2,7 @@ use crossterm::style::{Attribute, Color};
 
 use pueue_lib::{
     network::message::GroupResponseMessage,
+    resources::format_resources,
     state::{Group, GroupStatus},
 };
 
@@ -26,6 +27,12 @@ pub fn format_groups(
     }
 
     let mut text = String::new();
+    if let Some(max_parallel_tasks) = message.max_parallel_tasks {
+        text.push_str(&format!(
+            "Global limit: {max_parallel_tasks} parallel tasks across all groups\n"
+        ));
+    }
+
     let mut group_iter = message.groups.iter().peekable();
     while let Some((name, group)) = group_iter.next() {
         let styled = get_group_headline(name, group, style);
@@ -46,10 +53,22 @@ pub fn get_group_headline(name: &str, group: &Group, style: &OutputStyle) -> Str
     let name = style.style_text(format!("Group \"{name}\""), None, Some(Attribute::Bold));
 
     // Print the current state of the group.
-    let status = match group.status {
-        GroupStatus::Running => style.style_text("running", Some(Color::Green), None),
-        GroupStatus::Paused => style.style_text("paused", Some(Color::Yellow), None),
+    let status = match (group.status, &group.blocked) {
+        (GroupStatus::Running, None) => style.style_text("running", Some(Color::Green), None),
+        (GroupStatus::Running, Some(reason)) => {
+            style.style_text(format!("held back ({reason})"), Some(Color::Yellow), None)
+        }
+        (GroupStatus::Paused, _) => style.style_text("paused", Some(Color::Yellow), None),
     };
 
-    format!("{} ({} parallel): {}", name, group.parallel_tasks, status)
+    // Only show resources and the priority if the group actually sets them.
+    let mut limits = format!("{} parallel", group.parallel_tasks);
+    if !group.resources.is_empty() {
+        limits.push_str(&format!(", {}", format_resources(&group.resources)));
+    }
+    if group.priority != 0 {
+        limits.push_str(&format!(", priority {}", group.priority));
+    }
+
+    format!("{name} ({limits}): {status}")
 }
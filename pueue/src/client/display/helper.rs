@@ -1,8 +1,10 @@
 use std::collections::BTreeMap;
 
 use chrono::{DateTime, Local, LocalResult};
+use crossterm::style::Color;
 
-use pueue_lib::{settings::Settings, task::Task};
+use pueue_lib::settings::Settings;
+use pueue_lib::task::{Task, TaskResult, TaskStatus};
 
 /// Try to get the start of the current date to the best of our abilities.
 /// Throw an error, if we can't.
@@ -22,6 +24,24 @@ pub fn start_of_today() -> DateTime<Local> {
     }
 }
 
+/// Determine the human readable representation of a task's status and the respective color.
+pub fn status_text_and_color(status: &TaskStatus) -> (String, Color) {
+    let status_string = status.to_string();
+    match status {
+        TaskStatus::Running => (status_string, Color::Green),
+        TaskStatus::Paused | TaskStatus::Locked => (status_string, Color::White),
+        TaskStatus::Done(result) => match result {
+            TaskResult::Success => (TaskResult::Success.to_string(), Color::Green),
+            TaskResult::DependencyFailed => ("Dependency failed".to_string(), Color::Red),
+            TaskResult::FailedToSpawn(_) => ("Failed to spawn".to_string(), Color::Red),
+            TaskResult::Failed(code) => (format!("Failed ({code})"), Color::Red),
+            TaskResult::TimedOut => ("Timed out".to_string(), Color::Red),
+            _ => (result.to_string(), Color::Red),
+        },
+        _ => (status_string, Color::Yellow),
+    }
+}
+
 /// Sort given tasks by their groups.
 /// This is needed to print a table for each group.
 pub fn sort_tasks_by_group(tasks: Vec<Task>) -> BTreeMap<String, Vec<Task>> {
@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+
+use pueue_lib::archive::ArchivedTask;
+use pueue_lib::settings::Settings;
+use pueue_lib::task::Task;
+
+use super::{table_builder::TableBuilder, OutputStyle};
+use crate::client::cli::SubCommand;
+use crate::client::query::apply_query;
+
+/// Get the archived tasks in a nicely formatted table.
+/// This is used when calling `pueue history`.
+///
+/// The tasks are shown with their archive ids, as the ids of tasks may be reused.
+pub fn print_history(
+    archived: Vec<ArchivedTask>,
+    cli_command: &SubCommand,
+    style: &OutputStyle,
+    settings: &Settings,
+) -> Result<String> {
+    let SubCommand::History {
+        json, group, query, ..
+    } = cli_command
+    else {
+        panic!("Got wrong Subcommand {cli_command:?} in print_history. This shouldn't happen!")
+    };
+
+    let mut tasks: Vec<Task> = archived
+        .iter()
+        .filter(|archived| {
+            group
+                .as_ref()
+                .map_or(true, |group| &archived.task.group == group)
+        })
+        .map(|archived| {
+            let mut task = archived.task.clone();
+            task.id = archived.archive_id;
+            task
+        })
+        .collect();
+
+    let mut table_builder = TableBuilder::new(settings, style);
+    let query_result = apply_query(&query.join(" "))?;
+    table_builder.set_visibility_by_rules(&query_result.selected_columns);
+    tasks = query_result.apply_filters(tasks);
+    tasks = query_result.order_tasks(tasks);
+    tasks = query_result.limit_tasks(tasks);
+
+    // Print the matching archive entries in the resulting order.
+    if *json {
+        let by_id: HashMap<usize, &ArchivedTask> = archived
+            .iter()
+            .map(|entry| (entry.archive_id, entry))
+            .collect();
+        let archived: Vec<&ArchivedTask> = tasks
+            .iter()
+            .filter_map(|task| by_id.get(&task.id).copied())
+            .collect();
+        return Ok(serde_json::to_string(&archived).unwrap());
+    }
+
+    if tasks.is_empty() {
+        return Ok("There are no matching tasks in the archive".to_string());
+    }
+
+    Ok(table_builder.build(&tasks).to_string())
+}
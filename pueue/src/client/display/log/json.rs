@@ -1,10 +1,11 @@
 use std::collections::{BTreeMap, HashMap};
 use std::io::Read;
 
+use chrono::{DateTime, Local};
 use serde_derive::{Deserialize, Serialize};
 use snap::read::FrameDecoder;
 
-use pueue_lib::log::{get_log_file_handle, read_last_lines};
+use pueue_lib::log::{open_log_file, read_log_file_with_timestamps, LogFile, LogStream};
 use pueue_lib::network::message::TaskLogMessage;
 use pueue_lib::settings::Settings;
 use pueue_lib::task::Task;
@@ -14,63 +15,138 @@ use pueue_lib::task::Task;
 pub struct TaskLog {
     pub task: Task,
     pub output: String,
+    /// Only exists for tasks that write stderr to a separate file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stderr: Option<String>,
+    /// The lines of `output` with their timestamps.
+    /// Only exists, if timestamps have been requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_lines: Option<Vec<LogLine>>,
+    /// The lines of `stderr` with their timestamps.
+    /// Only exists, if timestamps have been requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stderr_lines: Option<Vec<LogLine>>,
+}
+
+/// A single line of a task's output and the time it has been written, if that's known.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LogLine {
+    pub timestamp: Option<DateTime<Local>>,
+    pub line: String,
+}
+
+/// Split some log output into its lines and attach their timestamps.
+fn log_lines(output: &str, timestamps: &[Option<DateTime<Local>>]) -> Vec<LogLine> {
+    output
+        .lines()
+        .enumerate()
+        .map(|(index, line)| LogLine {
+            timestamp: timestamps.get(index).copied().flatten(),
+            line: line.to_string(),
+        })
+        .collect()
 }
 
 pub fn print_log_json(
     task_log_messages: BTreeMap<usize, TaskLogMessage>,
     settings: &Settings,
     lines: Option<usize>,
+    stream: Option<LogStream>,
+    timestamps: bool,
 ) {
-    let mut tasks: BTreeMap<usize, Task> = BTreeMap::new();
-    let mut task_log: BTreeMap<usize, String> = BTreeMap::new();
+    let mut json = BTreeMap::new();
     // Convert the TaskLogMessage into a proper JSON serializable format.
     // Output in TaskLogMessages, if it exists, is compressed.
     // We need to decompress and convert to normal strings.
     for (id, message) in task_log_messages {
-        tasks.insert(id, message.task);
+        let mut task_log = TaskLog {
+            task: message.task,
+            output: String::new(),
+            stderr: None,
+            output_lines: None,
+            stderr_lines: None,
+        };
 
         if settings.client.read_local_logs {
-            let output = get_local_log(settings, id, lines);
-            task_log.insert(id, output);
-        } else {
-            let output = get_remote_log(message.output);
-            task_log.insert(id, output);
-        }
-    }
+            for file in LogFile::for_task(&task_log.task, stream) {
+                let (log, log_lines) = if timestamps {
+                    let (log, lines) = get_local_log_with_timestamps(settings, id, file, lines);
+                    (log, Some(lines))
+                } else {
+                    (get_local_log(settings, id, file, lines), None)
+                };
 
-    // Now assemble the final struct that will be returned
-    let mut json = BTreeMap::new();
-    for (id, mut task) in tasks {
-        let (id, output) = task_log.remove_entry(&id).unwrap();
+                if file == LogFile::Stderr {
+                    task_log.stderr = Some(log);
+                    task_log.stderr_lines = log_lines;
+                } else {
+                    task_log.output = log;
+                    task_log.output_lines = log_lines;
+                }
+            }
+        } else {
+            task_log.output = get_remote_log(message.output);
+            task_log.stderr = message.stderr.map(|bytes| get_remote_log(Some(bytes)));
+            task_log.output_lines = message
+                .output_timestamps
+                .map(|timestamps| log_lines(&task_log.output, &timestamps));
+            if let (Some(stderr), Some(timestamps)) = (&task_log.stderr, message.stderr_timestamps)
+            {
+                task_log.stderr_lines = Some(log_lines(stderr, &timestamps));
+            }
+        };
 
-        task.envs = HashMap::new();
-        json.insert(id, TaskLog { task, output });
+        task_log.task.envs = HashMap::new();
+        json.insert(id, task_log);
     }
 
     println!("{}", serde_json::to_string(&json).unwrap());
 }
 
 /// Read logs directly from local files for a specific task.
-fn get_local_log(settings: &Settings, id: usize, lines: Option<usize>) -> String {
-    let mut file = match get_log_file_handle(id, &settings.shared.pueue_directory()) {
-        Ok(file) => file,
+fn get_local_log(settings: &Settings, id: usize, file: LogFile, lines: Option<usize>) -> String {
+    // Only return the last few lines, if requested.
+    let (mut reader, _) = match open_log_file(id, &settings.shared.pueue_directory(), file, lines) {
+        Ok(result) => result,
         Err(err) => {
             return format!("(Pueue error) Failed to get log file handle: {err}");
         }
     };
 
-    // Only return the last few lines.
-    if let Some(lines) = lines {
-        return read_last_lines(&mut file, lines);
-    }
-
-    // Read the whole local log output.
-    let mut output = String::new();
-    if let Err(error) = file.read_to_string(&mut output) {
+    let mut output = Vec::new();
+    if let Err(error) = reader.read_to_end(&mut output) {
         return format!("(Pueue error) Failed to read local log output file: {error:?}");
     };
 
-    output
+    // The last line of the output is returned without its trailing newline.
+    let output = String::from_utf8_lossy(&output);
+    if lines.is_some() {
+        return output.strip_suffix('\n').unwrap_or(&output).to_string();
+    }
+
+    output.to_string()
+}
+
+/// Read logs directly from local files for a specific task, together with the timestamps of
+/// their lines.
+fn get_local_log_with_timestamps(
+    settings: &Settings,
+    id: usize,
+    file: LogFile,
+    lines: Option<usize>,
+) -> (String, Vec<LogLine>) {
+    let (output, _, timestamps) =
+        match read_log_file_with_timestamps(id, &settings.shared.pueue_directory(), file, lines) {
+            Ok(result) => result,
+            Err(err) => {
+                let error = format!("(Pueue error) Failed to read local log output file: {err}");
+                return (error, Vec::new());
+            }
+        };
+
+    let output = String::from_utf8_lossy(&output).to_string();
+    let lines = log_lines(&output, &timestamps);
+    (output, lines)
 }
 
 /// Read logs from from compressed remote logs.
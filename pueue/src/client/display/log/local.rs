@@ -1,72 +1,108 @@
-use std::fs::File;
-use std::io::{self, Stdout};
+use std::io::{self, BufRead, BufReader, Read, Stdout};
 
-use crossterm::style::{Attribute, Color};
-
-use pueue_lib::log::{get_log_file_handle, seek_to_last_lines};
+use pueue_lib::log::{
+    add_timestamps, open_log_file, read_log_file_with_timestamps, LogFile, LogStream,
+};
 use pueue_lib::settings::Settings;
+use pueue_lib::task::Task;
 
+use super::log_file_header;
 use crate::client::display::OutputStyle;
 
 /// The daemon didn't send any log output, thereby we didn't request any.
-/// If that's the case, read the log file from the local pueue directory.
+/// If that's the case, read the log files from the local pueue directory.
 pub fn print_local_log(
-    task_id: usize,
+    task: &Task,
     style: &OutputStyle,
     settings: &Settings,
     lines: Option<usize>,
+    stream: Option<LogStream>,
+    timestamps: bool,
 ) {
-    let mut file = match get_log_file_handle(task_id, &settings.shared.pueue_directory()) {
-        Ok(file) => file,
-        Err(err) => {
-            println!("Failed to get log file handle: {err}");
-            return;
-        }
-    };
     // Stdout handler to directly write log file output to io::stdout
     // without having to load anything into memory.
     let mut stdout = io::stdout();
 
-    print_local_file(
-        &mut stdout,
-        &mut file,
-        &lines,
-        style.style_text("output:", Some(Color::Green), Some(Attribute::Bold)),
-    );
-}
-
-/// Print a local log file of a task.
-fn print_local_file(stdout: &mut Stdout, file: &mut File, lines: &Option<usize>, header: String) {
-    if let Ok(metadata) = file.metadata() {
-        if metadata.len() != 0 {
-            // Indicates whether the full log output is shown or just the last part of it.
-            let mut output_complete = true;
-
-            // Only print the last lines if requested
-            if let Some(lines) = lines {
-                match seek_to_last_lines(file, *lines) {
-                    Ok(complete) => output_complete = complete,
+    for log_file in LogFile::for_task(task, stream) {
+        let pueue_directory = settings.shared.pueue_directory();
+        // The lines have to be read into memory, to put their timestamps in front of them.
+        if timestamps {
+            let (output, output_complete, line_timestamps) =
+                match read_log_file_with_timestamps(task.id, &pueue_directory, log_file, lines) {
+                    Ok(result) => result,
                     Err(err) => {
                         println!("Failed reading local log file: {err}");
                         return;
                     }
-                }
-            }
-
-            // Add a hint if we should limit the output to X lines **and** there are actually more
-            // lines than that given limit.
-            let mut line_info = String::new();
-            if !output_complete {
-                line_info = lines.map_or(String::new(), |lines| format!(" (last {lines} lines)"));
+                };
+            if output.is_empty() {
+                continue;
             }
 
-            // Print a newline between the task information and the first output.
-            println!("\n{header}{line_info}");
+            println!(
+                "\n{}{}",
+                log_file_header(log_file, style),
+                line_info(output_complete, &lines)
+            );
+            let output = String::from_utf8_lossy(&output);
+            print!("{}", add_timestamps(&output, &line_timestamps));
+            continue;
+        }
 
-            // Print everything
-            if let Err(err) = io::copy(file, stdout) {
-                println!("Failed reading local log file: {err}");
+        // Only print the last lines if requested.
+        let (reader, output_complete) =
+            match open_log_file(task.id, &pueue_directory, log_file, lines) {
+                Ok(result) => result,
+                Err(err) => {
+                    println!("Failed to get log file handle: {err}");
+                    return;
+                }
             };
+
+        print_local_file(
+            &mut stdout,
+            reader,
+            output_complete,
+            &lines,
+            log_file_header(log_file, style),
+        );
+    }
+}
+
+/// Print a local log file of a task, including its rotated segments.
+fn print_local_file(
+    stdout: &mut Stdout,
+    reader: Box<dyn Read>,
+    output_complete: bool,
+    lines: &Option<usize>,
+    header: String,
+) {
+    // Don't print anything for empty log files.
+    let mut reader = BufReader::new(reader);
+    match reader.fill_buf() {
+        Ok([]) => return,
+        Ok(_) => (),
+        Err(err) => {
+            println!("Failed reading local log file: {err}");
+            return;
         }
     }
+
+    // Print a newline between the task information and the first output.
+    println!("\n{header}{}", line_info(output_complete, lines));
+
+    // Print everything
+    if let Err(err) = io::copy(&mut reader, stdout) {
+        println!("Failed reading local log file: {err}");
+    };
+}
+
+/// Add a hint if we should limit the output to X lines **and** there are actually more
+/// lines than that given limit.
+fn line_info(output_complete: bool, lines: &Option<usize>) -> String {
+    if output_complete {
+        return String::new();
+    }
+
+    lines.map_or(String::new(), |lines| format!(" (last {lines} lines)"))
 }
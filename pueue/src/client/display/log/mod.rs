@@ -1,14 +1,16 @@
 use std::collections::BTreeMap;
 
 use comfy_table::{Attribute as ComfyAttribute, Cell, CellAlignment, Table};
-use crossterm::style::Color;
+use crossterm::style::{Attribute, Color};
 
+use pueue_lib::log::{LogFile, LogStream};
 use pueue_lib::network::message::TaskLogMessage;
 use pueue_lib::settings::Settings;
 use pueue_lib::task::{Task, TaskResult, TaskStatus};
 
 use super::OutputStyle;
 use crate::client::cli::SubCommand;
+use crate::client::client::selected_log_stream;
 
 mod json;
 mod local;
@@ -54,16 +56,20 @@ pub fn print_logs(
         task_ids,
         lines,
         full,
+        stdout,
+        stderr,
+        timestamps,
     } = cli_command
     else {
         panic!("Got wrong Subcommand {cli_command:?} in print_log. This shouldn't happen");
     };
 
     let lines = determine_log_line_amount(*full, lines);
+    let stream = selected_log_stream(*stdout, *stderr);
 
     // Return the server response in json representation.
     if *json {
-        print_log_json(task_logs, settings, lines);
+        print_log_json(task_logs, settings, lines, stream, *timestamps);
         return;
     }
 
@@ -81,7 +87,7 @@ pub fn print_logs(
     // Iterate over each task and print the respective log.
     let mut task_iter = task_logs.iter_mut().peekable();
     while let Some((_, task_log)) = task_iter.next() {
-        print_log(task_log, style, settings, lines);
+        print_log(task_log, style, settings, lines, stream, *timestamps);
 
         // Add a newline if there is another task that's going to be printed.
         if let Some((_, task_log)) = task_iter.peek() {
@@ -102,11 +108,17 @@ pub fn print_logs(
 /// lines: Whether we should reduce the log output of each task to a specific number of lines.
 ///         `None` implicates that everything should be printed.
 ///         This is only important, if we read local lines.
+/// stream: The selected output stream, if the task writes stdout and stderr separately.
+///         This is only important, if we read local lines.
+/// timestamps: Whether each line should be prefixed with its timestamp.
+///         This is only important, if we read local lines.
 fn print_log(
     message: &mut TaskLogMessage,
     style: &OutputStyle,
     settings: &Settings,
     lines: Option<usize>,
+    stream: Option<LogStream>,
+    timestamps: bool,
 ) {
     let task = &message.task;
     // We only show logs of finished or running tasks.
@@ -120,14 +132,27 @@ fn print_log(
     print_task_info(task, style);
 
     if settings.client.read_local_logs {
-        print_local_log(message.task.id, style, settings, lines);
-    } else if message.output.is_some() {
+        print_local_log(&message.task, style, settings, lines, stream, timestamps);
+    } else if message.output.is_some() || message.stderr.is_some() {
         print_remote_log(message, style, lines);
     } else {
         println!("Logs requested from pueue daemon, but none received. Please report this bug.");
     }
 }
 
+/// Print the output of an archived task, which has been requested via `pueue history --log`.
+/// The output is always sent by the daemon, as archived logs are only read by the daemon.
+pub fn print_archived_log(
+    task_logs: BTreeMap<usize, TaskLogMessage>,
+    style: &OutputStyle,
+    lines: Option<usize>,
+) {
+    for task_log in task_logs.values() {
+        print_task_info(&task_log.task, style);
+        print_remote_log(task_log, style, lines);
+    }
+}
+
 /// Print some information about a task, which is displayed on top of the task's log output.
 fn print_task_info(task: &Task, style: &OutputStyle) {
     // Print task id and exit code.
@@ -140,16 +165,7 @@ fn print_task_info(task: &Task, style: &OutputStyle) {
     let (exit_status, color) = match &task.status {
         TaskStatus::Paused => ("paused".into(), Color::White),
         TaskStatus::Running => ("running".into(), Color::Yellow),
-        TaskStatus::Done(result) => match result {
-            TaskResult::Success => ("completed successfully".into(), Color::Green),
-            TaskResult::Failed(exit_code) => {
-                (format!("failed with exit code {exit_code}"), Color::Red)
-            }
-            TaskResult::FailedToSpawn(err) => (format!("failed to spawn: {err}"), Color::Red),
-            TaskResult::Killed => ("killed by system or user".into(), Color::Red),
-            TaskResult::Errored => ("some IO error.\n Check daemon log.".into(), Color::Red),
-            TaskResult::DependencyFailed => ("dependency failed".into(), Color::Red),
-        },
+        TaskStatus::Done(result) => describe_result(result),
         _ => (task.status.to_string(), Color::White),
     };
     let status_cell = style.styled_cell(exit_status, Some(color), None);
@@ -201,6 +217,24 @@ fn print_task_info(task: &Task, style: &OutputStyle) {
         ]);
     }
 
+    // Previous runs, if the task has been automatically retried.
+    for (index, attempt) in task.attempts.iter().enumerate() {
+        let (result, color) = describe_result(&attempt.result);
+        let mut description = result.replace('\n', "");
+        if let (Some(start), Some(end)) = (attempt.start, attempt.end) {
+            description.push_str(&format!(" ({} - {})", start.to_rfc2822(), end.to_rfc2822()));
+        }
+
+        table.add_row(vec![
+            style.styled_cell(
+                format!("Attempt {}:", index + 1),
+                None,
+                Some(ComfyAttribute::Bold),
+            ),
+            style.styled_cell(description, Some(color), None),
+        ]);
+    }
+
     // Set the padding of the left column to 0 align the keys to the right
     let first_column = table.column_mut(0).unwrap();
     first_column.set_cell_alignment(CellAlignment::Right);
@@ -208,3 +242,25 @@ fn print_task_info(task: &Task, style: &OutputStyle) {
 
     println!("{table}");
 }
+
+/// Get a human readable description and color for the result of a finished task run.
+fn describe_result(result: &TaskResult) -> (String, Color) {
+    match result {
+        TaskResult::Success => ("completed successfully".into(), Color::Green),
+        TaskResult::Failed(exit_code) => (format!("failed with exit code {exit_code}"), Color::Red),
+        TaskResult::FailedToSpawn(err) => (format!("failed to spawn: {err}"), Color::Red),
+        TaskResult::Killed => ("killed by system or user".into(), Color::Red),
+        TaskResult::Errored => ("some IO error.\n Check daemon log.".into(), Color::Red),
+        TaskResult::DependencyFailed => ("dependency failed".into(), Color::Red),
+        TaskResult::TimedOut => ("timed out".into(), Color::Red),
+    }
+}
+
+/// Get the styled header, that's printed above the output of a task's log file.
+fn log_file_header(file: LogFile, style: &OutputStyle) -> String {
+    match file {
+        LogFile::Combined => style.style_text("output:", Some(Color::Green), Some(Attribute::Bold)),
+        LogFile::Stdout => style.style_text("stdout:", Some(Color::Green), Some(Attribute::Bold)),
+        LogFile::Stderr => style.style_text("stderr:", Some(Color::Red), Some(Attribute::Bold)),
+    }
+}
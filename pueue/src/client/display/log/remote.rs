@@ -1,33 +1,51 @@
-use std::io;
+use std::io::{self, Read, Write};
 
 use anyhow::Result;
-use crossterm::style::{Attribute, Color};
+use chrono::{DateTime, Local};
 use snap::read::FrameDecoder;
 
+use pueue_lib::log::{add_timestamps, LogFile};
 use pueue_lib::network::message::TaskLogMessage;
 
-use super::OutputStyle;
+use super::{log_file_header, OutputStyle};
 
 /// Prints log output received from the daemon.
-/// We can safely call .unwrap() on output in here, since this
-/// branch is always called after ensuring that it is `Some`.
+/// Tasks that write stdout and stderr to separate files may have both.
 pub fn print_remote_log(task_log: &TaskLogMessage, style: &OutputStyle, lines: Option<usize>) {
-    if let Some(bytes) = task_log.output.as_ref() {
-        if !bytes.is_empty() {
-            // Add a hint if we should limit the output to X lines **and** there are actually more
-            // lines than that given limit.
-            let mut line_info = String::new();
-            if !task_log.output_complete {
-                line_info = lines.map_or(String::new(), |lines| format!(" (last {lines} lines)"));
-            }
-
-            // Print a newline between the task information and the first output.
-            let header = style.style_text("output:", Some(Color::Green), Some(Attribute::Bold));
-            println!("\n{header}{line_info}");
-
-            if let Err(err) = decompress_and_print_remote_log(bytes) {
-                println!("Error while parsing stdout: {err}");
-            }
+    let output_file = if task_log.task.separate_output {
+        LogFile::Stdout
+    } else {
+        LogFile::Combined
+    };
+
+    for (file, output, timestamps) in [
+        (output_file, &task_log.output, &task_log.output_timestamps),
+        (
+            LogFile::Stderr,
+            &task_log.stderr,
+            &task_log.stderr_timestamps,
+        ),
+    ] {
+        let Some(bytes) = output.as_ref() else {
+            continue;
+        };
+        if bytes.is_empty() {
+            continue;
+        }
+
+        // Add a hint if we should limit the output to X lines **and** there are actually more
+        // lines than that given limit.
+        let mut line_info = String::new();
+        if !task_log.output_complete {
+            line_info = lines.map_or(String::new(), |lines| format!(" (last {lines} lines)"));
+        }
+
+        // Print a newline between the task information and the first output.
+        let header = log_file_header(file, style);
+        println!("\n{header}{line_info}");
+
+        if let Err(err) = decompress_and_print_remote_log(bytes, timestamps.as_deref()) {
+            println!("Error while parsing {}: {err}", file.extension());
         }
     }
 }
@@ -35,12 +53,25 @@ pub fn print_remote_log(task_log: &TaskLogMessage, style: &OutputStyle, lines: O
 /// We cannot easily stream log output from the client to the daemon (yet).
 /// Right now, the output is compressed in the daemon and sent as a single payload to the
 /// client. In here, we take that payload, decompress it and stream it it directly to stdout.
-fn decompress_and_print_remote_log(bytes: &[u8]) -> Result<()> {
+///
+/// If the daemon sent the timestamps of the lines, the output has to be decompressed into
+/// memory, to put the timestamps in front of the lines.
+fn decompress_and_print_remote_log(
+    bytes: &[u8],
+    timestamps: Option<&[Option<DateTime<Local>>]>,
+) -> Result<()> {
     let mut decompressor = FrameDecoder::new(bytes);
 
     let stdout = io::stdout();
     let mut write = stdout.lock();
-    io::copy(&mut decompressor, &mut write)?;
+    if let Some(timestamps) = timestamps {
+        let mut output = Vec::new();
+        decompressor.read_to_end(&mut output)?;
+        let output = String::from_utf8_lossy(&output);
+        write.write_all(add_timestamps(&output, timestamps).as_bytes())?;
+    } else {
+        io::copy(&mut decompressor, &mut write)?;
+    }
 
     Ok(())
 }
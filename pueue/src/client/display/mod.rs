@@ -2,22 +2,29 @@
 //! daemon.
 //!
 //! This includes formatting of task tables, group info, log inspection and log following.
+mod array;
 mod follow;
 mod group;
 pub mod helper;
+mod history;
 mod log;
+mod schedule;
 mod state;
 pub mod style;
 pub mod table_builder;
+mod workflow;
 
 use crossterm::style::Color;
 
 // Re-exports
 pub use self::follow::follow_local_task_logs;
 pub use self::group::format_groups;
-pub use self::log::{determine_log_line_amount, print_logs};
+pub use self::history::print_history;
+pub use self::log::{determine_log_line_amount, print_archived_log, print_logs};
+pub use self::schedule::format_schedules;
 pub use self::state::print_state;
 pub use self::style::OutputStyle;
+pub use self::workflow::format_workflows;
 
 /// Used to style any generic success message from the daemon.
 pub fn print_success(_style: &OutputStyle, message: &str) {
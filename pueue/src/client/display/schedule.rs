@@ -0,0 +1,80 @@
+use chrono::{DateTime, Local};
+use comfy_table::presets::UTF8_HORIZONTAL_ONLY;
+use comfy_table::{Cell, ContentArrangement, Table};
+use crossterm::style::Color;
+
+use pueue_lib::network::message::ScheduleResponseMessage;
+use pueue_lib::settings::Settings;
+
+use crate::client::cli::SubCommand;
+
+use super::OutputStyle;
+
+/// Print some info about the daemon's current schedules.
+/// This is used when calling `pueue schedule`.
+pub fn format_schedules(
+    message: ScheduleResponseMessage,
+    cli_command: &SubCommand,
+    style: &OutputStyle,
+    settings: &Settings,
+) -> String {
+    // Get commandline options to check whether we should return the schedules as json.
+    let SubCommand::Schedule { json, .. } = cli_command else {
+        panic!("Got wrong Subcommand {cli_command:?} in format_schedules. This shouldn't happen.")
+    };
+
+    if *json {
+        return serde_json::to_string(&message.schedules).unwrap();
+    }
+
+    if message.schedules.is_empty() {
+        return "There are no schedules".to_string();
+    }
+
+    let format_time = |time: Option<DateTime<Local>>| -> String {
+        time.map(|time| {
+            time.format(&settings.client.status_datetime_format)
+                .to_string()
+        })
+        .unwrap_or_default()
+    };
+
+    let mut table = Table::new();
+    table
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .load_preset(UTF8_HORIZONTAL_ONLY)
+        .set_header(vec![
+            Cell::new("Id"),
+            Cell::new("Status"),
+            Cell::new("Trigger"),
+            Cell::new("Group"),
+            Cell::new("Command"),
+            Cell::new("Last run"),
+            Cell::new("Next run"),
+        ]);
+
+    for (id, schedule) in message.schedules {
+        let status = if schedule.paused {
+            style.styled_cell("Paused", Some(Color::Yellow), None)
+        } else {
+            style.styled_cell("Active", Some(Color::Green), None)
+        };
+
+        table.add_row(vec![
+            Cell::new(id),
+            status,
+            Cell::new(schedule.trigger.to_string()),
+            Cell::new(&schedule.task.group),
+            Cell::new(&schedule.task.command),
+            Cell::new(format_time(schedule.last_run)),
+            Cell::new(format_time(schedule.next_run)),
+        ]);
+    }
+
+    // Explicitly force styling, in case we aren't on a tty, but `--color=always` is set.
+    if style.enabled {
+        table.enforce_styling();
+    }
+
+    table.to_string()
+}
@@ -4,7 +4,7 @@ use pueue_lib::settings::Settings;
 use pueue_lib::state::{State, PUEUE_DEFAULT_GROUP};
 use pueue_lib::task::Task;
 
-use super::{helper::*, table_builder::TableBuilder, OutputStyle};
+use super::{array::format_array_summaries, helper::*, table_builder::TableBuilder, OutputStyle};
 use crate::client::cli::SubCommand;
 use crate::client::display::group::get_group_headline;
 use crate::client::query::apply_query;
@@ -48,12 +48,27 @@ pub fn print_state(
         return Ok(output);
     }
 
+    // The `status` command additionally shows the aggregated status of task arrays.
+    let array_summaries = if query.is_some() {
+        let shown_tasks = tasks.iter().filter(|task| {
+            group_only
+                .as_ref()
+                .map_or(true, |group| task.group == *group)
+        });
+        format_array_summaries(shown_tasks, style)
+    } else {
+        None
+    };
+
     if let Some(group) = group_only {
         print_single_group(state, tasks, style, group, table_builder, &mut output);
-        return Ok(output);
+    } else {
+        print_all_groups(state, tasks, style, table_builder, &mut output);
     }
 
-    print_all_groups(state, tasks, style, table_builder, &mut output);
+    if let Some(array_summaries) = array_summaries {
+        output.push_str(&format!("\n\n{array_summaries}"));
+    }
 
     Ok(output)
 }
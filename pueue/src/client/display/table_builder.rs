@@ -1,12 +1,11 @@
 use chrono::Duration;
 use comfy_table::presets::UTF8_HORIZONTAL_ONLY;
 use comfy_table::{Cell, ContentArrangement, Row, Table};
-use crossterm::style::Color;
 
 use pueue_lib::settings::Settings;
-use pueue_lib::task::{Task, TaskResult, TaskStatus};
+use pueue_lib::task::{Task, TaskStatus};
 
-use super::helper::{formatted_start_end, start_of_today};
+use super::helper::{formatted_start_end, start_of_today, status_text_and_color};
 use super::OutputStyle;
 use crate::client::query::Rule;
 
@@ -205,22 +204,7 @@ impl<'a> TableBuilder<'a> {
             }
 
             if self.status {
-                // Determine the human readable task status representation and the respective color.
-                let status_string = task.status.to_string();
-                let (status_text, color) = match &task.status {
-                    TaskStatus::Running => (status_string, Color::Green),
-                    TaskStatus::Paused | TaskStatus::Locked => (status_string, Color::White),
-                    TaskStatus::Done(result) => match result {
-                        TaskResult::Success => (TaskResult::Success.to_string(), Color::Green),
-                        TaskResult::DependencyFailed => {
-                            ("Dependency failed".to_string(), Color::Red)
-                        }
-                        TaskResult::FailedToSpawn(_) => ("Failed to spawn".to_string(), Color::Red),
-                        TaskResult::Failed(code) => (format!("Failed ({code})"), Color::Red),
-                        _ => (result.to_string(), Color::Red),
-                    },
-                    _ => (status_string, Color::Yellow),
-                };
+                let (status_text, color) = status_text_and_color(&task.status);
                 row.add_cell(self.style.styled_cell(status_text, Some(color), None));
             }
 
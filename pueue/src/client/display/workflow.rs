@@ -0,0 +1,125 @@
+use std::collections::BTreeMap;
+
+use comfy_table::presets::UTF8_HORIZONTAL_ONLY;
+use comfy_table::{Cell, ContentArrangement, Table};
+use crossterm::style::Attribute;
+use serde_derive::Serialize;
+
+use pueue_lib::state::State;
+use pueue_lib::task::{Task, TaskResult, TaskStatus};
+
+use crate::client::cli::{SubCommand, WorkflowCommand};
+
+use super::helper::status_text_and_color;
+use super::OutputStyle;
+
+/// A single step of a workflow run, as it's printed with `--json`.
+#[derive(Serialize)]
+struct StepInfo<'a> {
+    step: &'a str,
+    task_id: usize,
+    status: &'a TaskStatus,
+    needs: Vec<&'a str>,
+}
+
+/// Print the progress of workflow runs.
+/// This is used when calling `pueue workflow status`.
+pub fn format_workflows(state: &State, cli_command: &SubCommand, style: &OutputStyle) -> String {
+    let SubCommand::Workflow {
+        cmd: WorkflowCommand::Status { run, json },
+    } = cli_command
+    else {
+        panic!("Got wrong Subcommand {cli_command:?} in format_workflows. This shouldn't happen.")
+    };
+
+    // Collect the tasks of all runs. Tasks are ordered by their id, and so are the steps.
+    let mut runs: BTreeMap<usize, Vec<&Task>> = BTreeMap::new();
+    for task in state.tasks.values() {
+        let Some(workflow) = &task.workflow else {
+            continue;
+        };
+        if run.map_or(false, |run| run != workflow.run) {
+            continue;
+        }
+        runs.entry(workflow.run).or_default().push(task);
+    }
+
+    // Map the dependencies of a step back to the names of the steps they belong to.
+    let step_name = |task_id: &usize| -> Option<&str> {
+        state
+            .tasks
+            .get(task_id)
+            .and_then(|task| task.workflow.as_ref())
+            .map(|workflow| workflow.step.as_str())
+    };
+
+    if *json {
+        let runs: BTreeMap<usize, Vec<StepInfo>> = runs
+            .iter()
+            .map(|(run, tasks)| {
+                let steps = tasks
+                    .iter()
+                    .map(|task| StepInfo {
+                        step: &task.workflow.as_ref().unwrap().step,
+                        task_id: task.id,
+                        status: &task.status,
+                        needs: task.dependencies.iter().filter_map(step_name).collect(),
+                    })
+                    .collect();
+                (*run, steps)
+            })
+            .collect();
+        return serde_json::to_string(&runs).unwrap();
+    }
+
+    if runs.is_empty() {
+        return match run {
+            Some(run) => format!("There's no workflow run {run}"),
+            None => "There are no workflow runs".to_string(),
+        };
+    }
+
+    let mut output = Vec::new();
+    for (run, tasks) in runs {
+        let name = &tasks[0].workflow.as_ref().unwrap().workflow;
+        let done = tasks
+            .iter()
+            .filter(|task| matches!(task.status, TaskStatus::Done(TaskResult::Success)))
+            .count();
+        let header = format!(
+            "Workflow '{name}' (run {run}): {done}/{} steps succeeded",
+            tasks.len()
+        );
+        output.push(style.style_text(header, None, Some(Attribute::Bold)));
+
+        let mut table = Table::new();
+        table
+            .set_content_arrangement(ContentArrangement::Dynamic)
+            .load_preset(UTF8_HORIZONTAL_ONLY)
+            .set_header(vec![
+                Cell::new("Step"),
+                Cell::new("Task"),
+                Cell::new("Status"),
+                Cell::new("Needs"),
+            ]);
+
+        for task in tasks {
+            let (status_text, color) = status_text_and_color(&task.status);
+            let needs: Vec<&str> = task.dependencies.iter().filter_map(step_name).collect();
+            table.add_row(vec![
+                Cell::new(&task.workflow.as_ref().unwrap().step),
+                Cell::new(task.id),
+                style.styled_cell(status_text, Some(color), None),
+                Cell::new(needs.join(", ")),
+            ]);
+        }
+
+        // Explicitly force styling, in case we aren't on a tty, but `--color=always` is set.
+        if style.enabled {
+            table.enforce_styling();
+        }
+        output.push(table.to_string());
+    }
+
+    output.join("\n\n")
+}
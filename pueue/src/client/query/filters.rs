@@ -253,6 +253,81 @@ pub fn label(section: Pair<'_, Rule>, query_result: &mut QueryResult) -> Result<
     Ok(())
 }
 
+/// Parse a filter for the command field.
+///
+/// This filter syntax looks like this:
+/// `command [=|!=|%=] string`
+///
+/// The data structure is the same as the one of the [label] filter.
+pub fn command(section: Pair<'_, Rule>, query_result: &mut QueryResult) -> Result<()> {
+    let mut filter = section.into_inner();
+    // The first word should be the `command` keyword.
+    let _command = filter.next().unwrap();
+
+    // Get the operator that should be applied in this filter.
+    // Can be either of [Rule::eq | Rule::neq | Rule::contains].
+    let operator = filter.next().unwrap().as_rule();
+
+    // Get the command we should filter for.
+    let operand = filter.next().unwrap().as_str().to_string();
+
+    // Build the command filter function.
+    let filter_function = Box::new(move |task: &Task| -> bool {
+        match operator {
+            Rule::eq => task.command == operand,
+            Rule::neq => task.command != operand,
+            Rule::contains => task.command.contains(&operand),
+            _ => false,
+        }
+    });
+    query_result.filters.push(filter_function);
+
+    Ok(())
+}
+
+/// Parse a filter for the exit code of finished tasks.
+///
+/// This filter syntax looks like this:
+/// `exit_code [=|!=] number`
+///
+/// Tasks that didn't exit with an exit code, e.g. because they're still running or have been
+/// killed, never match `=` and always match `!=`.
+pub fn exit_code(section: Pair<'_, Rule>, query_result: &mut QueryResult) -> Result<()> {
+    let mut filter = section.into_inner();
+    // The first word should be the `exit_code` keyword.
+    let _exit_code = filter.next().unwrap();
+
+    // Get the operator that should be applied in this filter.
+    // Can be either of [Rule::eq | Rule::neq].
+    let operator = filter.next().unwrap().as_rule();
+
+    // Get the exit code we should filter for.
+    let operand: i32 = filter
+        .next()
+        .unwrap()
+        .as_str()
+        .parse()
+        .context("Expected a valid exit code")?;
+
+    // Build the exit code filter function.
+    let filter_function = Box::new(move |task: &Task| -> bool {
+        let exit_code = match &task.status {
+            TaskStatus::Done(TaskResult::Success) => Some(0),
+            TaskStatus::Done(TaskResult::Failed(exit_code)) => Some(*exit_code),
+            _ => None,
+        };
+
+        match operator {
+            Rule::eq => exit_code == Some(operand),
+            Rule::neq => exit_code != Some(operand),
+            _ => false,
+        }
+    });
+    query_result.filters.push(filter_function);
+
+    Ok(())
+}
+
 /// Parse a filter for the status field.
 ///
 /// This filter syntax looks like this:
@@ -327,6 +402,9 @@ pub fn status(section: Pair<'_, Rule>, query_result: &mut QueryResult) -> Result
                 }
                 matches
             }
+            Rule::status_timed_out => {
+                matches!(&task.status, TaskStatus::Done(TaskResult::TimedOut))
+            }
             _ => return false,
         };
 
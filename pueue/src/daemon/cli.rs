@@ -24,4 +24,14 @@ pub struct CliArguments {
     /// The name of the profile that should be loaded from your config file.
     #[arg(short, long)]
     pub profile: Option<String>,
+
+    /// Write the stored state of the daemon as JSON to this file and exit.
+    /// This works with any state backend, even while the daemon is running.
+    #[arg(long, value_hint = ValueHint::FilePath, conflicts_with_all = ["daemonize", "import_state"])]
+    pub export_state: Option<PathBuf>,
+
+    /// Replace the stored state of the daemon with the state in this JSON file and exit.
+    /// The daemon mustn't be running while doing so.
+    #[arg(long, value_hint = ValueHint::FilePath, conflicts_with = "daemonize")]
+    pub import_state: Option<PathBuf>,
 }
@@ -0,0 +1,194 @@
+use tokio::sync::broadcast;
+
+use pueue_lib::event::Event;
+use pueue_lib::state::{Group, GroupStatus, State};
+use pueue_lib::task::{Task, TaskStatus};
+
+/// The amount of events that're buffered for each subscriber.
+/// Subscribers that fall behind further than this miss events.
+const EVENT_BUFFER_SIZE: usize = 1024;
+
+/// Publishes [Event]s to all subscribers.
+///
+/// Events are sent right where the daemon changes its state, while the state is still locked.
+/// Subscribers thereby receive every single change in the order in which it happened.
+#[derive(Clone)]
+pub struct EventSender {
+    sender: broadcast::Sender<Event>,
+}
+
+impl EventSender {
+    /// Subscribe to all events that're published from now on.
+    ///
+    /// The state should be locked while subscribing, so no change is missed between
+    /// reading the state and receiving the first event.
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.sender.subscribe()
+    }
+
+    /// Send an event to all subscribers.
+    /// The event is only created, if anybody is listening.
+    fn send(&self, event: impl FnOnce() -> Event) {
+        if self.sender.receiver_count() == 0 {
+            return;
+        }
+
+        // Sending only fails if all subscribers went away in the meantime.
+        let _ = self.sender.send(event());
+    }
+
+    /// A task has been added to the state.
+    pub fn task_added(&self, task: &Task) {
+        self.send(|| Event::TaskAdded(Box::new(task.clone())));
+    }
+
+    /// A task has been removed from the state.
+    pub fn task_removed(&self, task_id: usize) {
+        self.send(|| Event::TaskRemoved(task_id));
+    }
+
+    /// Change the status of a task and notify subscribers, if it actually changed.
+    pub fn set_task_status(&self, task: &mut Task, status: TaskStatus) {
+        let previous = std::mem::replace(&mut task.status, status);
+        if previous == task.status {
+            return;
+        }
+
+        self.send(|| Event::TaskStatusChanged {
+            task_id: task.id,
+            previous,
+            status: task.status.clone(),
+        });
+    }
+
+    /// Same as [EventSender::set_task_status], but for a task of the state.
+    /// Unknown tasks are ignored.
+    pub fn change_status(&self, state: &mut State, task_id: usize, status: TaskStatus) {
+        if let Some(task) = state.tasks.get_mut(&task_id) {
+            self.set_task_status(task, status);
+        }
+    }
+
+    /// A group has been created.
+    pub fn group_added(&self, name: &str, group: &Group) {
+        self.send(|| Event::GroupAdded {
+            group: name.to_string(),
+            parallel_tasks: group.parallel_tasks,
+        });
+    }
+
+    /// A group has been removed.
+    pub fn group_removed(&self, name: &str) {
+        self.send(|| Event::GroupRemoved(name.to_string()));
+    }
+
+    /// Change the status of a group and notify subscribers, if it actually changed.
+    pub fn set_group_status(&self, name: &str, group: &mut Group, status: GroupStatus) {
+        if group.status == status {
+            return;
+        }
+
+        group.status = status;
+        self.send(|| Event::GroupStatusChanged {
+            group: name.to_string(),
+            status,
+        });
+    }
+
+    /// Same as [EventSender::set_group_status], but for all groups of the state.
+    pub fn set_status_for_all_groups(&self, state: &mut State, status: GroupStatus) {
+        for (name, group) in state.groups.iter_mut() {
+            self.set_group_status(name, group, status);
+        }
+    }
+
+    /// Change the amount of parallel tasks of a group and notify subscribers, if it actually
+    /// changed.
+    pub fn set_parallel_tasks(&self, name: &str, group: &mut Group, parallel_tasks: usize) {
+        if group.parallel_tasks == parallel_tasks {
+            return;
+        }
+
+        group.parallel_tasks = parallel_tasks;
+        self.send(|| Event::ParallelTasksChanged {
+            group: name.to_string(),
+            parallel_tasks,
+        });
+    }
+}
+
+impl Default for EventSender {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_BUFFER_SIZE);
+        EventSender { sender }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use pretty_assertions::assert_eq;
+
+    use pueue_lib::task::TaskResult;
+
+    use super::*;
+
+    fn task() -> Task {
+        Task::new(
+            "ls".into(),
+            PathBuf::from("/tmp"),
+            Default::default(),
+            "default".into(),
+            TaskStatus::Queued,
+            Vec::new(),
+            0,
+            None,
+        )
+    }
+
+    /// Every single change is published, even if it's reverted right away.
+    #[test]
+    fn test_changes_are_published() {
+        let events = EventSender::default();
+        let mut receiver = events.subscribe();
+        let mut state = State::new();
+
+        let task_id = state.add_task(task());
+        let added = state.tasks[&task_id].clone();
+        events.task_added(&added);
+        events.change_status(&mut state, task_id, TaskStatus::Running);
+        events.change_status(&mut state, task_id, TaskStatus::Running);
+        events.change_status(&mut state, task_id, TaskStatus::Done(TaskResult::Success));
+        state.tasks.remove(&task_id);
+        events.task_removed(task_id);
+        events.set_status_for_all_groups(&mut state, GroupStatus::Paused);
+
+        let mut received = Vec::new();
+        while let Ok(event) = receiver.try_recv() {
+            received.push(event);
+        }
+
+        assert_eq!(
+            received,
+            vec![
+                Event::TaskAdded(Box::new(added)),
+                Event::TaskStatusChanged {
+                    task_id,
+                    previous: TaskStatus::Queued,
+                    status: TaskStatus::Running,
+                },
+                Event::TaskStatusChanged {
+                    task_id,
+                    previous: TaskStatus::Running,
+                    status: TaskStatus::Done(TaskResult::Success),
+                },
+                Event::TaskRemoved(task_id),
+                Event::GroupStatusChanged {
+                    group: "default".into(),
+                    status: GroupStatus::Paused,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,278 @@
+//! Metrics of the daemon in the Prometheus text format.
+//!
+//! Gauges, such as the amount of tasks per status, are derived from the state whenever the
+//! metrics are scraped.
+//! Everything else, such as finished tasks or failed callbacks, is counted in a global registry
+//! as it happens.
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use pueue_lib::state::State;
+use pueue_lib::task::{Task, TaskResult, TaskStatus};
+
+/// The buckets of the task duration histogram in seconds, ranging from a second to a day.
+const TASK_DURATION_BUCKETS: &[f64] = &[
+    1.0, 10.0, 60.0, 300.0, 900.0, 3600.0, 14400.0, 43200.0, 86400.0,
+];
+
+/// The buckets of the state save latency histogram in seconds.
+const STATE_SAVE_BUCKETS: &[f64] = &[0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0];
+
+static REGISTRY: Mutex<Registry> = Mutex::new(Registry {
+    task_durations: BTreeMap::new(),
+    finished_tasks: BTreeMap::new(),
+    callback_failures: 0,
+    state_saves: Histogram::new(STATE_SAVE_BUCKETS),
+});
+
+struct Registry {
+    /// The runtime of finished tasks by group.
+    task_durations: BTreeMap<String, Histogram>,
+    /// The amount of finished tasks by group and result.
+    finished_tasks: BTreeMap<(String, &'static str), u64>,
+    callback_failures: u64,
+    state_saves: Histogram,
+}
+
+struct Histogram {
+    buckets: &'static [f64],
+    /// The amount of observations per bucket. Each observation is only counted in the first
+    /// bucket it fits in, the cumulative counts are calculated once the metrics are rendered.
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    const fn new(buckets: &'static [f64]) -> Histogram {
+        Histogram {
+            buckets,
+            counts: Vec::new(),
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    fn observe(&mut self, value: f64) {
+        self.counts.resize(self.buckets.len(), 0);
+        if let Some(index) = self.buckets.iter().position(|bound| value <= *bound) {
+            self.counts[index] += 1;
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+
+    /// Write the samples of this histogram with the given labels.
+    fn render(&self, output: &mut String, name: &str, labels: &str) {
+        let separator = if labels.is_empty() { "" } else { "," };
+        let mut cumulative = 0;
+        for (index, bound) in self.buckets.iter().enumerate() {
+            cumulative += self.counts.get(index).copied().unwrap_or(0);
+            let _ = writeln!(
+                output,
+                "{name}_bucket{{{labels}{separator}le=\"{bound}\"}} {cumulative}"
+            );
+        }
+        let _ = writeln!(
+            output,
+            "{name}_bucket{{{labels}{separator}le=\"+Inf\"}} {}",
+            self.count
+        );
+        let labels = if labels.is_empty() {
+            String::new()
+        } else {
+            format!("{{{labels}}}")
+        };
+        let _ = writeln!(output, "{name}_sum{labels} {}", self.sum);
+        let _ = writeln!(output, "{name}_count{labels} {}", self.count);
+    }
+}
+
+/// Count a task that just finished.
+pub fn record_finished_task(task: &Task) {
+    let TaskStatus::Done(result) = &task.status else {
+        return;
+    };
+
+    let mut registry = REGISTRY.lock().unwrap();
+    *registry
+        .finished_tasks
+        .entry((task.group.clone(), result_name(result)))
+        .or_default() += 1;
+
+    // Tasks that never started don't have a duration.
+    if let (Some(start), Some(end)) = (task.start, task.end) {
+        let duration = end.signed_duration_since(start).num_milliseconds() as f64 / 1000.0;
+        registry
+            .task_durations
+            .entry(task.group.clone())
+            .or_insert_with(|| Histogram::new(TASK_DURATION_BUCKETS))
+            .observe(duration);
+    }
+}
+
+/// Count a callback that couldn't be spawned or that failed.
+pub fn record_callback_failure() {
+    REGISTRY.lock().unwrap().callback_failures += 1;
+}
+
+/// Record how long it took to save the state.
+pub fn record_state_save(duration: Duration) {
+    REGISTRY
+        .lock()
+        .unwrap()
+        .state_saves
+        .observe(duration.as_secs_f64());
+}
+
+/// Render all metrics in the Prometheus text format.
+pub fn render(state: &State) -> String {
+    // Writing to a `String` never fails, which is why all results are ignored.
+    let mut output = String::new();
+
+    // Count the tasks by group and status.
+    let mut tasks: BTreeMap<(&str, &str), u64> = BTreeMap::new();
+    for task in state.tasks.values() {
+        *tasks
+            .entry((&task.group, status_name(&task.status)))
+            .or_default() += 1;
+    }
+    write_header(
+        &mut output,
+        "pueue_tasks",
+        "gauge",
+        "The amount of tasks by group and status.",
+    );
+    for ((group, status), count) in &tasks {
+        let _ = writeln!(
+            output,
+            "pueue_tasks{{group=\"{}\",status=\"{status}\"}} {count}",
+            escape(group)
+        );
+    }
+
+    write_header(
+        &mut output,
+        "pueue_queue_depth",
+        "gauge",
+        "The amount of queued tasks, that wait to be started, by group.",
+    );
+    for group in state.groups.keys() {
+        let count = tasks.get(&(group.as_str(), "queued")).unwrap_or(&0);
+        let _ = writeln!(
+            output,
+            "pueue_queue_depth{{group=\"{}\"}} {count}",
+            escape(group)
+        );
+    }
+
+    write_header(
+        &mut output,
+        "pueue_running_tasks",
+        "gauge",
+        "The amount of task processes, including paused ones, by group.",
+    );
+    for group in state.groups.keys() {
+        let count = state
+            .tasks
+            .values()
+            .filter(|task| task.group == *group && task.is_running())
+            .count();
+        let _ = writeln!(
+            output,
+            "pueue_running_tasks{{group=\"{}\"}} {count}",
+            escape(group)
+        );
+    }
+
+    let registry = REGISTRY.lock().unwrap();
+    write_header(
+        &mut output,
+        "pueue_finished_tasks_total",
+        "counter",
+        "The amount of finished tasks by group and result.",
+    );
+    for ((group, result), count) in &registry.finished_tasks {
+        let _ = writeln!(
+            output,
+            "pueue_finished_tasks_total{{group=\"{}\",result=\"{result}\"}} {count}",
+            escape(group)
+        );
+    }
+
+    write_header(
+        &mut output,
+        "pueue_task_duration_seconds",
+        "histogram",
+        "The runtime of finished tasks by group.",
+    );
+    for (group, histogram) in &registry.task_durations {
+        histogram.render(
+            &mut output,
+            "pueue_task_duration_seconds",
+            &format!("group=\"{}\"", escape(group)),
+        );
+    }
+
+    write_header(
+        &mut output,
+        "pueue_callback_failures_total",
+        "counter",
+        "The amount of callbacks that couldn't be spawned or that failed.",
+    );
+    let _ = writeln!(
+        output,
+        "pueue_callback_failures_total {}",
+        registry.callback_failures
+    );
+
+    write_header(
+        &mut output,
+        "pueue_state_save_duration_seconds",
+        "histogram",
+        "The time it took to save the state.",
+    );
+    registry
+        .state_saves
+        .render(&mut output, "pueue_state_save_duration_seconds", "");
+
+    output
+}
+
+fn write_header(output: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(output, "# HELP {name} {help}");
+    let _ = writeln!(output, "# TYPE {name} {kind}");
+}
+
+/// Escape a label value, as group names may contain arbitrary characters.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn status_name(status: &TaskStatus) -> &'static str {
+    match status {
+        TaskStatus::Queued => "queued",
+        TaskStatus::Stashed { .. } => "stashed",
+        TaskStatus::Running => "running",
+        TaskStatus::Paused => "paused",
+        TaskStatus::Done(_) => "done",
+        TaskStatus::Locked => "locked",
+    }
+}
+
+fn result_name(result: &TaskResult) -> &'static str {
+    match result {
+        TaskResult::Success => "success",
+        TaskResult::Failed(_) => "failed",
+        TaskResult::FailedToSpawn(_) => "failed_to_spawn",
+        TaskResult::Killed => "killed",
+        TaskResult::Errored => "errored",
+        TaskResult::DependencyFailed => "dependency_failed",
+        TaskResult::TimedOut => "timed_out",
+    }
+}
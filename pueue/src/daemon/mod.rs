@@ -14,15 +14,24 @@ use pueue_lib::network::secret::init_shared_secret;
 use pueue_lib::settings::Settings;
 use pueue_lib::state::State;
 
+use self::events::EventSender;
 use self::state_helper::{restore_state, save_state};
+use self::state_store::{get_state_store, read_state_file, write_state_file};
+use crate::daemon::network::http::{accept_http, get_http_listener};
+use crate::daemon::network::metrics::{accept_metrics, get_metrics_listener};
+use crate::daemon::network::permissions::read_tokens;
 use crate::daemon::network::socket::accept_incoming;
-use crate::daemon::task_handler::{TaskHandler, TaskSender};
+use crate::daemon::task_handler::{check_reattach_shell, TaskHandler, TaskSender};
 
 pub mod cli;
+mod events;
+mod metrics;
 mod network;
 mod pid;
 /// Contains re-usable helper functions, that operate on the pueue-lib state.
 pub mod state_helper;
+/// The storage backends for the daemon's state.
+pub mod state_store;
 mod task_handler;
 
 /// The main entry point for the daemon logic.
@@ -51,6 +60,7 @@ pub async fn run(config_path: Option<PathBuf>, profile: Option<String>, test: bo
     if let Some(profile) = &profile {
         settings.load_profile(profile)?;
     }
+    check_reattach_shell(&settings)?;
 
     init_directories(&settings.shared.pueue_directory())?;
     if !settings.shared.daemon_key().exists() && !settings.shared.daemon_cert().exists() {
@@ -58,12 +68,16 @@ pub async fn run(config_path: Option<PathBuf>, profile: Option<String>, test: bo
     }
     init_shared_secret(&settings.shared.shared_secret_path())
         .context("Failed to initialize shared secret.")?;
+    let tokens = read_tokens(&settings)?;
     pid::create_pid_file(&settings.shared.pid_path()).context("Failed to create pid file.")?;
 
+    // The store is opened once and used for the whole lifetime of the daemon.
+    let store = get_state_store(&settings).context("Failed to open the state store.")?;
+
     // Restore the previous state and save any changes that might have happened during this
     // process. If no previous state exists, just create a new one.
     // Create a new empty state if any errors occur, but print the error message.
-    let state = match restore_state(&settings.shared.pueue_directory()) {
+    let mut state = match restore_state(&store, &settings) {
         Ok(Some(state)) => state,
         Ok(None) => State::new(),
         Err(error) => {
@@ -72,14 +86,23 @@ pub async fn run(config_path: Option<PathBuf>, profile: Option<String>, test: bo
             State::new()
         }
     };
+    state.max_parallel_tasks = settings.daemon.max_parallel_tasks;
 
     // Save the state once at the very beginning.
-    save_state(&state, &settings).context("Failed to save state on startup.")?;
+    save_state(&state, &store).context("Failed to save state on startup.")?;
     let state = Arc::new(Mutex::new(state));
 
     let (sender, receiver) = channel();
     let sender = TaskSender::new(sender);
-    let mut task_handler = TaskHandler::new(state.clone(), settings.clone(), receiver);
+    // Subscribed clients are notified about all changes of the state.
+    let events = EventSender::default();
+    let mut task_handler = TaskHandler::new(
+        state.clone(),
+        settings.clone(),
+        receiver,
+        events.clone(),
+        store.clone(),
+    );
 
     // Don't set ctrlc and panic handlers during testing.
     // This is necessary for multithreaded integration testing, since multiple listener per process
@@ -92,11 +115,96 @@ pub async fn run(config_path: Option<PathBuf>, profile: Option<String>, test: bo
         task_handler.run();
     });
 
-    accept_incoming(sender, state.clone(), settings.clone()).await?;
+    // Serve the HTTP API next to the default protocol, if it's enabled.
+    if let Some(address) = &settings.shared.http_api_address {
+        let listener = get_http_listener(address, &settings.shared).await?;
+        tokio::spawn(accept_http(
+            listener,
+            sender.clone(),
+            state.clone(),
+            settings.clone(),
+            tokens.clone(),
+            events.clone(),
+            store.clone(),
+        ));
+    }
+
+    // Serve the metrics, if they're enabled.
+    if let Some(address) = &settings.daemon.metrics_address {
+        let listener = get_metrics_listener(address).await?;
+        tokio::spawn(accept_metrics(listener, state.clone()));
+    }
+    #[cfg(not(target_os = "windows"))]
+    if let Some(path) = settings.daemon.metrics_socket_path() {
+        use crate::daemon::network::metrics::get_metrics_socket_listener;
+        let listener = get_metrics_socket_listener(&path)?;
+        tokio::spawn(accept_metrics(listener, state.clone()));
+    }
+
+    accept_incoming(
+        sender,
+        state.clone(),
+        settings.clone(),
+        tokens,
+        events,
+        store,
+    )
+    .await?;
 
     Ok(())
 }
 
+/// Write the stored state as JSON to the given file.
+///
+/// The state is exported as it has been stored, without any of the changes that are applied
+/// when it's restored by the daemon.
+pub fn export_state(
+    config_path: Option<PathBuf>,
+    profile: Option<String>,
+    path: &Path,
+) -> Result<()> {
+    let settings = read_settings(config_path, profile)?;
+    let Some(state) = get_state_store(&settings)?.load()? else {
+        bail!("There's no stored state to export.");
+    };
+
+    write_state_file(&state, path)?;
+    println!("Exported the state to {path:?}");
+    Ok(())
+}
+
+/// Replace the stored state with the state in the given JSON file.
+/// The current state is backed up beforehand.
+pub fn import_state(
+    config_path: Option<PathBuf>,
+    profile: Option<String>,
+    path: &Path,
+) -> Result<()> {
+    let settings = read_settings(config_path, profile)?;
+    pid::ensure_no_running_daemon(&settings.shared.pid_path())?;
+
+    let state = read_state_file(path)?;
+    let store = get_state_store(&settings)?;
+    if let Some(previous) = store.load()? {
+        store.backup(&previous, &settings.shared.pueue_directory())?;
+    }
+    store.save(&state)?;
+
+    println!("Imported the state from {path:?}");
+    Ok(())
+}
+
+/// Read the settings of the daemon, including the requested profile.
+fn read_settings(config_path: Option<PathBuf>, profile: Option<String>) -> Result<Settings> {
+    let (mut settings, _) =
+        Settings::read(&config_path).context("Error while reading configuration.")?;
+    if let Some(profile) = &profile {
+        settings.load_profile(profile)?;
+    }
+
+    Ok(settings)
+}
+
 /// Initialize all directories needed for normal operation.
 fn init_directories(pueue_dir: &Path) -> Result<()> {
     // Pueue base path
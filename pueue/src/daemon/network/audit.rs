@@ -0,0 +1,142 @@
+//! The audit log keeps track of all operations of clients that change the state.
+//!
+//! Each operation is appended as a single JSON line to the `audit.jsonl` file in the pueue
+//! directory, together with the identity of the client and the outcome of the operation.
+use std::fs::OpenOptions;
+use std::io::Write;
+
+use chrono::{DateTime, Local};
+use log::error;
+use serde_derive::Serialize;
+use serde_json::Value;
+
+use pueue_lib::network::message::*;
+use pueue_lib::network::peer::PeerIdentity;
+use pueue_lib::network::tokens::Principal;
+use pueue_lib::settings::Settings;
+
+/// Parameters whose values are replaced in the audit log, as they might contain secrets.
+/// These are the environment variables of new tasks and the input that's sent to tasks.
+const REDACTED_PARAMETERS: &[&str] = &["envs", "input"];
+
+/// An operation of a client, as it's written to the audit log.
+#[derive(Serialize)]
+pub struct AuditEntry {
+    time: DateTime<Local>,
+    client: PeerIdentity,
+    /// The name of the principal, if the client didn't authenticate via the shared secret.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    principal: Option<String>,
+    /// The kind of message, e.g. `Kill`.
+    operation: String,
+    /// The content of the message, with potential secrets being redacted.
+    parameters: Value,
+    /// Whether the daemon responded with a failure.
+    failed: bool,
+}
+
+impl AuditEntry {
+    /// Create an entry for a message of a client, before it's handled.
+    /// Returns `None`, if the audit log is disabled or the message doesn't change the state.
+    pub fn new(
+        message: &Message,
+        client: &PeerIdentity,
+        principal: Option<&Principal>,
+        settings: &Settings,
+    ) -> Option<Self> {
+        if !settings.daemon.audit_log || !is_audited(message) {
+            return None;
+        }
+
+        // Messages are serialized as `{"Variant": parameters}` or as `"Variant"`.
+        let (operation, mut parameters) = match serde_json::to_value(message) {
+            Ok(Value::Object(map)) => map.into_iter().next()?,
+            Ok(Value::String(operation)) => (operation, Value::Null),
+            Ok(_) => return None,
+            Err(err) => {
+                error!("Failed to serialize message for audit log: {err}");
+                return None;
+            }
+        };
+        redact(&mut parameters);
+
+        Some(AuditEntry {
+            time: Local::now(),
+            client: client.clone(),
+            principal: principal.map(|principal| principal.name.clone()),
+            operation,
+            parameters,
+            failed: false,
+        })
+    }
+
+    /// Append this entry, together with the outcome of the operation, to the audit log.
+    /// Failures are only logged, as they shouldn't affect the operation itself.
+    pub fn write(mut self, response: &Message, settings: &Settings) {
+        self.failed = matches!(response, Message::Failure(_));
+
+        let path = settings.shared.pueue_directory().join("audit.jsonl");
+        let result = serde_json::to_string(&self)
+            .map_err(|err| err.to_string())
+            .and_then(|line| {
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&path)
+                    // Write the entry at once, so concurrent entries don't get interleaved.
+                    .and_then(|mut file| file.write_all(format!("{line}\n").as_bytes()))
+                    .map_err(|err| err.to_string())
+            });
+
+        if let Err(err) = result {
+            error!("Failed to write to audit log at {path:?}: {err}");
+        }
+    }
+}
+
+/// Whether a message changes the state of the daemon.
+/// Messages that only read the state aren't audited.
+fn is_audited(message: &Message) -> bool {
+    match message {
+        Message::Group(GroupMessage::List) | Message::Schedule(ScheduleMessage::List) => false,
+        Message::Add(_)
+        | Message::AddBatch(_)
+        | Message::Remove(_)
+        | Message::Switch(_)
+        | Message::Stash(_)
+        | Message::Enqueue(_)
+        | Message::Start(_)
+        | Message::Restart(_)
+        | Message::Pause(_)
+        | Message::Kill(_)
+        | Message::Send(_)
+        | Message::EditRequest(_)
+        | Message::EditRestore(_)
+        | Message::Edit(_)
+        | Message::Group(_)
+        | Message::Schedule(_)
+        | Message::Reset(_)
+        | Message::Clean(_)
+        | Message::DaemonShutdown(_)
+        | Message::Parallel(_)
+        | Message::Workflow(_) => true,
+        _ => false,
+    }
+}
+
+/// Replace the values of all parameters that might contain secrets.
+fn redact(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if REDACTED_PARAMETERS.contains(&key.as_str()) {
+                    *value = Value::String("[redacted]".into());
+                } else {
+                    redact(value);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(redact),
+        _ => (),
+    }
+}
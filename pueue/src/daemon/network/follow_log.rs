@@ -1,4 +1,3 @@
-use std::io::Read;
 use std::path::Path;
 use std::time::Duration;
 
@@ -51,7 +50,7 @@ pub async fn handle_follow(
 
     // It might be that the task is not yet running.
     // Ensure that it exists and is started.
-    loop {
+    let files = loop {
         {
             let state = state.lock().unwrap();
             let Some(task) = state.tasks.get(&task_id) else {
@@ -61,49 +60,66 @@ pub async fn handle_follow(
             };
             // The task is running or finished, we can start to follow.
             if task.is_running() || task.is_done() {
-                break;
+                break LogFile::for_task(task, message.stream);
             }
         }
         tokio::time::sleep(Duration::from_millis(1000)).await;
-    }
-
-    let mut handle = match get_log_file_handle(task_id, pueue_directory) {
-        Err(_) => {
-            return Ok(create_failure_message(
-                "Couldn't find output files for task. Maybe it finished? Try `log`",
-            ))
-        }
-        Ok(handle) => handle,
     };
 
-    // Get the output path.
-    // We need to check continuously, whether the file still exists,
-    // since the file can go away (e.g. due to finishing a task).
-    let path = get_log_path(task_id, pueue_directory);
-
+    // Open all log files that should be followed.
     // If `lines` is passed as an option, we only want to show the last `X` lines.
-    // To achieve this, we seek the file handle to the start of the `Xth` line
-    // from the end of the file.
-    // The loop following this section will then only copy those last lines to stdout.
-    if let Some(lines) = message.lines {
-        if let Err(err) = seek_to_last_lines(&mut handle, lines) {
-            println!("Error seeking to last lines from log: {err}");
-        }
+    let mut followers = Vec::new();
+    for file in files {
+        match LogFollower::new(
+            task_id,
+            pueue_directory,
+            file,
+            message.lines,
+            message.timestamps,
+        ) {
+            Err(_) => {
+                return Ok(create_failure_message(
+                    "Couldn't find output files for task. Maybe it finished? Try `log`",
+                ))
+            }
+            Ok(follower) => followers.push(follower),
+        };
     }
 
     loop {
-        // Check whether the file still exists. Exit if it doesn't.
-        if !path.exists() {
+        // Check whether the files still exist. Exit if they don't.
+        if followers.iter().any(|follower| !follower.exists()) {
             return Ok(create_success_message(
                 "Pueue: Log file has gone away. Has the task been removed?",
             ));
         }
-        // Read the next chunk of text from the last position.
-        let mut buffer = Vec::new();
 
-        if let Err(err) = handle.read_to_end(&mut buffer) {
-            return Ok(create_failure_message(format!("Pueue Error: {err}")));
+        // Check if the task in question is still running.
+        // This is done before reading, so we don't miss any output that's written afterwards.
+        let running = {
+            let state = state.lock().unwrap();
+            let Some(task) = state.tasks.get(&task_id) else {
+                return Ok(create_failure_message(
+                    "Pueue: The followed task has been removed.",
+                ));
+            };
+            task.is_running()
         };
+
+        // Read the next chunk of text from the last position of each file.
+        let mut buffer = Vec::new();
+        for follower in followers.iter_mut() {
+            // Once the task finished, also read any incomplete last line.
+            let output = if running {
+                follower.read()
+            } else {
+                follower.finish()
+            };
+            match output {
+                Ok(output) => buffer.extend(output),
+                Err(err) => return Ok(create_failure_message(format!("Pueue Error: {err}"))),
+            }
+        }
         let text = String::from_utf8_lossy(&buffer).to_string();
 
         // Only send a message, if there's actual new content.
@@ -113,23 +129,9 @@ pub async fn handle_follow(
             send_message(response, stream).await?;
         }
 
-        // Check if the task in question does:
-        // 1. Still exist
-        // 2. Is still running
-        //
-        // In case it's not, close the stream.
-        {
-            let state = state.lock().unwrap();
-            let Some(task) = state.tasks.get(&task_id) else {
-                return Ok(create_failure_message(
-                    "Pueue: The followed task has been removed.",
-                ));
-            };
-
-            // The task is done, just close the stream.
-            if !task.is_running() {
-                return Ok(Message::Close);
-            }
+        // The task is done, just close the stream.
+        if !running {
+            return Ok(Message::Close);
         }
 
         // Wait for 1 second before sending the next chunk.
@@ -0,0 +1,507 @@
+//! A small HTTP/JSON API, which is served next to the default socket protocol.
+//!
+//! It allows scripts and web dashboards to talk to the daemon without having to implement
+//! Pueue's length-prefixed CBOR protocol.
+//! Just like the default protocol over TCP, it's only served via TLS with the daemon's
+//! certificate, as the bearer tokens must not be sent in cleartext.
+//! All requests are handled by [handle_message], which keeps the semantics identical to
+//! those of the default protocol.
+//!
+//! Endpoints:
+//! - `GET /api/status` Get the current state.
+//! - `POST /api/<operation>` Execute an operation. The body is the JSON representation of the
+//!   operation's message, e.g. an [AddMessage] for `POST /api/add`.
+//! - `POST /api/message` Send an arbitrary JSON serialized [Message].
+use std::io::Read;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use log::{debug, info, warn};
+use serde_json::Value;
+use snap::read::FrameDecoder;
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader,
+    Take,
+};
+use tokio::net::TcpListener;
+use tokio::time::{sleep, timeout};
+
+use pueue_lib::network::message::*;
+use pueue_lib::network::peer::PeerIdentity;
+use pueue_lib::network::secret::{read_shared_secret, secrets_match};
+use pueue_lib::network::tls::get_tls_listener;
+use pueue_lib::network::tokens::Tokens;
+use pueue_lib::settings::{Settings, Shared};
+use pueue_lib::state::SharedState;
+
+use crate::daemon::events::EventSender;
+use crate::daemon::network::audit::AuditEntry;
+use crate::daemon::network::message_handler::{handle_message, SENDER_ERR};
+use crate::daemon::network::permissions::check_permission;
+use crate::daemon::state_store::SharedStore;
+use crate::daemon::task_handler::TaskSender;
+
+/// The maximum size of a request body we're willing to accept.
+const MAX_BODY_SIZE: usize = 10 * 1024 * 1024;
+
+/// The maximum size of the request line and all headers of a request.
+const MAX_HEADER_SIZE: u64 = 16 * 1024;
+
+/// The time a client gets to finish the TLS handshake and to send its request.
+/// Otherwise, slow clients could keep their connection open forever.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Map the operations of the `POST /api/<operation>` endpoints to their [Message] variant.
+const OPERATIONS: [(&str, &str); 20] = [
+    ("add", "Add"),
+    ("add_batch", "AddBatch"),
+    ("remove", "Remove"),
+    ("switch", "Switch"),
+    ("stash", "Stash"),
+    ("enqueue", "Enqueue"),
+    ("start", "Start"),
+    ("restart", "Restart"),
+    ("pause", "Pause"),
+    ("kill", "Kill"),
+    ("send", "Send"),
+    ("group", "Group"),
+    ("status", "Status"),
+    ("log", "Log"),
+    ("parallel", "Parallel"),
+    ("reset", "Reset"),
+    ("clean", "Clean"),
+    ("schedule", "Schedule"),
+    ("shutdown", "DaemonShutdown"),
+    ("workflow", "Workflow"),
+];
+
+/// A parsed HTTP request.
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    token: Option<String>,
+    body: Vec<u8>,
+}
+
+/// A HTTP response.
+pub struct Response {
+    status: u16,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Response {
+    pub fn new(status: u16, content_type: &'static str, body: Vec<u8>) -> Response {
+        Response {
+            status,
+            content_type,
+            body,
+        }
+    }
+
+    fn json(status: u16, body: &Value) -> Response {
+        // Serializing a `Value` never fails.
+        let body = serde_json::to_vec(body).unwrap_or_default();
+        Response::new(status, "application/json", body)
+    }
+
+    fn error(status: u16, message: &str) -> Response {
+        Response::json(status, &serde_json::json!({ "Failure": message }))
+    }
+}
+
+/// Bind the HTTP API's listener.
+/// This is done before spawning [accept_http], so errors are reported on daemon startup.
+pub async fn get_http_listener(address: &str, settings: &Shared) -> Result<TcpListener> {
+    // An empty token would match requests with an empty bearer token.
+    if settings
+        .http_api_token
+        .as_ref()
+        .map_or(false, |token| token.is_empty())
+    {
+        bail!("The http_api_token must not be empty");
+    }
+
+    TcpListener::bind(address)
+        .await
+        .context(format!("Failed to listen for HTTP requests on {address}"))
+}
+
+/// Poll the listener of the HTTP API and handle each new connection in a separate task.
+#[allow(clippy::too_many_arguments)]
+pub async fn accept_http(
+    listener: TcpListener,
+    sender: TaskSender,
+    state: SharedState,
+    settings: Settings,
+    tokens: Tokens,
+    events: EventSender,
+    store: SharedStore,
+) -> Result<()> {
+    // Read secret once to prevent multiple disk reads.
+    let secret = read_shared_secret(&settings.shared.shared_secret_path())?;
+    let tls_acceptor = get_tls_listener(&settings.shared)?;
+
+    loop {
+        let (stream, address) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(err) => {
+                warn!("Failed connecting to HTTP client: {err:?}");
+                continue;
+            }
+        };
+
+        let tls_acceptor = tls_acceptor.clone();
+        let sender_clone = sender.clone();
+        let state_clone = state.clone();
+        let secret_clone = secret.clone();
+        let tokens_clone = tokens.clone();
+        let settings_clone = settings.clone();
+        let events_clone = events.clone();
+        let store_clone = store.clone();
+        tokio::spawn(async move {
+            let stream = match timeout(REQUEST_TIMEOUT, tls_acceptor.accept(stream)).await {
+                Ok(Ok(stream)) => stream,
+                Ok(Err(err)) => {
+                    debug!("Failed to initialize TLS for HTTP client: {err:?}");
+                    return;
+                }
+                Err(_) => {
+                    debug!("HTTP client didn't finish the TLS handshake in time");
+                    return;
+                }
+            };
+
+            let client = PeerIdentity {
+                address: Some(address.to_string()),
+                ..Default::default()
+            };
+            if let Err(err) = handle_http_connection(
+                stream,
+                client,
+                sender_clone,
+                state_clone,
+                settings_clone,
+                secret_clone,
+                tokens_clone,
+                events_clone,
+                store_clone,
+            )
+            .await
+            {
+                debug!("Failed to handle HTTP request: {err:?}");
+            }
+        });
+    }
+}
+
+/// Handle a single HTTP request.
+/// Connections aren't kept alive, each request gets its own connection.
+#[allow(clippy::too_many_arguments)]
+async fn handle_http_connection<S: AsyncRead + AsyncWrite + Unpin>(
+    mut stream: S,
+    client: PeerIdentity,
+    sender: TaskSender,
+    state: SharedState,
+    settings: Settings,
+    secret: Vec<u8>,
+    tokens: Tokens,
+    events: EventSender,
+    store: SharedStore,
+) -> Result<()> {
+    let Some(request) = receive_request(&mut stream, MAX_BODY_SIZE).await? else {
+        return Ok(());
+    };
+
+    // Check whether the client is allowed to talk to us.
+    // Principals of the token file may connect as well, but with restricted permissions.
+    let principal = request
+        .token
+        .as_ref()
+        .and_then(|token| tokens.principal(token.as_bytes()));
+    let authorized = principal.is_some()
+        || request.token.as_ref().map_or(false, |token| {
+            secrets_match(token.as_bytes(), &secret)
+                || settings
+                    .shared
+                    .http_api_token
+                    .as_ref()
+                    .map_or(false, |api_token| {
+                        secrets_match(token.as_bytes(), api_token.as_bytes())
+                    })
+        });
+    if !authorized {
+        warn!("Received HTTP request with invalid token");
+        // Wait for 1 second before responding, when getting an invalid token.
+        // This invalidates any timing attacks.
+        sleep(Duration::from_secs(1)).await;
+        let response = Response::error(401, "Missing or invalid bearer token");
+        return write_response(&mut stream, response).await;
+    }
+
+    let message = match parse_message(&request) {
+        Ok(message) => message,
+        Err(response) => return write_response(&mut stream, response).await,
+    };
+    info!("Received HTTP request: {} {}", request.method, request.path);
+
+    let response = match message {
+        Message::StreamRequest(_) | Message::Subscribe => {
+            Response::error(400, "Streaming isn't supported by the HTTP API")
+        }
+        // Respond first, as the daemon might be gone before we get the chance to do so.
+        Message::DaemonShutdown(shutdown_type) => {
+            let message = Message::DaemonShutdown(shutdown_type.clone());
+            let audit_entry = AuditEntry::new(&message, &client, principal.as_ref(), &settings);
+            let (response, allowed) = match check_permission(&message, principal.as_ref(), &state) {
+                Ok(()) => (create_success_message("Daemon is shutting down"), true),
+                Err(reason) => (create_failure_message(reason), false),
+            };
+            if let Some(audit_entry) = audit_entry {
+                audit_entry.write(&response, &settings);
+            }
+            write_response(&mut stream, message_to_response(response)).await?;
+            if allowed {
+                sender.send(shutdown_type).expect(SENDER_ERR);
+            }
+
+            return Ok(());
+        }
+        _ => message_to_response(handle_message(
+            message,
+            &client,
+            principal.as_ref(),
+            &sender,
+            &state,
+            &settings,
+            &events,
+            &store,
+        )),
+    };
+
+    write_response(&mut stream, response).await
+}
+
+/// Determine the [Message] that's represented by a request.
+fn parse_message(request: &Request) -> Result<Message, Response> {
+    let path = request.path.split('?').next().unwrap_or_default();
+    let Some(operation) = path.strip_prefix("/api/") else {
+        return Err(Response::error(404, "Not found"));
+    };
+
+    match (request.method.as_str(), operation) {
+        ("GET", "status") => Ok(Message::Status),
+        ("POST", "message") => serde_json::from_slice(&request.body)
+            .map_err(|err| Response::error(400, &format!("Failed to deserialize message: {err}"))),
+        ("POST", operation) => {
+            let Some((_, variant)) = OPERATIONS.iter().find(|(name, _)| *name == operation) else {
+                return Err(Response::error(404, "Not found"));
+            };
+
+            // Messages without payload are represented by their plain name.
+            let value = if request.body.iter().all(u8::is_ascii_whitespace) {
+                Value::String(variant.to_string())
+            } else {
+                let payload: Value = serde_json::from_slice(&request.body).map_err(|err| {
+                    Response::error(400, &format!("Failed to parse JSON body: {err}"))
+                })?;
+                let mut message = serde_json::Map::new();
+                message.insert(variant.to_string(), payload);
+                Value::Object(message)
+            };
+
+            serde_json::from_value(value)
+                .map_err(|err| Response::error(400, &format!("Invalid {operation} message: {err}")))
+        }
+        _ => Err(Response::error(405, "Method not allowed")),
+    }
+}
+
+/// Convert the daemon's response into a HTTP response.
+/// Failures are reported with a `400` status code.
+fn message_to_response(message: Message) -> Response {
+    let status = if matches!(message, Message::Failure(_)) {
+        400
+    } else {
+        200
+    };
+
+    let body = match message {
+        // Log output is sent compressed to our own clients.
+        // Other clients get the plain text instead.
+        Message::LogResponse(logs) => {
+            let logs: serde_json::Map<String, Value> = logs
+                .into_iter()
+                .map(|(task_id, log)| {
+                    let mut value = serde_json::json!({
+                        "task": log.task,
+                        "output_complete": log.output_complete,
+                    });
+                    value["output"] = decompress_output(log.output).into();
+                    value["stderr"] = decompress_output(log.stderr).into();
+
+                    (task_id.to_string(), value)
+                })
+                .collect();
+            serde_json::json!({ "LogResponse": logs })
+        }
+        message => serde_json::to_value(message).unwrap_or_else(
+            |err| serde_json::json!({ "Failure": format!("Failed to serialize response: {err}") }),
+        ),
+    };
+
+    Response::json(status, &body)
+}
+
+/// Decompress the [snap] compressed log output of a task.
+fn decompress_output(output: Option<Vec<u8>>) -> Option<String> {
+    let bytes = output?;
+    let mut decoder = FrameDecoder::new(&bytes[..]);
+    let mut output = String::new();
+    if let Err(error) = decoder.read_to_string(&mut output) {
+        return Some(format!(
+            "(Pueue error) Failed to decompress log output: {error:?}"
+        ));
+    }
+
+    Some(output)
+}
+
+/// Read a request from the stream, which has to be sent within [REQUEST_TIMEOUT].
+/// If the request is invalid, the client is told so and `None` is returned.
+pub async fn receive_request<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    max_body_size: usize,
+) -> Result<Option<Request>> {
+    let response = match timeout(REQUEST_TIMEOUT, read_request(stream, max_body_size)).await {
+        Ok(Ok(request)) => return Ok(Some(request)),
+        Ok(Err(err)) => Response::error(400, &format!("Invalid request: {err}")),
+        Err(_) => Response::error(408, "Timed out while reading the request"),
+    };
+    write_response(stream, response).await?;
+
+    Ok(None)
+}
+
+/// Read and parse a HTTP/1.x request from the stream.
+/// The request line and the headers may be at most [MAX_HEADER_SIZE] bytes large.
+async fn read_request<S: AsyncRead + Unpin>(
+    stream: &mut S,
+    max_body_size: usize,
+) -> Result<Request> {
+    let mut reader = BufReader::new(stream);
+    let mut head = (&mut reader).take(MAX_HEADER_SIZE);
+
+    let request_line = read_head_line(&mut head).await?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        bail!("Malformed request line");
+    };
+
+    // Parse all headers we're interested in.
+    let mut content_length = 0;
+    let mut token = None;
+    loop {
+        let line = read_head_line(&mut head).await?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+
+        let Some((name, value)) = line.split_once(':') else {
+            bail!("Malformed header");
+        };
+        let value = value.trim();
+        match name.trim().to_lowercase().as_str() {
+            "content-length" => content_length = value.parse().context("Invalid content length")?,
+            "authorization" => token = value.strip_prefix("Bearer ").map(str::to_string),
+            _ => (),
+        }
+    }
+
+    if content_length > max_body_size {
+        bail!("Request body is too large");
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).await?;
+
+    Ok(Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        token,
+        body,
+    })
+}
+
+/// Read a single line of the request line or headers.
+async fn read_head_line<R: AsyncBufRead + Unpin>(head: &mut Take<R>) -> Result<String> {
+    let mut line = String::new();
+    if head.read_line(&mut line).await? == 0 {
+        bail!("Connection closed while reading headers");
+    }
+    if !line.ends_with('\n') && head.limit() == 0 {
+        bail!("Request headers are too large");
+    }
+
+    Ok(line)
+}
+
+/// Write a response to the stream and close the connection.
+pub async fn write_response<S: AsyncWrite + Unpin>(
+    stream: &mut S,
+    response: Response,
+) -> Result<()> {
+    let reason = match response.status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        _ => "Internal Server Error",
+    };
+    let header = format!(
+        "HTTP/1.1 {} {reason}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        response.content_type,
+        response.body.len()
+    );
+
+    stream.write_all(header.as_bytes()).await?;
+    stream.write_all(&response.body).await?;
+    stream.shutdown().await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_request() -> Result<()> {
+        let mut stream: &[u8] =
+            b"POST /api/add HTTP/1.1\r\nAuthorization: Bearer token\r\nContent-Length: 2\r\n\r\n{}";
+        let request = read_request(&mut stream, MAX_BODY_SIZE).await?;
+
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/api/add");
+        assert_eq!(request.token.as_deref(), Some("token"));
+        assert_eq!(request.body, b"{}");
+
+        Ok(())
+    }
+
+    /// Clients cannot make the daemon buffer arbitrarily large headers.
+    #[tokio::test]
+    async fn test_read_request_with_large_headers() {
+        let request = format!(
+            "GET /api/status HTTP/1.1\r\nX-Large: {}\r\n\r\n",
+            "a".repeat(MAX_HEADER_SIZE as usize)
+        );
+        let mut stream = request.as_bytes();
+        let result = read_request(&mut stream, MAX_BODY_SIZE).await;
+
+        assert!(result.is_err());
+    }
+}
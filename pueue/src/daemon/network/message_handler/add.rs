@@ -1,50 +1,275 @@
+use std::collections::HashMap;
+
 use chrono::Local;
+use handlebars::Handlebars;
 use pueue_lib::aliasing::insert_alias;
 use pueue_lib::network::message::*;
+use pueue_lib::network::tokens::Principal;
+use pueue_lib::resources::{exceeded_capacity, format_resource_amount};
 use pueue_lib::state::{GroupStatus, SharedState};
-use pueue_lib::task::{Task, TaskStatus};
+use pueue_lib::task::{Task, TaskArrayRef, TaskStatus};
 
 use super::*;
-use crate::daemon::state_helper::save_state;
+use crate::daemon::events::EventSender;
+use crate::daemon::network::permissions::owns_task;
+use crate::daemon::state_helper::{save_state, LockedState};
+use crate::daemon::state_store::SharedStore;
 use crate::ok_or_return_failure_message;
 
 /// Invoked when calling `pueue add`.
-/// Queues a new task to the state.
+/// Queues a new task or a whole task array to the state.
 /// If the start_immediately flag is set, send a StartMessage to the task handler.
+/// The task is owned by the principal that added it, if there's any.
 pub fn add_task(
     message: AddMessage,
+    principal: Option<&Principal>,
     sender: &TaskSender,
     state: &SharedState,
     settings: &Settings,
+    events: &EventSender,
+    store: &SharedStore,
 ) -> Message {
+    if !message.batch_dependencies.is_empty() {
+        return create_failure_message(
+            "Tasks may only depend on entries of a batch, if they're added as part of it",
+        );
+    }
+
     let mut state = state.lock().unwrap();
     if let Err(message) = ensure_group_exists(&mut state, &message.group) {
         return message;
     }
 
+    // Create all tasks of an array before anything is added, so either all of them are added
+    // or none.
+    let tasks = match create_tasks(&message, principal, &state, settings) {
+        Ok(tasks) => tasks,
+        Err(text) => return create_failure_message(text),
+    };
+
+    // Check if the task's group is paused before we pass it to the state
+    let group_status = state
+        .groups
+        .get(&message.group)
+        .expect("We ensured that the group exists.")
+        .status;
+    let group_is_paused = matches!(group_status, GroupStatus::Paused);
+
+    // Add the tasks and persist the state.
+    let task_ids = insert_tasks(&mut state, tasks, message.array.is_some(), events);
+    ok_or_return_failure_message!(save_state(&state, store));
+
+    // Notify the task handler, in case the client wants to start the tasks immediately.
+    if message.start_immediately {
+        sender
+            .send(StartMessage {
+                tasks: TaskSelection::TaskIds(task_ids.clone()),
+            })
+            .expect(SENDER_ERR);
+    }
+
+    // Create the customized response for the client.
+    let first_id = task_ids[0];
+    let added = if message.array.is_some() {
+        let last_id = task_ids[task_ids.len() - 1];
+        format!("New task array added (id {first_id}) with the tasks {first_id}-{last_id}")
+    } else {
+        format!("New task added (id {first_id})")
+    };
+    let mut response = if message.print_task_id {
+        format_task_ids(&task_ids)
+    } else if let Some(enqueue_at) = message.enqueue_at {
+        let enqueue_at = enqueue_at.format("%Y-%m-%d %H:%M:%S");
+        format!("{added}. It will be enqueued at {enqueue_at}")
+    } else {
+        format!("{added}.")
+    };
+
+    // Notify the user if the task's group is paused
+    if !message.print_task_id && group_is_paused {
+        response.push_str("\nThe group of this task is currently paused!")
+    }
+
+    create_success_message(response)
+}
+
+/// Invoked when calling `pueue add --from-file` or `pueue add --from-stdin`.
+/// Queues the tasks of all messages at once. Either all of them are added or none.
+///
+/// Messages may depend on the tasks of earlier messages of the same batch via their
+/// `batch_dependencies`.
+pub fn add_batch(
+    batch: AddBatchMessage,
+    principal: Option<&Principal>,
+    sender: &TaskSender,
+    state: &SharedState,
+    settings: &Settings,
+    events: &EventSender,
+    store: &SharedStore,
+) -> Message {
+    let mut messages = batch.tasks;
+    if messages.is_empty() {
+        return create_failure_message("The batch doesn't contain any tasks");
+    }
+
+    // The environment variables of each message overwrite the shared ones.
+    for message in messages.iter_mut() {
+        let envs = std::mem::replace(&mut message.envs, batch.envs.clone());
+        message.envs.extend(envs);
+    }
+
+    let mut state = state.lock().unwrap();
+    for message in &messages {
+        if let Err(message) = ensure_group_exists(&mut state, &message.group) {
+            return message;
+        }
+    }
+
+    // Validate and create the tasks of all messages, before anything is added.
+    let mut batch = Vec::new();
+    for (index, message) in messages.iter().enumerate() {
+        if let Some(invalid) = message
+            .batch_dependencies
+            .iter()
+            .find(|dependency| **dependency >= index)
+        {
+            return create_failure_message(format!(
+                "Entry {index} of the batch depends on entry {invalid}. \
+                Entries may only depend on earlier entries."
+            ));
+        }
+
+        match create_tasks(message, principal, &state, settings) {
+            Ok(tasks) => batch.push(tasks),
+            Err(text) => return create_failure_message(format!("Entry {index}: {text}")),
+        }
+    }
+
+    // Add the tasks in order, so the ids of the tasks of earlier messages are known, once
+    // later messages depend on them.
+    let mut batch_task_ids: Vec<Vec<usize>> = Vec::new();
+    let mut started_task_ids = Vec::new();
+    for (message, mut tasks) in messages.iter().zip(batch) {
+        for task in tasks.iter_mut() {
+            for dependency in &message.batch_dependencies {
+                task.dependencies
+                    .extend_from_slice(&batch_task_ids[*dependency]);
+            }
+            task.dependencies.sort_unstable();
+            task.dependencies.dedup();
+        }
+
+        let task_ids = insert_tasks(&mut state, tasks, message.array.is_some(), events);
+        if message.start_immediately {
+            started_task_ids.extend_from_slice(&task_ids);
+        }
+        batch_task_ids.push(task_ids);
+    }
+    ok_or_return_failure_message!(save_state(&state, store));
+
+    // Notify the task handler, in case the client wants to start some tasks immediately.
+    if !started_task_ids.is_empty() {
+        sender
+            .send(StartMessage {
+                tasks: TaskSelection::TaskIds(started_task_ids),
+            })
+            .expect(SENDER_ERR);
+    }
+
+    let task_ids: Vec<usize> = batch_task_ids.into_iter().flatten().collect();
+    if messages.iter().any(|message| message.print_task_id) {
+        return create_success_message(format_task_ids(&task_ids));
+    }
+
+    let task_ids: Vec<String> = task_ids.iter().map(ToString::to_string).collect();
+    create_success_message(format!("New tasks added (ids {}).", task_ids.join(", ")))
+}
+
+/// Validate an [AddMessage] and create its tasks, without adding them to the state yet.
+/// A single task is created, unless the message describes a task array.
+///
+/// The message's group has to exist.
+fn create_tasks(
+    message: &AddMessage,
+    principal: Option<&Principal>,
+    state: &LockedState,
+    settings: &Settings,
+) -> Result<Vec<Task>, String> {
     // Ensure that specified dependencies actually exist.
     let not_found: Vec<_> = message
         .dependencies
         .iter()
+        .chain(message.dependency_conditions.keys())
         .filter(|id| !state.tasks.contains_key(id))
         .collect();
     if !not_found.is_empty() {
-        return create_failure_message(format!(
+        return Err(format!(
             "Unable to setup dependencies : task(s) {not_found:?} not found",
         ));
     }
 
-    // Create a new task and add it to the state.
+    // Each dependency may only have a single condition.
+    if let Some(task_id) = message
+        .dependencies
+        .iter()
+        .find(|task_id| message.dependency_conditions.contains_key(task_id))
+    {
+        return Err(format!(
+            "Task {task_id} can't be a dependency with and without a condition"
+        ));
+    }
+
+    // Restarted tasks may only keep the array membership of the task they've been restarted
+    // from, which is why that task has to exist and the client has to be allowed to manage it.
+    if let Some(member) = &message.array_member {
+        let exists = state
+            .tasks
+            .values()
+            .any(|task| task.array.as_ref() == Some(member) && owns_task(principal, task));
+        if !exists {
+            return Err(format!(
+                "There's no member {} of the task array {} to restart",
+                member.index, member.id
+            ));
+        }
+    }
+
+    // Reject tasks that need more resources than their group or the daemon provide at all,
+    // as they would never be started.
+    let group = state
+        .groups
+        .get(&message.group)
+        .expect("We ensured that the group exists.");
+    for capacity in [&group.resources, &settings.daemon.resources] {
+        if let Some((name, capacity)) = exceeded_capacity(&message.resources, capacity) {
+            return Err(format!(
+                "The task requests more \"{name}\" than available ({})",
+                format_resource_amount(capacity)
+            ));
+        }
+    }
+
+    // Create a new task.
     let mut task = Task::new(
-        message.command,
-        message.path,
-        message.envs,
-        message.group,
+        message.command.clone(),
+        message.path.clone(),
+        message.envs.clone(),
+        message.group.clone(),
         TaskStatus::Locked,
-        message.dependencies,
+        message.dependencies.clone(),
         message.priority.unwrap_or(0),
-        message.label,
+        message.label.clone(),
     );
+    task.retry_policy = message.retry_policy.clone();
+    task.timeout = message.timeout;
+    task.resources = message.resources.clone();
+    task.separate_output = message.separate_output || settings.daemon.separate_output;
+    task.owner = principal.map(|principal| principal.name.clone());
+
+    // Dependencies with a condition are dependencies as well.
+    task.dependencies
+        .extend(message.dependency_conditions.keys().copied());
+    task.dependency_conditions = message.dependency_conditions.clone();
 
     // Set the starting status.
     if message.stashed || message.enqueue_at.is_some() {
@@ -68,41 +293,95 @@ pub fn add_task(
     task.dependencies.sort_unstable();
     task.dependencies.dedup();
 
-    // Check if the task's group is paused before we pass it to the state
-    let group_status = state
-        .groups
-        .get(&task.group)
-        .expect("We ensured that the group exists.")
-        .status;
-    let group_is_paused = matches!(group_status, GroupStatus::Paused);
+    match &message.array {
+        Some(values) => render_task_array(&task, values, settings),
+        None => {
+            task.array = message.array_member.clone();
+            Ok(vec![task])
+        }
+    }
+}
 
-    // Add the task and persist the state.
-    let task_id = state.add_task(task);
-    ok_or_return_failure_message!(save_state(&state, settings));
+/// Add the given tasks to the state and return their ids.
+/// The tasks of a new task array get the id of their first task as array id.
+fn insert_tasks(
+    state: &mut LockedState,
+    tasks: Vec<Task>,
+    new_array: bool,
+    events: &EventSender,
+) -> Vec<usize> {
+    let mut task_ids: Vec<usize> = Vec::new();
+    for task in tasks {
+        let task_id = state.add_task(task);
+        let task = state
+            .tasks
+            .get_mut(&task_id)
+            .expect("Task has just been added");
+        // Arrays are identified by the id of their first task.
+        if new_array {
+            if let Some(array) = task.array.as_mut() {
+                array.id = task_ids.first().copied().unwrap_or(task_id);
+            }
+        }
+        events.task_added(task);
+        task_ids.push(task_id);
+    }
 
-    // Notify the task handler, in case the client wants to start the task immediately.
-    if message.start_immediately {
-        sender
-            .send(StartMessage {
-                tasks: TaskSelection::TaskIds(vec![task_id]),
-            })
-            .expect(SENDER_ERR);
+    task_ids
+}
+
+/// Task ids are printed space separated, so they can be passed to other commands, e.g. `--after`.
+fn format_task_ids(task_ids: &[usize]) -> String {
+    let task_ids: Vec<String> = task_ids.iter().map(ToString::to_string).collect();
+    task_ids.join(" ")
+}
+
+/// Create a task for each parameter of a task array.
+/// The command and the label of the given task are rendered with the parameter's `value` and
+/// `index`.
+fn render_task_array(
+    task: &Task,
+    values: &[String],
+    settings: &Settings,
+) -> Result<Vec<Task>, String> {
+    if values.is_empty() {
+        return Err("The task array doesn't have any parameters".into());
+    }
+    if values.len() > MAX_ARRAY_SIZE {
+        return Err(format!(
+            "The task array has {} parameters, but at most {MAX_ARRAY_SIZE} are allowed",
+            values.len()
+        ));
     }
 
-    // Create the customized response for the client.
-    let mut response = if message.print_task_id {
-        task_id.to_string()
-    } else if let Some(enqueue_at) = message.enqueue_at {
-        let enqueue_at = enqueue_at.format("%Y-%m-%d %H:%M:%S");
-        format!("New task added (id {task_id}). It will be enqueued at {enqueue_at}")
-    } else {
-        format!("New task added (id {task_id}).")
-    };
+    // Init Handlebars. We set to strict, as we want to show an error on missing variables.
+    // Parameters are inserted as they are, since commands aren't HTML.
+    let mut handlebars = Handlebars::new();
+    handlebars.set_strict_mode(true);
+    handlebars.register_escape_fn(handlebars::no_escape);
 
-    // Notify the user if the task's group is paused
-    if !message.print_task_id && group_is_paused {
-        response.push_str("\nThe group of this task is currently paused!")
+    let mut tasks = Vec::new();
+    for (index, value) in values.iter().enumerate() {
+        let mut parameters = HashMap::new();
+        parameters.insert("index", index.to_string());
+        parameters.insert("value", value.clone());
+        let render = |template: &str| {
+            handlebars
+                .render_template(template, &parameters)
+                .map_err(|err| format!("Failed to render the task array: {err}"))
+        };
+
+        let mut array_task = task.clone();
+        array_task.original_command = render(&task.original_command)?;
+        array_task.command = insert_alias(settings, array_task.original_command.clone());
+        array_task.label = task.label.as_deref().map(render).transpose()?;
+        array_task.array = Some(TaskArrayRef {
+            id: 0,
+            index,
+            value: value.clone(),
+        });
+        tasks.push(array_task);
     }
 
-    create_success_message(response)
+    Ok(tasks)
 }
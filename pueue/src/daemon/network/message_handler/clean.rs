@@ -4,7 +4,9 @@ use pueue_lib::state::SharedState;
 use pueue_lib::task::{TaskResult, TaskStatus};
 
 use super::*;
-use crate::daemon::state_helper::{is_task_removable, save_state};
+use crate::daemon::events::EventSender;
+use crate::daemon::state_helper::{archive_removed_tasks, is_task_removable, save_state};
+use crate::daemon::state_store::SharedStore;
 use crate::ok_or_return_failure_message;
 
 fn construct_success_clean_message(message: CleanMessage) -> String {
@@ -24,12 +26,19 @@ fn construct_success_clean_message(message: CleanMessage) -> String {
 
 /// Invoked when calling `pueue clean`.
 /// Remove all failed or done tasks from the state.
-pub fn clean(message: CleanMessage, state: &SharedState, settings: &Settings) -> Message {
+pub fn clean(
+    message: CleanMessage,
+    state: &SharedState,
+    settings: &Settings,
+    events: &EventSender,
+    store: &SharedStore,
+) -> Message {
     let mut state = state.lock().unwrap();
 
     let filtered_tasks =
         state.filter_tasks(|task| matches!(task.status, TaskStatus::Done(_)), None);
 
+    let mut removed = Vec::new();
     for task_id in &filtered_tasks.matching_ids {
         // Ensure the task is removable, i.e. there are no dependant tasks.
         if !is_task_removable(&state, task_id, &[]) {
@@ -52,11 +61,30 @@ pub fn clean(message: CleanMessage, state: &SharedState, settings: &Settings) ->
                 }
             }
         }
+        removed.push(*task_id);
+    }
+
+    // Archive the tasks first, so they're kept if anything goes wrong.
+    let archive = match archive_removed_tasks(&state, &removed, settings) {
+        Ok(archive) => archive,
+        Err(err) => return create_failure_message(format!("{err:?}")),
+    };
+
+    for task_id in &removed {
         let _ = state.tasks.remove(task_id).unwrap();
+        events.task_removed(*task_id);
         clean_log_handles(*task_id, &settings.shared.pueue_directory());
     }
 
-    ok_or_return_failure_message!(save_state(&state, settings));
+    ok_or_return_failure_message!(save_state(&state, store));
+    drop(state);
+
+    // Compressing the logs takes a while, which is why it's done without locking the state.
+    if let Some(archive) = archive {
+        if let Err(err) = archive.write() {
+            return create_failure_message(format!("Failed to archive removed tasks: {err}"));
+        }
+    }
 
     create_success_message(construct_success_clean_message(message))
 }
@@ -116,7 +144,13 @@ mod tests {
         let (state, settings, _tempdir) = get_stub_state();
 
         // Only task 1 will be removed, since it's the only TaskStatus with `Done`.
-        let message = clean(get_message(false, None), &state, &settings);
+        let message = clean(
+            get_message(false, None),
+            &state,
+            &settings,
+            &EventSender::default(),
+            &get_store(&settings),
+        );
 
         // Return message is correct
         assert!(matches!(message, Message::Success(_)));
@@ -133,7 +167,13 @@ mod tests {
         let (state, settings, _tempdir) = get_clean_test_state(&[PUEUE_DEFAULT_GROUP]);
 
         // All finished tasks should removed when calling default `clean`.
-        let message = clean(get_message(false, None), &state, &settings);
+        let message = clean(
+            get_message(false, None),
+            &state,
+            &settings,
+            &EventSender::default(),
+            &get_store(&settings),
+        );
 
         // Return message is correct
         assert!(matches!(message, Message::Success(_)));
@@ -151,7 +191,13 @@ mod tests {
 
         // Only successfully finished tasks should get removed when
         // calling `clean` with the `successful_only` flag.
-        let message = clean(get_message(true, None), &state, &settings);
+        let message = clean(
+            get_message(true, None),
+            &state,
+            &settings,
+            &EventSender::default(),
+            &get_store(&settings),
+        );
 
         // Return message is correct
         assert!(matches!(message, Message::Success(_)));
@@ -170,7 +216,13 @@ mod tests {
         let (state, settings, _tempdir) = get_clean_test_state(&[PUEUE_DEFAULT_GROUP, "other"]);
 
         // All finished tasks should removed in selected group (other)
-        let message = clean(get_message(false, Some("other".into())), &state, &settings);
+        let message = clean(
+            get_message(false, Some("other".into())),
+            &state,
+            &settings,
+            &EventSender::default(),
+            &get_store(&settings),
+        );
 
         // Return message is correct
         assert!(matches!(message, Message::Success(_)));
@@ -193,7 +245,13 @@ mod tests {
         let (state, settings, _tempdir) = get_clean_test_state(&[PUEUE_DEFAULT_GROUP, "other"]);
 
         // Only successfully finished tasks should removed in the 'other' group
-        let message = clean(get_message(true, Some("other".into())), &state, &settings);
+        let message = clean(
+            get_message(true, Some("other".into())),
+            &state,
+            &settings,
+            &EventSender::default(),
+            &get_store(&settings),
+        );
 
         // Return message is correct
         assert!(matches!(message, Message::Success(_)));
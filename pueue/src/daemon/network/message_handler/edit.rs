@@ -4,13 +4,15 @@ use pueue_lib::state::SharedState;
 use pueue_lib::task::TaskStatus;
 
 use super::*;
+use crate::daemon::events::EventSender;
 use crate::daemon::state_helper::save_state;
+use crate::daemon::state_store::SharedStore;
 use crate::ok_or_return_failure_message;
 
 /// Invoked when calling `pueue edit`.
 /// If a user wants to edit a message, we need to send him the current command.
 /// Lock the task to prevent execution, before the user has finished editing the command.
-pub fn edit_request(task_id: usize, state: &SharedState) -> Message {
+pub fn edit_request(task_id: usize, state: &SharedState, events: &EventSender) -> Message {
     // Check whether the task exists and is queued/stashed. Abort if that's not the case.
     let mut state = state.lock().unwrap();
     match state.tasks.get_mut(&task_id) {
@@ -19,7 +21,7 @@ pub fn edit_request(task_id: usize, state: &SharedState) -> Message {
                 return create_failure_message("You can only edit a queued/stashed task");
             }
             task.prev_status = task.status.clone();
-            task.status = TaskStatus::Locked;
+            events.set_task_status(task, TaskStatus::Locked);
 
             EditResponseMessage {
                 task_id: task.id,
@@ -35,7 +37,13 @@ pub fn edit_request(task_id: usize, state: &SharedState) -> Message {
 
 /// Invoked after closing the editor on `pueue edit`.
 /// Now we actually update the message with the updated command from the client.
-pub fn edit(message: EditMessage, state: &SharedState, settings: &Settings) -> Message {
+pub fn edit(
+    message: EditMessage,
+    state: &SharedState,
+    settings: &Settings,
+    events: &EventSender,
+    store: &SharedStore,
+) -> Message {
     // Check whether the task exists and is locked. Abort if that's not the case.
     let mut state = state.lock().unwrap();
     match state.tasks.get_mut(&message.task_id) {
@@ -45,7 +53,7 @@ pub fn edit(message: EditMessage, state: &SharedState, settings: &Settings) -> M
             }
 
             // Restore the task to its previous state.
-            task.status = task.prev_status.clone();
+            events.set_task_status(task, task.prev_status.clone());
 
             // Update command if applicable.
             if let Some(command) = message.command {
@@ -63,7 +71,7 @@ pub fn edit(message: EditMessage, state: &SharedState, settings: &Settings) -> M
                 task.label = None;
             }
 
-            ok_or_return_failure_message!(save_state(&state, settings));
+            ok_or_return_failure_message!(save_state(&state, store));
 
             create_success_message("Command has been updated")
         }
@@ -72,7 +80,7 @@ pub fn edit(message: EditMessage, state: &SharedState, settings: &Settings) -> M
 }
 
 /// Invoked if a client fails to edit a task and asks the daemon to restore the task's status.
-pub fn edit_restore(task_id: usize, state: &SharedState) -> Message {
+pub fn edit_restore(task_id: usize, state: &SharedState, events: &EventSender) -> Message {
     // Check whether the task exists and is queued/stashed. Abort if that's not the case.
     let mut state = state.lock().unwrap();
     match state.tasks.get_mut(&task_id) {
@@ -80,7 +88,7 @@ pub fn edit_restore(task_id: usize, state: &SharedState) -> Message {
             if task.status != TaskStatus::Locked {
                 return create_failure_message("The requested task isn't locked");
             }
-            task.status = task.prev_status.clone();
+            events.set_task_status(task, task.prev_status.clone());
 
             create_success_message(format!(
                 "The requested task's status has been restored to '{}'",
@@ -3,11 +3,12 @@ use pueue_lib::network::message::*;
 use pueue_lib::state::SharedState;
 use pueue_lib::task::TaskStatus;
 
+use crate::daemon::events::EventSender;
 use crate::daemon::network::response_helper::*;
 
 /// Invoked when calling `pueue enqueue`.
 /// Enqueue specific stashed tasks.
-pub fn enqueue(message: EnqueueMessage, state: &SharedState) -> Message {
+pub fn enqueue(message: EnqueueMessage, state: &SharedState, events: &EventSender) -> Message {
     let mut state = state.lock().unwrap();
     let filtered_tasks = state.filter_tasks(
         |task| matches!(task.status, TaskStatus::Stashed { .. } | TaskStatus::Locked),
@@ -21,11 +22,14 @@ pub fn enqueue(message: EnqueueMessage, state: &SharedState) -> Message {
         // Either specify the point of time the task should be enqueued or enqueue the task
         // immediately.
         if message.enqueue_at.is_some() {
-            task.status = TaskStatus::Stashed {
-                enqueue_at: message.enqueue_at,
-            };
+            events.set_task_status(
+                task,
+                TaskStatus::Stashed {
+                    enqueue_at: message.enqueue_at,
+                },
+            );
         } else {
-            task.status = TaskStatus::Queued;
+            events.set_task_status(task, TaskStatus::Queued);
             task.enqueued_at = Some(Local::now());
         }
     }
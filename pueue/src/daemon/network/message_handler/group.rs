@@ -10,6 +10,8 @@ use crate::ok_or_return_failure_message;
 /// Manage groups.
 /// - Show groups
 /// - Add group
+/// - Set the resources of a group
+/// - Set the priority of a group
 /// - Remove group
 pub fn group(message: GroupMessage, sender: &TaskSender, state: &SharedState) -> Message {
     let mut state = state.lock().unwrap();
@@ -19,12 +21,15 @@ pub fn group(message: GroupMessage, sender: &TaskSender, state: &SharedState) ->
             // Return information about all groups to the client.
             GroupResponseMessage {
                 groups: state.groups.clone(),
+                max_parallel_tasks: state.max_parallel_tasks,
             }
             .into()
         }
         GroupMessage::Add {
             name,
             parallel_tasks,
+            resources,
+            priority,
         } => {
             if state.groups.contains_key(&name) {
                 return create_failure_message(format!("Group \"{name}\" already exists"));
@@ -35,11 +40,31 @@ pub fn group(message: GroupMessage, sender: &TaskSender, state: &SharedState) ->
             let result = sender.send(GroupMessage::Add {
                 name: name.clone(),
                 parallel_tasks,
+                resources,
+                priority,
             });
             ok_or_return_failure_message!(result);
 
             create_success_message(format!("Group \"{name}\" is being created"))
         }
+        GroupMessage::SetResources { name, resources } => {
+            let group = match ensure_group_exists(&mut state, &name) {
+                Ok(group) => group,
+                Err(message) => return message,
+            };
+            group.resources = resources;
+
+            create_success_message(format!("Resources of group \"{name}\" adjusted"))
+        }
+        GroupMessage::SetPriority { name, priority } => {
+            let group = match ensure_group_exists(&mut state, &name) {
+                Ok(group) => group,
+                Err(message) => return message,
+            };
+            group.priority = priority;
+
+            create_success_message(format!("Priority of group \"{name}\" adjusted"))
+        }
         GroupMessage::Remove(group) => {
             if let Err(message) = ensure_group_exists(&mut state, &group) {
                 return message;
@@ -0,0 +1,63 @@
+use std::collections::BTreeMap;
+
+use pueue_lib::archive::{find_archived_task, read_archive, read_archived_log};
+use pueue_lib::log::{compress_log_output, LogFile};
+use pueue_lib::network::message::*;
+use pueue_lib::settings::Settings;
+
+/// Invoked when calling `pueue history`.
+/// Return the requested page of tasks in the archive.
+pub fn get_history(message: HistoryRequestMessage, settings: &Settings) -> Message {
+    let pueue_directory = settings.shared.pueue_directory();
+    match read_archive(&pueue_directory, message.before, message.limit) {
+        Ok(tasks) => Message::HistoryResponse(tasks),
+        Err(err) => create_failure_message(format!("Failed to read archive: {err}")),
+    }
+}
+
+/// Invoked when calling `pueue history --log`.
+/// Return an archived task and its output.
+/// The id of the returned task is replaced by its archive id.
+pub fn get_history_log(message: HistoryLogRequestMessage, settings: &Settings) -> Message {
+    let pueue_directory = settings.shared.pueue_directory();
+    let archived = match find_archived_task(&pueue_directory, message.archive_id) {
+        Ok(archived) => archived,
+        Err(err) => return create_failure_message(format!("Failed to read archive: {err}")),
+    };
+    let Some(archived) = archived else {
+        return create_failure_message("There's no archived task with this id.");
+    };
+
+    let mut task = archived.task;
+    task.id = archived.archive_id;
+    let mut task_log = TaskLogMessage {
+        task,
+        output: None,
+        output_complete: true,
+        stderr: None,
+        output_timestamps: None,
+        stderr_timestamps: None,
+    };
+
+    for file in LogFile::for_task(&task_log.task, None) {
+        // Tasks may have been archived without any output.
+        let Ok((output, output_complete)) =
+            read_archived_log(&pueue_directory, message.archive_id, file, message.lines)
+        else {
+            continue;
+        };
+        let output = match compress_log_output(&output) {
+            Ok(output) => output,
+            Err(err) => return create_failure_message(format!("Failed to read log: {err}")),
+        };
+
+        task_log.output_complete &= output_complete;
+        if file == LogFile::Stderr {
+            task_log.stderr = Some(output);
+        } else {
+            task_log.output = Some(output);
+        }
+    }
+
+    Message::LogResponse(BTreeMap::from([(message.archive_id, task_log)]))
+}
@@ -1,13 +1,25 @@
 use std::collections::BTreeMap;
 
-use pueue_lib::log::read_and_compress_log_file;
+use pueue_lib::error::Error;
+use pueue_lib::log::{
+    compress_log_output, read_and_compress_log_file, read_log_file_with_timestamps, LineTimestamps,
+    LogFile,
+};
 use pueue_lib::network::message::*;
+use pueue_lib::network::tokens::Principal;
 use pueue_lib::settings::Settings;
 use pueue_lib::state::SharedState;
 
+use crate::daemon::network::permissions::redact_envs;
+
 /// Invoked when calling `pueue log`.
 /// Return tasks and their output to the client.
-pub fn get_log(message: LogRequestMessage, state: &SharedState, settings: &Settings) -> Message {
+pub fn get_log(
+    message: LogRequestMessage,
+    principal: Option<&Principal>,
+    state: &SharedState,
+    settings: &Settings,
+) -> Message {
     let state = { state.lock().unwrap().clone() };
     // Return all logs, if no specific task id is specified.
     let task_ids = if message.task_ids.is_empty() {
@@ -22,31 +34,70 @@ pub fn get_log(message: LogRequestMessage, state: &SharedState, settings: &Setti
             // We send log output and the task at the same time.
             // This isn't as efficient as sending the raw compressed data directly,
             // but it's a lot more convenient for now.
-            let (output, output_complete) = if message.send_logs {
-                match read_and_compress_log_file(
-                    *task_id,
-                    &settings.shared.pueue_directory(),
-                    message.lines,
-                ) {
-                    Ok((output, output_complete)) => (Some(output), output_complete),
-                    Err(err) => {
-                        // Fail early if there's some problem with getting the log output
-                        return create_failure_message(format!(
-                            "Failed reading process output file: {err:?}"
-                        ));
+            let mut task_log = TaskLogMessage {
+                task: task.clone(),
+                output: None,
+                output_complete: true,
+                stderr: None,
+                output_timestamps: None,
+                stderr_timestamps: None,
+            };
+
+            if message.send_logs {
+                for file in LogFile::for_task(task, message.stream) {
+                    let (output, output_complete, timestamps) =
+                        match read_log(*task_id, settings, file, message.lines, message.timestamps)
+                        {
+                            Ok(result) => result,
+                            Err(err) => {
+                                // Fail early if there's some problem with getting the log output
+                                return create_failure_message(format!(
+                                    "Failed reading process output file: {err:?}"
+                                ));
+                            }
+                        };
+
+                    task_log.output_complete &= output_complete;
+                    // Stderr is sent separately, while stdout or the combined output is sent
+                    // as the task's main output.
+                    if file == LogFile::Stderr {
+                        task_log.stderr = Some(output);
+                        task_log.stderr_timestamps = timestamps;
+                    } else {
+                        task_log.output = Some(output);
+                        task_log.output_timestamps = timestamps;
                     }
                 }
-            } else {
-                (None, true)
-            };
+            }
 
-            let task_log = TaskLogMessage {
-                task: task.clone(),
-                output,
-                output_complete,
-            };
+            redact_envs(&mut task_log.task, principal);
             tasks.insert(*task_id, task_log);
         }
     }
     Message::LogResponse(tasks)
 }
+
+/// Read and compress a log file of a task.
+/// The timestamps of its lines are only read, if they've been requested.
+fn read_log(
+    task_id: usize,
+    settings: &Settings,
+    file: LogFile,
+    lines: Option<usize>,
+    timestamps: bool,
+) -> Result<(Vec<u8>, bool, Option<LineTimestamps>), Error> {
+    let pueue_directory = settings.shared.pueue_directory();
+    if !timestamps {
+        let (output, output_complete) =
+            read_and_compress_log_file(task_id, &pueue_directory, file, lines)?;
+        return Ok((output, output_complete, None));
+    }
+
+    let (output, output_complete, timestamps) =
+        read_log_file_with_timestamps(task_id, &pueue_directory, file, lines)?;
+    Ok((
+        compress_log_output(&output)?,
+        output_complete,
+        Some(timestamps),
+    ))
+}
@@ -1,58 +1,102 @@
 use std::fmt::Display;
 
 use pueue_lib::network::message::*;
+use pueue_lib::network::peer::PeerIdentity;
+use pueue_lib::network::tokens::Principal;
 use pueue_lib::settings::Settings;
 use pueue_lib::state::SharedState;
 
 use super::TaskSender;
+use crate::daemon::events::EventSender;
+use crate::daemon::network::audit::AuditEntry;
+use crate::daemon::network::permissions::{check_permission, redact_envs};
 use crate::daemon::network::response_helper::*;
+use crate::daemon::state_store::SharedStore;
 
 mod add;
 mod clean;
 mod edit;
 mod enqueue;
 mod group;
+mod history;
 mod kill;
 mod log;
 mod parallel;
 mod pause;
 mod remove;
 mod restart;
+mod schedule;
 mod send;
 mod start;
 mod stash;
 mod switch;
+mod workflow;
 
 pub static SENDER_ERR: &str = "Failed to send message to task handler thread";
 
+#[allow(clippy::too_many_arguments)]
 pub fn handle_message(
     message: Message,
+    client: &PeerIdentity,
+    principal: Option<&Principal>,
     sender: &TaskSender,
     state: &SharedState,
     settings: &Settings,
+    events: &EventSender,
+    store: &SharedStore,
 ) -> Message {
-    match message {
-        Message::Add(message) => add::add_task(message, sender, state, settings),
-        Message::Clean(message) => clean::clean(message, state, settings),
-        Message::Edit(message) => edit::edit(message, state, settings),
-        Message::EditRequest(task_id) => edit::edit_request(task_id, state),
-        Message::EditRestore(task_id) => edit::edit_restore(task_id, state),
-        Message::Enqueue(message) => enqueue::enqueue(message, state),
+    // Remember operations that change the state, so they can be audited once they're handled.
+    let audit_entry = AuditEntry::new(&message, client, principal, settings);
+
+    if let Err(reason) = check_permission(&message, principal, state) {
+        let response = create_failure_message(reason);
+        if let Some(audit_entry) = audit_entry {
+            audit_entry.write(&response, settings);
+        }
+        return response;
+    }
+
+    let response = match message {
+        Message::Add(message) => {
+            add::add_task(message, principal, sender, state, settings, events, store)
+        }
+        Message::AddBatch(message) => {
+            add::add_batch(message, principal, sender, state, settings, events, store)
+        }
+        Message::Clean(message) => clean::clean(message, state, settings, events, store),
+        Message::Edit(message) => edit::edit(message, state, settings, events, store),
+        Message::EditRequest(task_id) => edit::edit_request(task_id, state, events),
+        Message::EditRestore(task_id) => edit::edit_restore(task_id, state, events),
+        Message::Enqueue(message) => enqueue::enqueue(message, state, events),
         Message::Group(message) => group::group(message, sender, state),
+        Message::History(message) => history::get_history(message, settings),
+        Message::HistoryLog(message) => history::get_history_log(message, settings),
         Message::Kill(message) => kill::kill(message, sender, state),
-        Message::Log(message) => log::get_log(message, state, settings),
-        Message::Parallel(message) => parallel::set_parallel_tasks(message, state),
+        Message::Log(message) => log::get_log(message, principal, state, settings),
+        Message::Parallel(message) => parallel::set_parallel_tasks(message, state, events),
         Message::Pause(message) => pause::pause(message, sender, state),
-        Message::Remove(task_ids) => remove::remove(task_ids, state, settings),
+        Message::Remove(task_ids) => remove::remove(task_ids, state, settings, events, store),
         Message::Reset(message) => reset(message, sender),
-        Message::Restart(message) => restart::restart_multiple(message, sender, state, settings),
+        Message::Restart(message) => {
+            restart::restart_multiple(message, sender, state, settings, events)
+        }
+        Message::Schedule(message) => schedule::schedule(message, state, settings, store),
         Message::Send(message) => send::send(message, sender, state),
         Message::Start(message) => start::start(message, sender, state),
-        Message::Stash(task_ids) => stash::stash(task_ids, state),
-        Message::Switch(message) => switch::switch(message, state, settings),
-        Message::Status => get_status(state),
+        Message::Stash(task_ids) => stash::stash(task_ids, state, events),
+        Message::Switch(message) => switch::switch(message, state, events, store),
+        Message::Status => get_status(principal, state),
+        Message::Workflow(message) => {
+            workflow::run_workflow(message, principal, state, settings, events, store)
+        }
         _ => create_failure_message("Not yet implemented"),
+    };
+
+    if let Some(audit_entry) = audit_entry {
+        audit_entry.write(&response, settings);
     }
+
+    response
 }
 
 /// Invoked when calling `pueue reset`.
@@ -65,8 +109,11 @@ fn reset(message: ResetMessage, sender: &TaskSender) -> Message {
 
 /// Invoked when calling `pueue status`.
 /// Return the current state.
-fn get_status(state: &SharedState) -> Message {
-    let state = state.lock().unwrap().clone();
+fn get_status(principal: Option<&Principal>, state: &SharedState) -> Message {
+    let mut state = state.lock().unwrap().clone();
+    for task in state.tasks.values_mut() {
+        redact_envs(task, principal);
+    }
     Message::StatusResponse(Box::new(state))
 }
 
@@ -105,6 +152,7 @@ mod fixtures {
 
     pub use super::*;
     pub use crate::daemon::network::response_helper::*;
+    use crate::daemon::state_store::get_state_store;
 
     pub fn get_settings() -> (Settings, TempDir) {
         let tempdir = TempDir::new().expect("Failed to create test pueue directory");
@@ -114,6 +162,10 @@ mod fixtures {
         (settings, tempdir)
     }
 
+    pub fn get_store(settings: &Settings) -> SharedStore {
+        get_state_store(settings).expect("Failed to open test state store")
+    }
+
     pub fn get_state() -> (SharedState, Settings, TempDir) {
         let (settings, tempdir) = get_settings();
 
@@ -1,17 +1,35 @@
 use pueue_lib::network::message::*;
 use pueue_lib::state::SharedState;
 
+use crate::daemon::events::EventSender;
 use crate::daemon::network::response_helper::*;
 
-/// Set the parallel tasks for a specific group.
-pub fn set_parallel_tasks(message: ParallelMessage, state: &SharedState) -> Message {
+/// Set the parallel tasks for a specific group or the daemon-wide limit across all groups.
+pub fn set_parallel_tasks(
+    message: ParallelMessage,
+    state: &SharedState,
+    events: &EventSender,
+) -> Message {
     let mut state = state.lock().unwrap();
+    if message.global {
+        return match message.parallel_tasks {
+            0 => {
+                state.max_parallel_tasks = None;
+                create_success_message("Global limit of parallel tasks removed")
+            }
+            parallel_tasks => {
+                state.max_parallel_tasks = Some(parallel_tasks);
+                create_success_message("Global limit of parallel tasks adjusted")
+            }
+        };
+    }
+
     let group = match ensure_group_exists(&mut state, &message.group) {
         Ok(group) => group,
         Err(message) => return message,
     };
 
-    group.parallel_tasks = message.parallel_tasks;
+    events.set_parallel_tasks(&message.group, group, message.parallel_tasks);
 
     create_success_message(format!(
         "Parallel tasks setting for group \"{}\" adjusted",
@@ -5,14 +5,22 @@ use pueue_lib::state::SharedState;
 use pueue_lib::task::{Task, TaskStatus};
 
 use super::ok_or_failure_message;
+use crate::daemon::events::EventSender;
 use crate::daemon::network::response_helper::*;
-use crate::daemon::state_helper::{is_task_removable, save_state};
+use crate::daemon::state_helper::{archive_removed_tasks, is_task_removable, save_state};
+use crate::daemon::state_store::SharedStore;
 use crate::ok_or_return_failure_message;
 
 /// Invoked when calling `pueue remove`.
 /// Remove tasks from the queue.
 /// We have to ensure that those tasks aren't running!
-pub fn remove(task_ids: Vec<usize>, state: &SharedState, settings: &Settings) -> Message {
+pub fn remove(
+    task_ids: Vec<usize>,
+    state: &SharedState,
+    settings: &Settings,
+    events: &EventSender,
+    store: &SharedStore,
+) -> Message {
     let mut state = state.lock().unwrap();
 
     // Filter all running tasks, since we cannot remove them.
@@ -36,13 +44,28 @@ pub fn remove(task_ids: Vec<usize>, state: &SharedState, settings: &Settings) ->
         };
     }
 
+    // Archive the tasks first, so they're kept if anything goes wrong.
+    let archive = match archive_removed_tasks(&state, &filtered_tasks.matching_ids, settings) {
+        Ok(archive) => archive,
+        Err(err) => return create_failure_message(format!("{err:?}")),
+    };
+
     for task_id in &filtered_tasks.matching_ids {
         state.tasks.remove(task_id);
+        events.task_removed(*task_id);
 
         clean_log_handles(*task_id, &settings.shared.pueue_directory());
     }
 
-    ok_or_return_failure_message!(save_state(&state, settings));
+    ok_or_return_failure_message!(save_state(&state, store));
+    drop(state);
+
+    // Compressing the logs takes a while, which is why it's done without locking the state.
+    if let Some(archive) = archive {
+        if let Err(err) = archive.write() {
+            return create_failure_message(format!("Failed to archive removed tasks: {err}"));
+        }
+    }
 
     compile_task_response("Tasks removed from list", filtered_tasks)
 }
@@ -60,7 +83,13 @@ mod tests {
 
         // 3 and 4 aren't allowed to be removed, since they're running.
         // The rest will succeed.
-        let message = remove(vec![0, 1, 2, 3, 4], &state, &settings);
+        let message = remove(
+            vec![0, 1, 2, 3, 4],
+            &state,
+            &settings,
+            &EventSender::default(),
+            &get_store(&settings),
+        );
 
         // Return message is correct
         assert!(matches!(message, Message::Success(_)));
@@ -93,7 +122,13 @@ mod tests {
         }
 
         // Make sure we cannot remove a task with dependencies.
-        let message = remove(vec![1], &state, &settings);
+        let message = remove(
+            vec![1],
+            &state,
+            &settings,
+            &EventSender::default(),
+            &get_store(&settings),
+        );
 
         // Return message is correct
         assert!(matches!(message, Message::Failure(_)));
@@ -107,7 +142,13 @@ mod tests {
         }
 
         // Make sure we cannot remove a task with recursive dependencies.
-        let message = remove(vec![1, 5], &state, &settings);
+        let message = remove(
+            vec![1, 5],
+            &state,
+            &settings,
+            &EventSender::default(),
+            &get_store(&settings),
+        );
 
         // Return message is correct
         assert!(matches!(message, Message::Failure(_)));
@@ -121,7 +162,13 @@ mod tests {
         }
 
         // Make sure we can remove tasks with dependencies if all dependencies are specified.
-        let message = remove(vec![1, 5, 6], &state, &settings);
+        let message = remove(
+            vec![1, 5, 6],
+            &state,
+            &settings,
+            &EventSender::default(),
+            &get_store(&settings),
+        );
 
         // Return message is correct
         assert!(matches!(message, Message::Success(_)));
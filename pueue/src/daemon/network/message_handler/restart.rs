@@ -8,6 +8,7 @@ use pueue_lib::state::{SharedState, State};
 use pueue_lib::task::TaskStatus;
 
 use super::{task_action_response_helper, TaskSender, SENDER_ERR};
+use crate::daemon::events::EventSender;
 
 /// This is a small wrapper around the actual in-place task `restart` functionality.
 ///
@@ -18,6 +19,7 @@ pub fn restart_multiple(
     sender: &TaskSender,
     state: &SharedState,
     settings: &Settings,
+    events: &EventSender,
 ) -> Message {
     let task_ids: Vec<usize> = message.tasks.iter().map(|task| task.task_id).collect();
     let mut state = state.lock().unwrap();
@@ -33,7 +35,7 @@ pub fn restart_multiple(
 
     // Actually restart all tasks
     for task in message.tasks.into_iter() {
-        restart(&mut state, task, message.stashed, settings);
+        restart(&mut state, task, message.stashed, settings, events);
     }
 
     // Tell the task manager to start the task immediately if requested.
@@ -58,6 +60,7 @@ fn restart(
     to_restart: TaskToRestart,
     stashed: bool,
     settings: &Settings,
+    events: &EventSender,
 ) {
     // Check if we actually know this task.
     let Some(task) = state.tasks.get_mut(&to_restart.task_id) else {
@@ -71,10 +74,10 @@ fn restart(
 
     // Either enqueue the task or stash it.
     if stashed {
-        task.status = TaskStatus::Stashed { enqueue_at: None };
+        events.set_task_status(task, TaskStatus::Stashed { enqueue_at: None });
         task.enqueued_at = None;
     } else {
-        task.status = TaskStatus::Queued;
+        events.set_task_status(task, TaskStatus::Queued);
         task.enqueued_at = Some(Local::now());
     };
 
@@ -96,7 +99,13 @@ fn restart(
         task.label = None
     }
 
+    // Update timeout if applicable.
+    if to_restart.timeout.is_some() {
+        task.timeout = to_restart.timeout;
+    }
+
     // Reset all variables of any previous run.
     task.start = None;
     task.end = None;
+    task.attempts.clear();
 }
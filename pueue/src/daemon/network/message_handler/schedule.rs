@@ -0,0 +1,107 @@
+use chrono::Local;
+use pueue_lib::aliasing::insert_alias;
+use pueue_lib::network::message::*;
+use pueue_lib::schedule::Schedule;
+use pueue_lib::settings::Settings;
+use pueue_lib::state::SharedState;
+use pueue_lib::task::{Task, TaskStatus};
+
+use crate::daemon::network::message_handler::ok_or_failure_message;
+use crate::daemon::network::response_helper::ensure_group_exists;
+use crate::daemon::state_helper::save_state;
+use crate::daemon::state_store::SharedStore;
+use crate::ok_or_return_failure_message;
+
+/// Invoked on `pueue schedule`.
+/// Manage recurring schedules.
+/// - Show schedules
+/// - Add schedule
+/// - Remove schedule
+/// - Pause/resume schedule
+pub fn schedule(
+    message: ScheduleMessage,
+    state: &SharedState,
+    settings: &Settings,
+    store: &SharedStore,
+) -> Message {
+    let mut state = state.lock().unwrap();
+
+    match message {
+        ScheduleMessage::List => ScheduleResponseMessage {
+            schedules: state.schedules.clone(),
+        }
+        .into(),
+        ScheduleMessage::Add(message) => {
+            if let Err(message) = ensure_group_exists(&mut state, &message.group) {
+                return message;
+            }
+
+            // Make sure the schedule actually fires at some point.
+            let next_run = match message.trigger.next_after(Local::now()) {
+                Ok(Some(next_run)) => next_run,
+                Ok(None) => {
+                    return create_failure_message("This schedule would never fire.");
+                }
+                Err(error) => return create_failure_message(error.to_string()),
+            };
+
+            // Build the template from which all tasks of this schedule are created.
+            let mut task = Task::new(
+                message.command,
+                message.path,
+                message.envs,
+                message.group,
+                TaskStatus::Queued,
+                Vec::new(),
+                0,
+                message.label,
+            );
+            task.command = insert_alias(settings, task.original_command.clone());
+
+            let schedule_id = state.add_schedule(Schedule {
+                id: 0,
+                trigger: message.trigger,
+                task,
+                paused: false,
+                skip_if_running: message.skip_if_running,
+                catch_up: message.catch_up,
+                next_run: Some(next_run),
+                last_run: None,
+                last_task_id: None,
+            });
+            ok_or_return_failure_message!(save_state(&state, store));
+
+            let next_run = next_run.format("%Y-%m-%d %H:%M:%S");
+            create_success_message(format!(
+                "New schedule added (id {schedule_id}). Next run at {next_run}"
+            ))
+        }
+        ScheduleMessage::Remove(schedule_id) => {
+            if state.schedules.remove(&schedule_id).is_none() {
+                return create_failure_message(format!("Schedule {schedule_id} doesn't exist"));
+            }
+            ok_or_return_failure_message!(save_state(&state, store));
+
+            create_success_message(format!("Schedule {schedule_id} has been removed"))
+        }
+        ScheduleMessage::Pause(schedule_id) | ScheduleMessage::Resume(schedule_id) => {
+            let pause = matches!(message, ScheduleMessage::Pause(_));
+            let Some(schedule) = state.schedules.get_mut(&schedule_id) else {
+                return create_failure_message(format!("Schedule {schedule_id} doesn't exist"));
+            };
+
+            schedule.paused = pause;
+            // Don't fire any runs that have been missed while the schedule was paused.
+            if !pause && schedule.next_run.map_or(true, |next| next <= Local::now()) {
+                schedule.next_run = schedule
+                    .trigger
+                    .next_after(Local::now())
+                    .unwrap_or_default();
+            }
+            ok_or_return_failure_message!(save_state(&state, store));
+
+            let action = if pause { "paused" } else { "resumed" };
+            create_success_message(format!("Schedule {schedule_id} has been {action}"))
+        }
+    }
+}
@@ -1,16 +1,22 @@
 use pueue_lib::network::message::*;
-use pueue_lib::settings::Settings;
 use pueue_lib::state::SharedState;
 use pueue_lib::task::TaskStatus;
 
 use super::ok_or_failure_message;
+use crate::daemon::events::EventSender;
 use crate::daemon::state_helper::save_state;
+use crate::daemon::state_store::SharedStore;
 use crate::ok_or_return_failure_message;
 
 /// Invoked when calling `pueue switch`.
 /// Switch the position of two tasks in the upcoming queue.
 /// We have to ensure that those tasks are either `Queued` or `Stashed`
-pub fn switch(message: SwitchMessage, state: &SharedState, settings: &Settings) -> Message {
+pub fn switch(
+    message: SwitchMessage,
+    state: &SharedState,
+    events: &EventSender,
+    store: &SharedStore,
+) -> Message {
     let mut state = state.lock().unwrap();
 
     let task_ids = [message.task_id_1, message.task_id_2];
@@ -36,10 +42,24 @@ pub fn switch(message: SwitchMessage, state: &SharedState, settings: &Settings)
     second_task.id = first_id;
 
     // Put tasks back in again
-    state.tasks.insert(first_task.id, first_task);
-    state.tasks.insert(second_task.id, second_task);
+    // For subscribers, the tasks at both positions are replaced.
+    for task in [first_task, second_task] {
+        events.task_removed(task.id);
+        events.task_added(&task);
+        state.tasks.insert(task.id, task);
+    }
 
     for (_, task) in state.tasks.iter_mut() {
+        // The conditions of dependencies follow their tasks.
+        let first_condition = task.dependency_conditions.remove(&first_id);
+        let second_condition = task.dependency_conditions.remove(&second_id);
+        if let Some(condition) = first_condition {
+            task.dependency_conditions.insert(second_id, condition);
+        }
+        if let Some(condition) = second_condition {
+            task.dependency_conditions.insert(first_id, condition);
+        }
+
         // If the task depends on both, we can just keep it as it is.
         if task.dependencies.contains(&first_id) && task.dependencies.contains(&second_id) {
             continue;
@@ -55,7 +75,7 @@ pub fn switch(message: SwitchMessage, state: &SharedState, settings: &Settings)
         }
     }
 
-    ok_or_return_failure_message!(save_state(&state, settings));
+    ok_or_return_failure_message!(save_state(&state, store));
     create_success_message("Tasks have been switched")
 }
 
@@ -64,6 +84,8 @@ mod tests {
     use pretty_assertions::assert_eq;
     use tempfile::TempDir;
 
+    use pueue_lib::task::DependencyCondition;
+
     use super::super::fixtures::*;
     use super::*;
 
@@ -112,7 +134,12 @@ mod tests {
     fn switch_normal() {
         let (state, settings, _tempdir) = get_test_state();
 
-        let message = switch(get_message(1, 2), &state, &settings);
+        let message = switch(
+            get_message(1, 2),
+            &state,
+            &EventSender::default(),
+            &get_store(&settings),
+        );
 
         // Return message is correct
         assert!(matches!(message, Message::Success(_)));
@@ -130,7 +157,12 @@ mod tests {
     fn switch_task_with_itself() {
         let (state, settings, _tempdir) = get_test_state();
 
-        let message = switch(get_message(1, 1), &state, &settings);
+        let message = switch(
+            get_message(1, 1),
+            &state,
+            &EventSender::default(),
+            &get_store(&settings),
+        );
 
         // Return message is correct
         assert!(matches!(message, Message::Failure(_)));
@@ -145,19 +177,53 @@ mod tests {
     fn switch_task_with_dependant() {
         let (state, settings, _tempdir) = get_test_state();
 
-        switch(get_message(0, 3), &state, &settings);
+        switch(
+            get_message(0, 3),
+            &state,
+            &EventSender::default(),
+            &get_store(&settings),
+        );
 
         let state = state.lock().unwrap();
         assert_eq!(state.tasks.get(&4).unwrap().dependencies, vec![0, 3]);
     }
 
+    #[test]
+    /// The conditions of dependencies follow their tasks, when those are switched.
+    fn switch_dependency_conditions() {
+        let (state, settings, _tempdir) = get_test_state();
+        {
+            let mut state = state.lock().unwrap();
+            let task = state.tasks.get_mut(&4).unwrap();
+            task.dependency_conditions
+                .insert(0, DependencyCondition::Failure);
+        }
+
+        switch(
+            get_message(0, 3),
+            &state,
+            &EventSender::default(),
+            &get_store(&settings),
+        );
+
+        let state = state.lock().unwrap();
+        let task = state.tasks.get(&4).unwrap();
+        assert_eq!(task.dependency_condition(0), DependencyCondition::Success);
+        assert_eq!(task.dependency_condition(3), DependencyCondition::Failure);
+    }
+
     #[test]
     /// A task with two dependencies shouldn't experience any change, if those two dependencies
     /// switched places.
     fn switch_double_dependency() {
         let (state, settings, _tempdir) = get_test_state();
 
-        switch(get_message(1, 2), &state, &settings);
+        switch(
+            get_message(1, 2),
+            &state,
+            &EventSender::default(),
+            &get_store(&settings),
+        );
 
         let state = state.lock().unwrap();
         assert_eq!(state.tasks.get(&5).unwrap().dependencies, vec![2]);
@@ -182,7 +248,12 @@ mod tests {
         ];
 
         for ids in combinations {
-            let message = switch(get_message(ids.0, ids.1), &state, &settings);
+            let message = switch(
+                get_message(ids.0, ids.1),
+                &state,
+                &EventSender::default(),
+                &get_store(&settings),
+            );
 
             // Assert, that we get a Failure message with the correct text.
             assert!(matches!(message, Message::Failure(_)));
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+
+use chrono::Local;
+use pueue_lib::aliasing::insert_alias;
+use pueue_lib::network::message::*;
+use pueue_lib::network::tokens::Principal;
+use pueue_lib::state::{SharedState, PUEUE_DEFAULT_GROUP};
+use pueue_lib::task::{Task, TaskStatus};
+use pueue_lib::workflow::WorkflowRef;
+
+use super::*;
+use crate::daemon::events::EventSender;
+use crate::daemon::state_helper::save_state;
+use crate::daemon::state_store::SharedStore;
+use crate::ok_or_return_failure_message;
+
+/// Invoked when calling `pueue workflow run`.
+/// Create a task for each step of the workflow, whose dependencies are the tasks of the steps
+/// it needs.
+///
+/// Everything is validated first, so either all tasks are created or none.
+pub fn run_workflow(
+    message: WorkflowMessage,
+    principal: Option<&Principal>,
+    state: &SharedState,
+    settings: &Settings,
+    events: &EventSender,
+    store: &SharedStore,
+) -> Message {
+    let steps = match message.workflow.ordered_steps() {
+        Ok(steps) => steps,
+        Err(err) => return create_failure_message(err.to_string()),
+    };
+
+    let mut state = state.lock().unwrap();
+    for (_, step) in &steps {
+        let group = step.group.as_deref().unwrap_or(PUEUE_DEFAULT_GROUP);
+        if let Err(message) = ensure_group_exists(&mut state, group) {
+            return message;
+        }
+    }
+
+    // The steps are ordered, so the tasks of needed steps always exist already.
+    let mut task_ids: HashMap<&String, usize> = HashMap::new();
+    let mut run = None;
+    for (name, step) in steps {
+        let dependencies = step.needs.iter().map(|need| task_ids[need]).collect();
+        let path = match &step.path {
+            Some(path) => message.path.join(path),
+            None => message.path.clone(),
+        };
+        let group = step
+            .group
+            .clone()
+            .unwrap_or_else(|| PUEUE_DEFAULT_GROUP.to_string());
+
+        let mut task = Task::new(
+            step.command.clone(),
+            path,
+            message.envs.clone(),
+            group,
+            TaskStatus::Locked,
+            dependencies,
+            0,
+            Some(name.clone()),
+        );
+        task.command = insert_alias(settings, task.original_command.clone());
+        task.separate_output = settings.daemon.separate_output;
+        task.owner = principal.map(|principal| principal.name.clone());
+        if message.stashed {
+            task.status = TaskStatus::Stashed { enqueue_at: None };
+        } else {
+            task.status = TaskStatus::Queued;
+            task.enqueued_at = Some(Local::now());
+        }
+
+        let task_id = state.add_task(task);
+        task_ids.insert(name, task_id);
+
+        // Runs are identified by the id of their first task.
+        let run_id = *run.get_or_insert(task_id);
+        let task = state
+            .tasks
+            .get_mut(&task_id)
+            .expect("Task has just been added");
+        task.workflow = Some(WorkflowRef {
+            workflow: message.workflow.name.clone(),
+            run: run_id,
+            step: name.clone(),
+        });
+        events.task_added(task);
+    }
+    ok_or_return_failure_message!(save_state(&state, store));
+
+    let mut tasks: Vec<(&String, usize)> = task_ids.into_iter().collect();
+    tasks.sort_by_key(|(_, task_id)| *task_id);
+    let tasks: Vec<String> = tasks
+        .into_iter()
+        .map(|(name, task_id)| format!("{name} ({task_id})"))
+        .collect();
+
+    create_success_message(format!(
+        "New workflow run {} of '{}' with the tasks: {}",
+        run.unwrap_or_default(),
+        message.workflow.name,
+        tasks.join(", ")
+    ))
+}
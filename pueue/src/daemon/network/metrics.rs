@@ -0,0 +1,107 @@
+//! Serve the daemon's metrics via HTTP, so they can be scraped by Prometheus.
+//!
+//! The metrics are served on `GET /metrics`, either on a TCP address, a unix socket or both.
+//! Requests are parsed and answered by the same logic as the requests of the HTTP API.
+use std::future::poll_fn;
+use std::io;
+use std::task::{Context as TaskContext, Poll};
+
+use anyhow::{Context, Result};
+use log::{debug, warn};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, TcpStream};
+
+use pueue_lib::state::SharedState;
+
+use crate::daemon::metrics::render;
+use crate::daemon::network::http::{receive_request, write_response, Response};
+
+/// The content type of the Prometheus text format.
+const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// The listeners the metrics can be served on.
+pub trait MetricsListener: Send + Sync + 'static {
+    type Stream: AsyncRead + AsyncWrite + Unpin + Send + 'static;
+
+    fn poll_accept(&self, cx: &mut TaskContext<'_>) -> Poll<io::Result<Self::Stream>>;
+}
+
+impl MetricsListener for TcpListener {
+    type Stream = TcpStream;
+
+    fn poll_accept(&self, cx: &mut TaskContext<'_>) -> Poll<io::Result<TcpStream>> {
+        TcpListener::poll_accept(self, cx).map_ok(|(stream, _)| stream)
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+impl MetricsListener for tokio::net::UnixListener {
+    type Stream = tokio::net::UnixStream;
+
+    fn poll_accept(&self, cx: &mut TaskContext<'_>) -> Poll<io::Result<Self::Stream>> {
+        tokio::net::UnixListener::poll_accept(self, cx).map_ok(|(stream, _)| stream)
+    }
+}
+
+/// Bind the listener of the metrics endpoint.
+/// This is done before spawning [accept_metrics], so errors are reported on daemon startup.
+pub async fn get_metrics_listener(address: &str) -> Result<TcpListener> {
+    TcpListener::bind(address).await.context(format!(
+        "Failed to listen for metrics requests on {address}"
+    ))
+}
+
+/// Bind the unix socket of the metrics endpoint.
+/// A socket of a previous session is removed first.
+#[cfg(not(target_os = "windows"))]
+pub fn get_metrics_socket_listener(path: &std::path::Path) -> Result<tokio::net::UnixListener> {
+    if path.exists() {
+        std::fs::remove_file(path)
+            .with_context(|| format!("Failed to remove old metrics socket at {path:?}"))?;
+    }
+
+    tokio::net::UnixListener::bind(path)
+        .with_context(|| format!("Failed to create metrics socket at {path:?}"))
+}
+
+/// Poll a listener of the metrics endpoint and answer each request in a separate task.
+pub async fn accept_metrics<L: MetricsListener>(listener: L, state: SharedState) -> Result<()> {
+    loop {
+        let stream = match poll_fn(|cx| listener.poll_accept(cx)).await {
+            Ok(stream) => stream,
+            Err(err) => {
+                warn!("Failed connecting to metrics client: {err:?}");
+                continue;
+            }
+        };
+
+        let state_clone = state.clone();
+        tokio::spawn(async move {
+            if let Err(err) = handle_metrics_request(stream, state_clone).await {
+                debug!("Failed to handle metrics request: {err:?}");
+            }
+        });
+    }
+}
+
+/// Answer a single HTTP request. Connections aren't kept alive.
+async fn handle_metrics_request<S>(mut stream: S, state: SharedState) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    // Scrapes never have a body.
+    let Some(request) = receive_request(&mut stream, 0).await? else {
+        return Ok(());
+    };
+
+    // The metrics are rendered while the state is locked, so the state doesn't have to be cloned.
+    let response = match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/metrics") => {
+            let body = render(&state.lock().unwrap());
+            Response::new(200, METRICS_CONTENT_TYPE, body.into_bytes())
+        }
+        _ => Response::new(404, METRICS_CONTENT_TYPE, b"Not found\n".to_vec()),
+    };
+
+    write_response(&mut stream, response).await
+}
@@ -1,6 +1,11 @@
+pub mod audit;
 pub mod follow_log;
+pub mod http;
 pub mod message_handler;
+pub mod metrics;
+pub mod permissions;
 pub mod response_helper;
 pub mod socket;
+pub mod subscribe;
 
 use super::TaskSender;
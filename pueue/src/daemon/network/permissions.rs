@@ -0,0 +1,126 @@
+//! Permissions of principals, that authenticated via a token of the token file.
+//!
+//! Clients that authenticated via the shared secret aren't restricted in any way.
+use anyhow::{Context, Result};
+
+use pueue_lib::network::message::*;
+use pueue_lib::network::tokens::{Principal, Role, Tokens};
+use pueue_lib::settings::Settings;
+use pueue_lib::state::SharedState;
+use pueue_lib::task::Task;
+
+/// Read the token file, if one has been configured.
+pub fn read_tokens(settings: &Settings) -> Result<Tokens> {
+    let Some(path) = settings.daemon.tokens_path() else {
+        return Ok(Tokens::default());
+    };
+
+    Tokens::read(&path).with_context(|| format!("Failed to read token file at {path:?}"))
+}
+
+/// Check whether a client is allowed to send a message.
+/// Returns the reason for the client, if it isn't.
+///
+/// - `read_only` principals may only inspect the state and the logs of tasks.
+/// - `submit` principals may additionally add tasks and manage those tasks they added.
+/// - `admin` principals may do anything.
+pub fn check_permission(
+    message: &Message,
+    principal: Option<&Principal>,
+    state: &SharedState,
+) -> Result<(), String> {
+    let Some(principal) = principal else {
+        return Ok(());
+    };
+
+    let allowed = match required_role(message) {
+        Requirement::Role(role) => principal.role >= role,
+        Requirement::Owner(task_ids) => {
+            principal.role == Role::Admin
+                || (principal.role == Role::Submit && owns_tasks(principal, &task_ids, state))
+        }
+    };
+    if allowed {
+        return Ok(());
+    }
+
+    Err(format!(
+        "Principal '{}' with role '{}' isn't allowed to do this",
+        principal.name, principal.role
+    ))
+}
+
+/// What's needed to send a message.
+enum Requirement {
+    /// At least the given role.
+    Role(Role),
+    /// The `submit` role and the ownership of all given tasks, or the `admin` role.
+    Owner(Vec<usize>),
+}
+
+fn required_role(message: &Message) -> Requirement {
+    match message {
+        Message::Status
+        | Message::Log(_)
+        | Message::StreamRequest(_)
+        | Message::Subscribe
+        | Message::History(_)
+        | Message::HistoryLog(_)
+        | Message::Group(GroupMessage::List)
+        | Message::Schedule(ScheduleMessage::List) => Requirement::Role(Role::ReadOnly),
+        Message::Add(_) | Message::AddBatch(_) | Message::Workflow(_) => {
+            Requirement::Role(Role::Submit)
+        }
+        Message::Remove(task_ids) | Message::Stash(task_ids) => {
+            Requirement::Owner(task_ids.clone())
+        }
+        Message::Switch(message) => Requirement::Owner(vec![message.task_id_1, message.task_id_2]),
+        Message::Enqueue(message) => Requirement::Owner(message.task_ids.clone()),
+        Message::Start(StartMessage { tasks })
+        | Message::Pause(PauseMessage { tasks, .. })
+        | Message::Kill(KillMessage { tasks, .. }) => match tasks {
+            TaskSelection::TaskIds(task_ids) => Requirement::Owner(task_ids.clone()),
+            // Groups contain the tasks of other principals as well.
+            TaskSelection::Group(_) | TaskSelection::All => Requirement::Role(Role::Admin),
+        },
+        Message::Restart(message) => {
+            Requirement::Owner(message.tasks.iter().map(|task| task.task_id).collect())
+        }
+        Message::Send(message) => Requirement::Owner(vec![message.task_id]),
+        Message::EditRequest(task_id) | Message::EditRestore(task_id) => {
+            Requirement::Owner(vec![*task_id])
+        }
+        Message::Edit(message) => Requirement::Owner(vec![message.task_id]),
+        _ => Requirement::Role(Role::Admin),
+    }
+}
+
+/// Check whether all given tasks have been added by the principal.
+/// Tasks that don't exist are ignored, so the usual error is shown for them.
+fn owns_tasks(principal: &Principal, task_ids: &[usize], state: &SharedState) -> bool {
+    let state = state.lock().unwrap();
+    task_ids.iter().all(|task_id| {
+        state
+            .tasks
+            .get(task_id)
+            .map_or(true, |task| task.owner.as_ref() == Some(&principal.name))
+    })
+}
+
+/// Check whether the principal added a task or may manage the tasks of others anyway.
+pub fn owns_task(principal: Option<&Principal>, task: &Task) -> bool {
+    let Some(principal) = principal else {
+        return true;
+    };
+
+    principal.role == Role::Admin || task.owner.as_ref() == Some(&principal.name)
+}
+
+/// Remove the environment variables of a task, unless it has been added by the principal.
+/// Environment variables frequently contain secrets, which only the owner of a task and
+/// admins may see.
+pub fn redact_envs(task: &mut Task, principal: Option<&Principal>) {
+    if !owns_task(principal, task) {
+        task.envs.clear();
+    }
+}
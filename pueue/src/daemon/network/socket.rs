@@ -8,20 +8,33 @@ use tokio::time::sleep;
 use pueue_lib::error::Error;
 use pueue_lib::network::message::*;
 use pueue_lib::network::protocol::*;
-use pueue_lib::network::secret::read_shared_secret;
+use pueue_lib::network::secret::{read_shared_secret, secrets_match};
+use pueue_lib::network::tokens::Tokens;
 use pueue_lib::settings::Settings;
 use pueue_lib::state::SharedState;
 
+use crate::daemon::events::EventSender;
+use crate::daemon::network::audit::AuditEntry;
 use crate::daemon::network::follow_log::handle_follow;
 use crate::daemon::network::message_handler::{handle_message, SENDER_ERR};
+use crate::daemon::network::permissions::check_permission;
+use crate::daemon::network::subscribe::handle_subscribe;
+use crate::daemon::state_store::SharedStore;
 use crate::daemon::task_handler::TaskSender;
 
+/// The maximum size of the secret or token a client sends to authenticate itself.
+/// Anyone that can reach the socket may send this, so don't allocate arbitrary amounts of memory.
+const MAX_SECRET_SIZE: usize = 16 * 1024;
+
 /// Poll the listener and accept new incoming connections.
 /// Create a new future to handle the message and spawn it.
 pub async fn accept_incoming(
     sender: TaskSender,
     state: SharedState,
     settings: Settings,
+    tokens: Tokens,
+    events: EventSender,
+    store: SharedStore,
 ) -> Result<()> {
     let listener = get_listener(&settings.shared).await?;
     // Read secret once to prevent multiple disk reads.
@@ -41,7 +54,10 @@ pub async fn accept_incoming(
         let sender_clone = sender.clone();
         let state_clone = state.clone();
         let secret_clone = secret.clone();
+        let tokens_clone = tokens.clone();
         let settings_clone = settings.clone();
+        let events_clone = events.clone();
+        let store_clone = store.clone();
         tokio::spawn(async move {
             let _result = handle_incoming(
                 stream,
@@ -49,6 +65,9 @@ pub async fn accept_incoming(
                 state_clone,
                 settings_clone,
                 secret_clone,
+                tokens_clone,
+                events_clone,
+                store_clone,
             )
             .await;
         });
@@ -58,15 +77,19 @@ pub async fn accept_incoming(
 /// Continuously poll the existing incoming futures.
 /// In case we received an instruction, handle it and create a response future.
 /// The response future is added to unix_responses and handled in a separate function.
+#[allow(clippy::too_many_arguments)]
 async fn handle_incoming(
     mut stream: GenericStream,
     sender: TaskSender,
     state: SharedState,
     settings: Settings,
     secret: Vec<u8>,
+    tokens: Tokens,
+    events: EventSender,
+    store: SharedStore,
 ) -> Result<()> {
     // Receive the secret once and check, whether the client is allowed to connect
-    let payload_bytes = receive_bytes(&mut stream).await?;
+    let payload_bytes = receive_bytes_with_max_size(&mut stream, Some(MAX_SECRET_SIZE)).await?;
 
     // Didn't receive any bytes. The client disconnected.
     if payload_bytes.is_empty() {
@@ -76,8 +99,27 @@ async fn handle_incoming(
 
     let start = SystemTime::now();
 
+    // Clients either authenticate via the shared secret, which grants full access,
+    // or via the token of a principal, whose permissions are restricted by its role.
+    // Clients of the unix socket might also be allowed via their credentials, in which case
+    // the role that's configured for their user or group id restricts their permissions.
+    let client = stream.peer_identity();
+    let valid_secret = secrets_match(&payload_bytes, &secret);
+    let principal = if valid_secret {
+        None
+    } else {
+        tokens.principal(&payload_bytes).or_else(|| {
+            let principal = peer_principal(&settings.shared, &client)?;
+            debug!(
+                "Client authenticated via its credentials as {} with role {}",
+                principal.name, principal.role
+            );
+            Some(principal)
+        })
+    };
+
     // Return if we got a wrong secret from the client.
-    if payload_bytes != secret {
+    if !valid_secret && principal.is_none() {
         let received_secret = String::from_utf8(payload_bytes)?;
         warn!("Received invalid secret: {received_secret}");
 
@@ -126,6 +168,11 @@ async fn handle_incoming(
             Message::StreamRequest(message) => {
                 handle_follow(&pueue_directory, &mut stream, &state, message).await?
             }
+            // The client wants to be notified about all changes of the state.
+            // This keeps the connection open and continuously pushes events.
+            Message::Subscribe => {
+                handle_subscribe(&mut stream, principal.as_ref(), &events).await?
+            }
             // Initialize the shutdown procedure.
             // The message is forwarded to the TaskHandler, which is responsible for
             // gracefully shutting down.
@@ -134,8 +181,20 @@ async fn handle_incoming(
             // Otherwise it might happen, that the daemon shuts down too fast and we aren't
             // capable of actually sending the message back to the client.
             Message::DaemonShutdown(shutdown_type) => {
-                let response = create_success_message("Daemon is shutting down");
+                let message = Message::DaemonShutdown(shutdown_type.clone());
+                let audit_entry = AuditEntry::new(&message, &client, principal.as_ref(), &settings);
+                let (response, allowed) =
+                    match check_permission(&message, principal.as_ref(), &state) {
+                        Ok(()) => (create_success_message("Daemon is shutting down"), true),
+                        Err(reason) => (create_failure_message(reason), false),
+                    };
+                if let Some(audit_entry) = audit_entry {
+                    audit_entry.write(&response, &settings);
+                }
                 send_message(response, &mut stream).await?;
+                if !allowed {
+                    continue;
+                }
 
                 // Notify the task handler.
                 sender.send(shutdown_type).expect(SENDER_ERR);
@@ -144,7 +203,16 @@ async fn handle_incoming(
             }
             _ => {
                 // Process a normal message.
-                handle_message(message, &sender, &state, &settings)
+                handle_message(
+                    message,
+                    &client,
+                    principal.as_ref(),
+                    &sender,
+                    &state,
+                    &settings,
+                    &events,
+                    &store,
+                )
             }
         };
 
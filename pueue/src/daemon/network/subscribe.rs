@@ -0,0 +1,39 @@
+use anyhow::Result;
+use log::{debug, warn};
+use tokio::sync::broadcast::error::RecvError;
+
+use pueue_lib::event::Event;
+use pueue_lib::network::message::*;
+use pueue_lib::network::protocol::{send_message, GenericStream};
+use pueue_lib::network::tokens::Principal;
+
+use crate::daemon::events::EventSender;
+use crate::daemon::network::permissions::redact_envs;
+
+/// Handle a subscription of a client.
+/// All changes of the state are continuously pushed to the client as [Message::Event]s,
+/// until the client goes away.
+pub async fn handle_subscribe(
+    stream: &mut GenericStream,
+    principal: Option<&Principal>,
+    events: &EventSender,
+) -> Result<Message> {
+    let mut receiver = events.subscribe();
+    debug!("Client subscribed to events");
+
+    loop {
+        match receiver.recv().await {
+            Ok(mut event) => {
+                if let Event::TaskAdded(task) = &mut event {
+                    redact_envs(task, principal);
+                }
+                send_message(Message::Event(event), stream).await?
+            }
+            // The client couldn't keep up with all events. Continue with the latest ones.
+            Err(RecvError::Lagged(missed)) => {
+                warn!("Subscriber fell behind and missed {missed} events");
+            }
+            Err(RecvError::Closed) => return Ok(Message::Close),
+        }
+    }
+}
@@ -31,14 +31,21 @@ fn check_for_running_daemon(pid_path: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Throw an error, if another daemon instance is running.
+pub fn ensure_no_running_daemon(pid_path: &Path) -> Result<()> {
+    if pid_path.exists() {
+        check_for_running_daemon(pid_path)?;
+    }
+
+    Ok(())
+}
+
 /// Create a file containing the current pid of the daemon's main process.
 /// Fails if it already exists or cannot be created.
 pub fn create_pid_file(pid_path: &Path) -> Result<()> {
     // If an old PID file exists, check if the referenced process is still running.
     // The pid might not have been properly cleaned up, if the machine or Pueue crashed hard.
-    if pid_path.exists() {
-        check_for_running_daemon(pid_path)?;
-    }
+    ensure_no_running_daemon(pid_path)?;
     let mut file = File::create(pid_path)
         .map_err(|err| Error::IoPathError(pid_path.to_path_buf(), "creating pid file", err))?;
 
@@ -1,16 +1,22 @@
 use std::collections::BTreeMap;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 use std::sync::MutexGuard;
-use std::time::SystemTime;
+use std::time::{Instant, SystemTime};
 
 use anyhow::{Context, Result};
 use chrono::prelude::*;
-use log::{debug, info};
+use log::info;
 
+use pueue_lib::archive::{prepare_archive, PendingArchive};
+use pueue_lib::resources::Resources;
 use pueue_lib::settings::Settings;
 use pueue_lib::state::{Group, GroupStatus, State, PUEUE_DEFAULT_GROUP};
-use pueue_lib::task::{TaskResult, TaskStatus};
+use pueue_lib::task::{Task, TaskResult, TaskStatus};
+
+use crate::daemon::events::EventSender;
+use crate::daemon::metrics::record_state_save;
+use crate::daemon::state_store::SharedStore;
 
 pub type LockedState<'a> = MutexGuard<'a, State>;
 
@@ -47,117 +53,125 @@ pub fn is_task_removable(state: &LockedState, task_id: &usize, to_delete: &[usiz
         .all(|task_id| is_task_removable(state, task_id, to_delete))
 }
 
+/// Prepare the archival of the finished ones of the given tasks, before they're removed.
+/// Returns `None`, unless archiving has been enabled and there's anything to archive.
+///
+/// Only the logs of the tasks are moved out of the way, while the state is locked.
+/// The returned archive should be written, once the state has been unlocked.
+pub fn archive_removed_tasks(
+    state: &LockedState,
+    task_ids: &[usize],
+    settings: &Settings,
+) -> Result<Option<PendingArchive>> {
+    if !settings.daemon.archive_removed_tasks {
+        return Ok(None);
+    }
+
+    let tasks: Vec<Task> = task_ids
+        .iter()
+        .filter_map(|task_id| state.tasks.get(task_id))
+        .filter(|task| task.is_done())
+        .cloned()
+        .collect();
+    if tasks.is_empty() {
+        return Ok(None);
+    }
+
+    let archive = prepare_archive(&settings.shared.pueue_directory(), tasks)
+        .context("Failed to archive removed tasks")?;
+
+    Ok(Some(archive))
+}
+
 /// A small helper for handling task failures. \
 /// Users can specify whether they want to pause the task's group or the
 /// whole daemon on a failed tasks. This function wraps that logic and decides if anything should be
 /// paused depending on the current settings.
 ///
 /// `group` should be the name of the failed task.
-pub fn pause_on_failure(state: &mut LockedState, settings: &Settings, group: &str) {
+pub fn pause_on_failure(
+    state: &mut LockedState,
+    settings: &Settings,
+    group: &str,
+    events: &EventSender,
+) {
     if settings.daemon.pause_group_on_failure {
-        if let Some(group) = state.groups.get_mut(group) {
-            group.status = GroupStatus::Paused;
+        if let Some(group_ref) = state.groups.get_mut(group) {
+            events.set_group_status(group, group_ref, GroupStatus::Paused);
         }
     } else if settings.daemon.pause_all_on_failure {
-        state.set_status_for_all_groups(GroupStatus::Paused);
+        events.set_status_for_all_groups(state, GroupStatus::Paused);
     }
 }
 
 /// Do a full reset of the state.
 /// This doesn't reset any processes!
-pub fn reset_state(state: &mut LockedState, settings: &Settings) -> Result<()> {
-    backup_state(state, settings)?;
-    state.tasks = BTreeMap::new();
-    state.set_status_for_all_groups(GroupStatus::Running);
+pub fn reset_state(
+    state: &mut LockedState,
+    store: &SharedStore,
+    settings: &Settings,
+    events: &EventSender,
+) -> Result<()> {
+    backup_state(state, store, settings)?;
+    for task_id in std::mem::take(&mut state.tasks).into_keys() {
+        events.task_removed(task_id);
+    }
+    events.set_status_for_all_groups(state, GroupStatus::Running);
 
-    save_state(state, settings)
+    save_state(state, store)
 }
 
-/// Convenience wrapper around save_to_file.
-pub fn save_state(state: &State, settings: &Settings) -> Result<()> {
-    save_state_to_file(state, settings, false)
+/// Persist the state via the configured [StateStore](crate::daemon::state_store::StateStore).
+pub fn save_state(state: &State, store: &SharedStore) -> Result<()> {
+    let start = Instant::now();
+    let result = store.save(state);
+    record_state_save(start.elapsed());
+
+    result
 }
 
 /// Save the current current state in a file with a timestamp.
 /// At the same time remove old state logs from the log directory.
 /// This function is called, when large changes to the state are applied, e.g. clean/reset.
-pub fn backup_state(state: &LockedState, settings: &Settings) -> Result<()> {
-    save_state_to_file(state, settings, true)?;
+pub fn backup_state(state: &LockedState, store: &SharedStore, settings: &Settings) -> Result<()> {
+    store.backup(state, &settings.shared.pueue_directory())?;
     rotate_state(settings).context("Failed to rotate old log files")?;
     Ok(())
 }
 
-/// Save the current state to disk. \
-/// We do this to restore in case of a crash. \
-/// If log == true, the file will be saved with a time stamp.
-///
-/// In comparison to the daemon -> client communication, the state is saved
-/// as JSON for readability and debugging purposes.
-fn save_state_to_file(state: &State, settings: &Settings, log: bool) -> Result<()> {
-    let serialized = serde_json::to_string(&state).context("Failed to serialize state:");
-
-    let serialized = serialized.unwrap();
-    let path = settings.shared.pueue_directory();
-    let (temp, real) = if log {
-        let path = path.join("log");
-        let now: DateTime<Utc> = Utc::now();
-        let time = now.format("%Y-%m-%d_%H-%M-%S");
-        (
-            path.join(format!("{time}_state.json.partial")),
-            path.join(format!("{time}_state.json")),
-        )
-    } else {
-        (path.join("state.json.partial"), path.join("state.json"))
-    };
-
-    // Write to temporary log file first, to prevent loss due to crashes.
-    fs::write(&temp, serialized).context("Failed to write temp file while saving state.")?;
-
-    // Overwrite the original with the temp file, if everything went fine.
-    fs::rename(&temp, &real).context("Failed to overwrite old state while saving state")?;
-
-    if log {
-        debug!("State backup created at: {real:?}");
-    } else {
-        debug!("State saved at: {real:?}");
-    }
-
-    Ok(())
-}
-
 /// Restore the last state from a previous session. \
-/// The state is stored as json in the `pueue_directory`.
+/// The state is loaded from the configured [StateStore](crate::daemon::state_store::StateStore).
 ///
 /// If the state cannot be deserialized, an empty default state will be used instead. \
 /// All groups with queued tasks will be automatically paused to prevent unwanted execution.
-pub fn restore_state(pueue_directory: &Path) -> Result<Option<State>> {
-    let path = pueue_directory.join("state.json");
-
-    // Ignore if the file doesn't exist. It doesn't have to.
-    if !path.exists() {
-        info!("Couldn't find state from previous session at location: {path:?}");
+///
+/// If `daemon.reattach_tasks` is enabled, running tasks with a known process id keep their
+/// status, as the TaskHandler reattaches to them.
+pub fn restore_state(store: &SharedStore, settings: &Settings) -> Result<Option<State>> {
+    let Some(mut state) = store.load()? else {
         return Ok(None);
-    }
+    };
     info!("Restoring state");
 
-    // Try to load the file.
-    let data = fs::read_to_string(&path).context("State restore: Failed to read file:\n\n{}")?;
-
-    // Try to deserialize the state file.
-    let mut state: State = serde_json::from_str(&data).context("Failed to deserialize state.")?;
-
     // Restore all tasks.
     // While restoring the tasks, check for any invalid/broken stati.
     for (_, task) in state.tasks.iter_mut() {
-        // Handle ungraceful shutdowns while executing tasks.
-        if task.status == TaskStatus::Running || task.status == TaskStatus::Paused {
-            info!(
-                "Setting task {} with previous status {:?} to new status {:?}",
-                task.id,
-                task.status,
-                TaskResult::Killed
-            );
-            task.status = TaskStatus::Done(TaskResult::Killed);
+        if task.is_running() {
+            if settings.daemon.reattach_tasks && task.pid.is_some() {
+                // Tasks that kept running while the daemon was down are picked up by the
+                // TaskHandler. If their process is gone by now, it handles them as finished.
+                info!("Reattaching to task {} with pid {:?}", task.id, task.pid);
+            } else {
+                // Handle ungraceful shutdowns while executing tasks.
+                info!(
+                    "Setting task {} with previous status {:?} to new status {:?}",
+                    task.id,
+                    task.status,
+                    TaskResult::Killed
+                );
+                task.status = TaskStatus::Done(TaskResult::Killed);
+                task.pid = None;
+            }
         }
 
         // Handle crash during editing of the task command.
@@ -177,6 +191,9 @@ pub fn restore_state(pueue_directory: &Path) -> Result<Option<State>> {
                     .or_insert(Group {
                         status: GroupStatus::Running,
                         parallel_tasks: 1,
+                        resources: Resources::new(),
+                        blocked: None,
+                        priority: 0,
                     })
             }
         };
@@ -192,6 +209,20 @@ pub fn restore_state(pueue_directory: &Path) -> Result<Option<State>> {
         }
     }
 
+    // Handle schedule runs that have been missed while the daemon was down.
+    // Schedules that should catch up keep their overdue run, which then fires right away.
+    // All other schedules simply skip the missed runs.
+    let now = Local::now();
+    for (_, schedule) in state.schedules.iter_mut() {
+        let overdue = schedule.next_run.map_or(false, |next_run| next_run <= now);
+        if !overdue || schedule.catch_up {
+            continue;
+        }
+
+        info!("Skipping missed runs of schedule {}", schedule.id);
+        schedule.next_run = schedule.trigger.next_after(now).unwrap_or_default();
+    }
+
     Ok(Some(state))
 }
 
@@ -0,0 +1,77 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use log::{debug, info};
+
+use pueue_lib::state::State;
+
+use super::StateStore;
+
+/// The default backend, which keeps the whole state in `state.json`.
+///
+/// In comparison to the daemon -> client communication, the state is saved
+/// as JSON for readability and debugging purposes.
+pub struct JsonStore {
+    path: PathBuf,
+}
+
+impl JsonStore {
+    pub fn new(pueue_directory: &Path) -> JsonStore {
+        JsonStore {
+            path: pueue_directory.join("state.json"),
+        }
+    }
+}
+
+impl StateStore for JsonStore {
+    fn save(&self, state: &State) -> Result<()> {
+        write_state_file(state, &self.path)?;
+
+        debug!("State saved at: {:?}", self.path);
+        Ok(())
+    }
+
+    fn load(&self) -> Result<Option<State>> {
+        // Ignore if the file doesn't exist. It doesn't have to.
+        if !self.path.exists() {
+            info!(
+                "Couldn't find state from previous session at location: {:?}",
+                self.path
+            );
+            return Ok(None);
+        }
+
+        read_state_file(&self.path).map(Some)
+    }
+}
+
+/// Write the state as JSON to the given path.
+///
+/// The state is written to a temporary file first, which then replaces the original.
+/// That way, the previous state isn't lost, if the daemon crashes while writing.
+pub fn write_state_file(state: &State, path: &Path) -> Result<()> {
+    let serialized = serde_json::to_string(&state).context("Failed to serialize state:")?;
+
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".partial");
+    let temp = PathBuf::from(temp);
+
+    // Write to temporary log file first, to prevent loss due to crashes.
+    fs::write(&temp, serialized).context("Failed to write temp file while saving state.")?;
+
+    // Overwrite the original with the temp file, if everything went fine.
+    fs::rename(&temp, path).context("Failed to overwrite old state while saving state")?;
+
+    Ok(())
+}
+
+/// Read a state, that has been written as JSON.
+pub fn read_state_file(path: &Path) -> Result<State> {
+    // Try to load the file.
+    let data =
+        fs::read_to_string(path).with_context(|| format!("Failed to read state file: {path:?}"))?;
+
+    // Try to deserialize the state file.
+    serde_json::from_str(&data).context("Failed to deserialize state.")
+}
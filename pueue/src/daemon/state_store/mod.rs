@@ -0,0 +1,56 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::prelude::*;
+use log::debug;
+
+use pueue_lib::settings::{Settings, StateBackend};
+use pueue_lib::state::State;
+
+mod json;
+mod sqlite;
+
+pub use self::json::{read_state_file, write_state_file, JsonStore};
+pub use self::sqlite::SqliteStore;
+
+/// A storage backend for the daemon's state.
+///
+/// The state is saved after each change and restored, once the daemon starts again.
+pub trait StateStore: Send + Sync {
+    /// Persist the current state.
+    fn save(&self, state: &State) -> Result<()>;
+
+    /// Load the state of the previous session, if there's any.
+    fn load(&self) -> Result<Option<State>>;
+
+    /// Save a copy of the state with a timestamp in the `log` directory.
+    ///
+    /// Backups are always written as JSON, so they can be inspected and imported via
+    /// `pueued --import-state` regardless of the backend.
+    fn backup(&self, state: &State, pueue_directory: &Path) -> Result<()> {
+        let time = Utc::now().format("%Y-%m-%d_%H-%M-%S");
+        let path = pueue_directory
+            .join("log")
+            .join(format!("{time}_state.json"));
+        write_state_file(state, &path)?;
+
+        debug!("State backup created at: {path:?}");
+        Ok(())
+    }
+}
+
+/// The store of the daemon, which is shared between the TaskHandler and the message handlers.
+pub type SharedStore = Arc<dyn StateStore>;
+
+/// Open the store of the configured backend.
+/// The daemon does this once on startup and keeps using the same store.
+pub fn get_state_store(settings: &Settings) -> Result<SharedStore> {
+    let pueue_directory = settings.shared.pueue_directory();
+    let store: SharedStore = match settings.daemon.state_backend {
+        StateBackend::Json => Arc::new(JsonStore::new(&pueue_directory)),
+        StateBackend::Sqlite => Arc::new(SqliteStore::open(&pueue_directory)?),
+    };
+
+    Ok(store)
+}
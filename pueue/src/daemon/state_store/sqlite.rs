@@ -0,0 +1,283 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use log::{debug, info};
+use rusqlite::types::{FromSql, ToSql};
+use rusqlite::{params, Connection, Transaction};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use pueue_lib::schedule::Schedule;
+use pueue_lib::state::{Group, State};
+use pueue_lib::task::Task;
+
+use super::{JsonStore, StateStore};
+
+/// The schema migrations of the state database.
+///
+/// The schema version of a database is the amount of migrations that have been applied to it.
+/// Existing migrations must never be changed, new ones are only ever appended.
+const MIGRATIONS: &[&str] = &[
+    // Version 1: Each task, group and schedule is stored as JSON in its own row.
+    "CREATE TABLE tasks (key INTEGER PRIMARY KEY, data TEXT NOT NULL);
+     CREATE TABLE groups (key TEXT PRIMARY KEY, data TEXT NOT NULL);
+     CREATE TABLE schedules (key INTEGER PRIMARY KEY, data TEXT NOT NULL);",
+];
+
+/// The state, as it has last been written to the database.
+struct SavedState {
+    tasks: BTreeMap<usize, Task>,
+    groups: BTreeMap<String, Group>,
+    schedules: BTreeMap<usize, Schedule>,
+}
+
+/// Keeps the state in the `state.sqlite` database.
+///
+/// Each task is stored in its own row, so only those tasks that changed are written,
+/// whenever the state is saved.
+pub struct SqliteStore {
+    path: PathBuf,
+    pueue_directory: PathBuf,
+    connection: Mutex<Connection>,
+    /// The state as it has last been saved.
+    /// This allows to only write the parts of the state that changed since the last save.
+    /// Until the state has been saved once, all rows are compared with the state instead.
+    saved: Mutex<Option<SavedState>>,
+}
+
+impl SqliteStore {
+    /// Open the database and bring its schema up to date.
+    pub fn open(pueue_directory: &Path) -> Result<SqliteStore> {
+        let path = pueue_directory.join("state.sqlite");
+        let mut connection = Connection::open(&path)
+            .with_context(|| format!("Failed to open state database: {path:?}"))?;
+        // Wait for other connections, e.g. of `pueued --export-state`, instead of failing.
+        connection.busy_timeout(Duration::from_secs(5))?;
+        migrate(&mut connection)?;
+
+        Ok(SqliteStore {
+            path,
+            pueue_directory: pueue_directory.to_path_buf(),
+            connection: Mutex::new(connection),
+            saved: Mutex::new(None),
+        })
+    }
+
+    /// Import the state of a previous session, that has been saved by the JSON backend.
+    ///
+    /// The imported `state.json` is renamed afterwards.
+    /// Otherwise, the outdated file would be restored, if the daemon is switched back to the
+    /// JSON backend later on.
+    fn import_json_state(&self) -> Result<Option<State>> {
+        let Some(state) = JsonStore::new(&self.pueue_directory).load()? else {
+            return Ok(None);
+        };
+
+        info!("Importing state.json into the state database");
+        self.save(&state)?;
+        fs::rename(
+            self.pueue_directory.join("state.json"),
+            self.pueue_directory.join("state.json.imported"),
+        )
+        .context("Failed to rename the imported state.json")?;
+
+        Ok(Some(state))
+    }
+}
+
+impl StateStore for SqliteStore {
+    fn save(&self, state: &State) -> Result<()> {
+        let mut saved_state = self.saved.lock().unwrap();
+        // Take the saved state out, so it's discarded if anything goes wrong.
+        let saved = saved_state.take();
+
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection
+            .transaction()
+            .context("Failed to start transaction")?;
+        let saved = match saved {
+            Some(mut saved) => {
+                write_changes(&transaction, "tasks", &state.tasks, &mut saved.tasks)?;
+                write_changes(&transaction, "groups", &state.groups, &mut saved.groups)?;
+                write_changes(
+                    &transaction,
+                    "schedules",
+                    &state.schedules,
+                    &mut saved.schedules,
+                )?;
+                saved
+            }
+            None => {
+                write_all(&transaction, "tasks", &state.tasks)?;
+                write_all(&transaction, "groups", &state.groups)?;
+                write_all(&transaction, "schedules", &state.schedules)?;
+                SavedState {
+                    tasks: state.tasks.clone(),
+                    groups: state.groups.clone(),
+                    schedules: state.schedules.clone(),
+                }
+            }
+        };
+        transaction
+            .commit()
+            .context("Failed to commit state to database")?;
+        *saved_state = Some(saved);
+
+        debug!("State saved at: {:?}", self.path);
+        Ok(())
+    }
+
+    fn load(&self) -> Result<Option<State>> {
+        let connection = self.connection.lock().unwrap();
+        // A saved state always contains the default group.
+        // Without any groups, the database has just been created.
+        let groups = read_table(&connection, "groups")?;
+        if groups.is_empty() {
+            drop(connection);
+            return self.import_json_state();
+        }
+
+        let mut state = State::new();
+        state.groups = groups;
+        state.tasks = read_table(&connection, "tasks")?;
+        state.schedules = read_table(&connection, "schedules")?;
+
+        Ok(Some(state))
+    }
+}
+
+/// Apply all migrations that haven't been applied to the database yet.
+/// The schema version is stored as the `user_version` of the database.
+fn migrate(connection: &mut Connection) -> Result<()> {
+    let version: usize = connection
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .context("Failed to read the schema version of the state database")?;
+    if version == MIGRATIONS.len() {
+        return Ok(());
+    } else if version > MIGRATIONS.len() {
+        bail!("The state database has been created by a newer version of Pueue (schema version {version})");
+    }
+
+    let transaction = connection.transaction()?;
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        info!("Migrating state database to schema version {}", index + 1);
+        transaction
+            .execute_batch(migration)
+            .with_context(|| format!("Failed to migrate to schema version {}", index + 1))?;
+    }
+    // Pragmas don't support parameters.
+    transaction.execute_batch(&format!("PRAGMA user_version = {}", MIGRATIONS.len()))?;
+    transaction.commit()?;
+
+    Ok(())
+}
+
+/// Write those entries that changed since they've been saved the last time.
+/// Rows of entries that have been removed in the meantime are deleted.
+fn write_changes<K, V>(
+    transaction: &Transaction,
+    table: &str,
+    entries: &BTreeMap<K, V>,
+    saved: &mut BTreeMap<K, V>,
+) -> Result<()>
+where
+    K: ToSql + Ord + Clone,
+    V: Serialize + PartialEq + Clone,
+{
+    for (key, value) in entries {
+        if saved.get(key) != Some(value) {
+            upsert(transaction, table, key, value)?;
+            saved.insert(key.clone(), value.clone());
+        }
+    }
+
+    let removed: Vec<K> = saved
+        .keys()
+        .filter(|key| !entries.contains_key(key))
+        .cloned()
+        .collect();
+    for key in removed {
+        delete(transaction, table, &key)?;
+        saved.remove(&key);
+    }
+
+    Ok(())
+}
+
+/// Write all entries and delete the rows of all entries that no longer exist.
+/// Rows whose content didn't change aren't touched.
+fn write_all<K, V>(transaction: &Transaction, table: &str, entries: &BTreeMap<K, V>) -> Result<()>
+where
+    K: ToSql + FromSql + Ord,
+    V: Serialize,
+{
+    for (key, value) in entries {
+        upsert(transaction, table, key, value)?;
+    }
+
+    let mut statement = transaction.prepare(&format!("SELECT key FROM {table}"))?;
+    let keys = statement
+        .query_map([], |row| row.get::<_, K>(0))?
+        .collect::<Result<Vec<K>, _>>()?;
+    for key in keys.iter().filter(|key| !entries.contains_key(key)) {
+        delete(transaction, table, key)?;
+    }
+
+    Ok(())
+}
+
+/// Insert or update a single row, if its content changed.
+fn upsert(
+    transaction: &Transaction,
+    table: &str,
+    key: &impl ToSql,
+    value: &impl Serialize,
+) -> Result<()> {
+    let data = serde_json::to_string(value).context("Failed to serialize state:")?;
+    transaction
+        .prepare_cached(&format!(
+            "INSERT INTO {table} (key, data) VALUES (?1, ?2)
+             ON CONFLICT (key) DO UPDATE SET data = excluded.data WHERE data != excluded.data"
+        ))?
+        .execute(params![key, data])
+        .with_context(|| format!("Failed to write to {table}"))?;
+
+    Ok(())
+}
+
+/// Delete a single row.
+fn delete(transaction: &Transaction, table: &str, key: &impl ToSql) -> Result<()> {
+    transaction
+        .prepare_cached(&format!("DELETE FROM {table} WHERE key = ?1"))?
+        .execute([key])
+        .with_context(|| format!("Failed to delete from {table}"))?;
+
+    Ok(())
+}
+
+/// Read and deserialize all rows of a table.
+fn read_table<K, V>(connection: &Connection, table: &str) -> Result<BTreeMap<K, V>>
+where
+    K: FromSql + Ord,
+    V: DeserializeOwned,
+{
+    let mut statement = connection.prepare(&format!("SELECT key, data FROM {table}"))?;
+    let rows = statement
+        .query_map([], |row| {
+            Ok((row.get::<_, K>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("Failed to read {table} from state database"))?;
+
+    rows.into_iter()
+        .map(|(key, data)| {
+            let value = serde_json::from_str(&data)
+                .with_context(|| format!("Failed to deserialize entry of {table}"))?;
+            Ok((key, value))
+        })
+        .collect()
+}
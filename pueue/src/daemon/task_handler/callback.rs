@@ -2,6 +2,8 @@ use handlebars::RenderError;
 
 use super::*;
 
+use crate::daemon::metrics::record_callback_failure;
+
 impl TaskHandler {
     /// Users can specify a callback that's fired whenever a task finishes.
     /// Execute the callback by spawning a new subprocess.
@@ -16,6 +18,7 @@ impl TaskHandler {
             Ok(callback_command) => callback_command,
             Err(err) => {
                 error!("Failed to create callback command from template with error: {err}");
+                record_callback_failure();
                 return;
             }
         };
@@ -27,6 +30,7 @@ impl TaskHandler {
         let child = match spawn_result {
             Err(error) => {
                 error!("Failed to spawn callback with error: {error}");
+                record_callback_failure();
                 return;
             }
             Ok(child) => child,
@@ -70,21 +74,29 @@ impl TaskHandler {
         parameters.insert("end", print_time(task.end));
 
         // Read the last lines of the process' output and make it available.
-        if let Ok(output) = read_last_log_file_lines(
-            task.id,
-            &self.pueue_directory,
-            self.settings.daemon.callback_log_lines,
-        ) {
-            parameters.insert("output", output);
+        // For tasks with separate stdout and stderr, `output` only contains stdout.
+        let (output_file, stderr_file) = if task.separate_output {
+            (LogFile::Stdout, Some(LogFile::Stderr))
         } else {
-            parameters.insert("output", "".to_string());
-        }
+            (LogFile::Combined, None)
+        };
+        parameters.insert("output", self.read_callback_output(task.id, output_file));
 
-        let out_path = get_log_path(task.id, &self.pueue_directory);
+        let out_path = self.callback_log_path(task.id, output_file);
         // Using Display impl of PathBuf which isn't necessarily a perfect
         // representation of the path but should work for most cases here
         parameters.insert("output_path", out_path.display().to_string());
 
+        // Stderr is only available separately, if the task has been configured to do so.
+        if let Some(stderr_file) = stderr_file {
+            parameters.insert("stderr", self.read_callback_output(task.id, stderr_file));
+            let err_path = self.callback_log_path(task.id, stderr_file);
+            parameters.insert("stderr_path", err_path.display().to_string());
+        } else {
+            parameters.insert("stderr", "".to_string());
+            parameters.insert("stderr_path", "".to_string());
+        }
+
         // Get the exit code
         if let TaskStatus::Done(result) = &task.status {
             match result {
@@ -99,6 +111,30 @@ impl TaskHandler {
         handlebars.render_template(template_string, &parameters)
     }
 
+    /// Read the last lines of a log file of a task for the callback.
+    /// Missing or unreadable output results in an empty string.
+    fn read_callback_output(&self, task_id: usize, file: LogFile) -> String {
+        read_last_log_file_lines(
+            task_id,
+            &self.pueue_directory,
+            file,
+            self.settings.daemon.callback_log_lines,
+        )
+        .unwrap_or_default()
+    }
+
+    /// Get the path to a log file of a task for the callback.
+    /// This points to the compressed log file, if the log has already been compressed.
+    fn callback_log_path(&self, task_id: usize, file: LogFile) -> PathBuf {
+        let path = get_log_file_path(task_id, &self.pueue_directory, file);
+        let compressed_path = get_compressed_log_file_path(task_id, &self.pueue_directory, file);
+        if !path.exists() && compressed_path.exists() {
+            return compressed_path;
+        }
+
+        path
+    }
+
     /// Look at all running callbacks and log any errors.
     /// If everything went smoothly, simply remove them from the list.
     pub fn check_callbacks(&mut self) {
@@ -108,12 +144,16 @@ impl TaskHandler {
                 // Handle a child error.
                 Err(error) => {
                     error!("Callback failed with error {error:?}");
+                    record_callback_failure();
                     finished.push(id);
                 }
                 // Child process did not exit yet.
                 Ok(None) => continue,
-                Ok(exit_status) => {
+                Ok(Some(exit_status)) => {
                     info!("Callback finished with exit code {exit_status:?}");
+                    if !exit_status.success() {
+                        record_callback_failure();
+                    }
                     finished.push(id);
                 }
             }
@@ -2,9 +2,14 @@ use super::*;
 
 use pueue_lib::state::Group;
 
+use crate::daemon::metrics::record_finished_task;
+
 impl TaskHandler {
     /// Ensure that no `Queued` tasks have any failed dependencies.
     /// Otherwise set their status to `Done` and result to `DependencyFailed`.
+    ///
+    /// A dependency failed, if it finished without fulfilling its condition.
+    /// By default, that's the case if it didn't succeed.
     pub fn check_failed_dependencies(&mut self) {
         // Clone the state ref, so we don't have two mutable borrows later on.
         let state_ref = self.state.clone();
@@ -22,8 +27,11 @@ impl TaskHandler {
                     .dependencies
                     .iter()
                     .flat_map(|id| state.tasks.get(id))
-                    .filter(|task| task.failed())
-                    .map(|task| task.id)
+                    .filter(|dependency| {
+                        task.dependency_condition(dependency.id)
+                            .is_violated(&dependency.status)
+                    })
+                    .map(|dependency| dependency.id)
                     .next();
 
                 failed.map(|f| (*id, f))
@@ -51,9 +59,12 @@ impl TaskHandler {
             }
 
             let task = state.tasks.get_mut(&id).unwrap();
-            task.status = TaskStatus::Done(TaskResult::DependencyFailed);
+            self.events
+                .set_task_status(task, TaskStatus::Done(TaskResult::DependencyFailed));
             task.start = Some(Local::now());
             task.end = Some(Local::now());
+            record_finished_task(task);
+            self.retention_check_due = true;
             self.spawn_callback(task);
         }
     }
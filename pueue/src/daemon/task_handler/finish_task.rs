@@ -1,7 +1,11 @@
 use anyhow::Context;
 
+use pueue_lib::task::TaskAttempt;
+
+use super::reattach::clean_exit_code_file;
 use super::*;
 
+use crate::daemon::metrics::record_finished_task;
 use crate::daemon::state_helper::{pause_on_failure, save_state};
 use crate::ok_or_shutdown;
 
@@ -16,6 +20,14 @@ impl TaskHandler {
             return;
         }
 
+        // Make sure that the tasks' logs are complete, before anyone gets to see them.
+        // This might take a while, so it's done before the state is locked.
+        let task_ids: Vec<usize> = finished
+            .iter()
+            .map(|((task_id, _, _), _)| *task_id)
+            .collect();
+        self.finish_output_recording(&task_ids);
+
         // Clone the state ref, so we don't have two mutable borrows later on.
         let state_ref = self.state.clone();
         let mut state = state_ref.lock().unwrap();
@@ -32,17 +44,27 @@ impl TaskHandler {
                     .remove(worker_id)
                     .expect("Errored child went missing while handling finished task.");
 
+                error!("Child {} failed with io::Error: {:?}", task_id, error);
+                self.timed_out.remove(task_id);
+                clean_exit_code_file(*task_id, &self.pueue_directory);
                 let group = {
                     let task = state.tasks.get_mut(task_id).unwrap();
-                    task.status = TaskStatus::Done(TaskResult::Errored);
+                    task.pid = None;
+                    if schedule_retry(task, &TaskResult::Errored, &self.events) {
+                        continue;
+                    }
+
+                    self.events
+                        .set_task_status(task, TaskStatus::Done(TaskResult::Errored));
                     task.end = Some(Local::now());
-                    self.spawn_callback(task);
+                    record_finished_task(task);
+                    self.retention_check_due = true;
+                    self.compress_logs_and_spawn_callback(task);
 
                     task.group.clone()
                 };
-                error!("Child {} failed with io::Error: {:?}", task_id, error);
 
-                pause_on_failure(&mut state, &self.settings, &group);
+                pause_on_failure(&mut state, &self.settings, &group, &self.events);
                 continue;
             }
 
@@ -65,13 +87,20 @@ impl TaskHandler {
                 ))
                 .unwrap()
                 .code();
+            clean_exit_code_file(*task_id, &self.pueue_directory);
 
+            // Processes that have been terminated due to their timeout are marked as such,
+            // no matter how they exited.
             // Processes with exit code 0 exited successfully
             // Processes with `None` have been killed by a Signal
-            let result = match exit_code {
-                Some(0) => TaskResult::Success,
-                Some(exit_code) => TaskResult::Failed(exit_code),
-                None => TaskResult::Killed,
+            let result = if self.timed_out.remove(task_id).is_some() {
+                TaskResult::TimedOut
+            } else {
+                match exit_code {
+                    Some(0) => TaskResult::Success,
+                    Some(exit_code) => TaskResult::Failed(exit_code),
+                    None => TaskResult::Killed,
+                }
             };
 
             // Update all properties on the task and get the group for later
@@ -80,16 +109,26 @@ impl TaskHandler {
                     .tasks
                     .get_mut(task_id)
                     .expect("Task was removed before child process has finished!");
+                task.pid = None;
 
-                task.status = TaskStatus::Done(result.clone());
+                // Failed tasks with retries left are rescheduled instead of being finished.
+                // Callbacks and `pause_on_failure` are only triggered by the final attempt.
+                if schedule_retry(task, &result, &self.events) {
+                    continue;
+                }
+
+                self.events
+                    .set_task_status(task, TaskStatus::Done(result.clone()));
                 task.end = Some(Local::now());
-                self.spawn_callback(task);
+                record_finished_task(task);
+                self.retention_check_due = true;
+                self.compress_logs_and_spawn_callback(task);
 
                 task.group.clone()
             };
 
-            if let TaskResult::Failed(_) = result {
-                pause_on_failure(&mut state, &self.settings, &group);
+            if matches!(result, TaskResult::Failed(_) | TaskResult::TimedOut) {
+                pause_on_failure(&mut state, &self.settings, &group, &self.events);
             }
 
             // Already remove the output files, if the daemon is being reset anyway
@@ -98,7 +137,7 @@ impl TaskHandler {
             }
         }
 
-        ok_or_shutdown!(self, save_state(&state, &self.settings));
+        ok_or_shutdown!(self, save_state(&state, &self.store));
     }
 
     /// Gather all finished tasks and sort them by finished and errored.
@@ -125,3 +164,52 @@ impl TaskHandler {
         finished
     }
 }
+
+/// Schedule another run of a finished task, if its retry policy allows it.
+/// The finished run is recorded in the task's attempt history and the task is either
+/// queued right away or stashed until the retry delay has elapsed.
+///
+/// Returns `true`, if the task has been rescheduled.
+pub fn schedule_retry(task: &mut Task, result: &TaskResult, events: &EventSender) -> bool {
+    if !task.should_retry(result) {
+        return false;
+    }
+
+    task.attempts.push(TaskAttempt {
+        result: result.clone(),
+        start: task.start,
+        end: Some(Local::now()),
+    });
+
+    let retry = task.attempts.len();
+    let delay = task
+        .retry_policy
+        .as_ref()
+        .map(|policy| policy.delay_for_retry(retry))
+        .unwrap_or(0);
+    info!(
+        "Retrying task {} (retry {retry}) in {delay} seconds",
+        task.id
+    );
+
+    // Retries without delay are directly enqueued.
+    // Otherwise, the task is stashed and enqueued by `enqueue_delayed_tasks` once the delay elapsed.
+    if delay == 0 {
+        events.set_task_status(task, TaskStatus::Queued);
+        task.enqueued_at = Some(Local::now());
+    } else {
+        // Cap absurdly long delays, which would otherwise overflow the timestamp.
+        let delay = delay.min(i32::MAX as u64) as i64;
+        events.set_task_status(
+            task,
+            TaskStatus::Stashed {
+                enqueue_at: Some(Local::now() + chrono::Duration::seconds(delay)),
+            },
+        );
+        task.enqueued_at = None;
+    }
+    task.start = None;
+    task.end = None;
+
+    true
+}
@@ -21,10 +21,14 @@ impl TaskHandler {
         let mut state = cloned_state_mutex.lock().unwrap();
 
         match message {
-            GroupMessage::List => {}
+            GroupMessage::List
+            | GroupMessage::SetResources { .. }
+            | GroupMessage::SetPriority { .. } => {}
             GroupMessage::Add {
                 name,
                 parallel_tasks,
+                resources,
+                priority,
             } => {
                 if state.groups.contains_key(&name) {
                     error!("Group \"{name}\" already exists");
@@ -34,13 +38,16 @@ impl TaskHandler {
                 if let Some(parallel_tasks) = parallel_tasks {
                     group.parallel_tasks = parallel_tasks;
                 }
+                group.resources = resources;
+                group.priority = priority;
+                self.events.group_added(&name, group);
                 info!("New group \"{name}\" has been created");
 
                 // Create the worker pool.
                 self.children.0.insert(name, BTreeMap::new());
 
                 // Persist the state.
-                ok_or_shutdown!(self, save_state(&state, &self.settings));
+                ok_or_shutdown!(self, save_state(&state, &self.store));
             }
             GroupMessage::Remove(group) => {
                 if !state.groups.contains_key(&group) {
@@ -58,6 +65,7 @@ impl TaskHandler {
                     error!("Error while removing group: \"{error}\"");
                     return;
                 }
+                self.events.group_removed(&group);
 
                 // Make sure the worker pool exists and is empty.
                 // There shouldn't be any children, if there are no tasks in this group.
@@ -78,7 +86,7 @@ impl TaskHandler {
                 self.children.0.remove(&group);
 
                 // Persist the state.
-                ok_or_shutdown!(self, save_state(&state, &self.settings));
+                ok_or_shutdown!(self, save_state(&state, &self.store));
 
                 info!("Group \"{group}\" has been removed");
             }
@@ -39,7 +39,8 @@ impl TaskHandler {
                 // Check whether the group should be paused before killing the tasks.
                 if should_pause_group(&state, issued_by_user, &group_name) {
                     let group = state.groups.get_mut(&group_name).unwrap();
-                    group.status = GroupStatus::Paused;
+                    self.events
+                        .set_group_status(&group_name, group, GroupStatus::Paused);
                 }
 
                 // Determine all running or paused tasks in that group.
@@ -56,12 +57,15 @@ impl TaskHandler {
                 let group_names: Vec<String> = state.groups.keys().cloned().collect();
                 for group_name in group_names {
                     if should_pause_group(&state, issued_by_user, &group_name) {
-                        state.set_status_for_all_groups(GroupStatus::Paused);
+                        self.events
+                            .set_status_for_all_groups(&mut state, GroupStatus::Paused);
                     }
                 }
 
                 info!("Killing all running tasks");
-                self.children.all_task_ids()
+                let mut task_ids = self.children.all_task_ids();
+                task_ids.extend(self.reattached.keys());
+                task_ids
             }
         };
 
@@ -73,13 +77,25 @@ impl TaskHandler {
             }
         }
 
-        ok_or_shutdown!(self, save_state(&state, &self.settings));
+        ok_or_shutdown!(self, save_state(&state, &self.store));
     }
 
     /// Send a signal to a specific child process.
     /// This is a wrapper around [send_signal_to_child], which does a little bit of
     /// additional error handling.
     pub fn send_internal_signal(&mut self, task_id: usize, signal: Signal) {
+        // Tasks we reattached to after a restart can only be reached via their process group.
+        if self.reattached.contains_key(&task_id) {
+            let Some(pgid) = self.reattached_process_group(task_id) else {
+                warn!("Reattached task {task_id} has already finished");
+                return;
+            };
+            if let Err(err) = send_signal_to_process_group(pgid, signal) {
+                warn!("Failed to send signal to task {task_id} with error: {err}");
+            };
+            return;
+        }
+
         let child = match self.children.get_child_mut(task_id) {
             Some(child) => child,
             None => {
@@ -100,6 +116,16 @@ impl TaskHandler {
             kill_child(task_id, child).unwrap_or_else(|err| {
                 warn!("Failed to send kill to task {task_id} child process {child:?} with error {err:?}");
             })
+        } else if self.reattached.contains_key(&task_id) {
+            let Some(pgid) = self.reattached_process_group(task_id) else {
+                warn!("Reattached task {task_id} has already finished");
+                return;
+            };
+            send_signal_to_process_group(pgid, Signal::SigKill).unwrap_or_else(|err| {
+                warn!(
+                    "Failed to send kill to task {task_id} process group {pgid} with error {err:?}"
+                );
+            })
         } else {
             warn!("Tried to kill non-existing child: {task_id}");
         }
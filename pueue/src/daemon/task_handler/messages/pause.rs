@@ -27,7 +27,8 @@ impl TaskHandler {
                 };
 
                 // Pause a specific group.
-                group.status = GroupStatus::Paused;
+                self.events
+                    .set_group_status(&group_name, group, GroupStatus::Paused);
                 info!("Pausing group {group_name}");
 
                 let filtered_tasks = state.filter_tasks_of_group(
@@ -39,7 +40,8 @@ impl TaskHandler {
             }
             TaskSelection::All => {
                 // Pause all groups, since we're pausing the whole daemon.
-                state.set_status_for_all_groups(GroupStatus::Paused);
+                self.events
+                    .set_status_for_all_groups(&mut state, GroupStatus::Paused);
 
                 info!("Pausing everything");
                 self.children.all_task_ids()
@@ -53,7 +55,7 @@ impl TaskHandler {
             }
         }
 
-        ok_or_shutdown!(self, save_state(&state, &self.settings));
+        ok_or_shutdown!(self, save_state(&state, &self.store));
     }
     /// Pause a specific task.
     /// Send a signal to the process to actually pause the OS process.
@@ -62,7 +64,7 @@ impl TaskHandler {
             Err(err) => error!("Failed pausing task {id}: {err:?}"),
             Ok(success) => {
                 if success {
-                    state.change_status(id, TaskStatus::Paused);
+                    self.events.change_status(state, id, TaskStatus::Paused);
                 }
             }
         }
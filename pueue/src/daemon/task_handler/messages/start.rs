@@ -32,7 +32,7 @@ impl TaskHandler {
                         self.start_process(task_id, &mut state);
                     }
                 }
-                ok_or_shutdown!(self, save_state(&state, &self.settings));
+                ok_or_shutdown!(self, save_state(&state, &self.store));
                 return;
             }
             TaskSelection::Group(group_name) => {
@@ -43,7 +43,8 @@ impl TaskHandler {
                 };
 
                 // Set the group to running.
-                group.status = GroupStatus::Running;
+                self.events
+                    .set_group_status(&group_name, group, GroupStatus::Running);
                 info!("Resuming group {}", &group_name);
 
                 let filtered_tasks = state.filter_tasks_of_group(
@@ -56,7 +57,8 @@ impl TaskHandler {
             TaskSelection::All => {
                 // Resume all groups and the default queue
                 info!("Resuming everything");
-                state.set_status_for_all_groups(GroupStatus::Running);
+                self.events
+                    .set_status_for_all_groups(&mut state, GroupStatus::Running);
 
                 self.children.all_task_ids()
             }
@@ -67,7 +69,7 @@ impl TaskHandler {
             self.continue_task(&mut state, task_id);
         }
 
-        ok_or_shutdown!(self, save_state(&state, &self.settings));
+        ok_or_shutdown!(self, save_state(&state, &self.store));
     }
 
     /// Send a start signal to a paused task to continue execution.
@@ -91,7 +93,8 @@ impl TaskHandler {
         };
 
         if success {
-            state.change_status(task_id, TaskStatus::Running);
+            self.events
+                .change_status(state, task_id, TaskStatus::Running);
         }
     }
 }
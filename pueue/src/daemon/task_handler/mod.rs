@@ -3,12 +3,14 @@ use std::path::PathBuf;
 use std::process::Child;
 use std::process::Stdio;
 use std::sync::mpsc::{Receiver, SendError, Sender};
+use std::thread::JoinHandle;
+use std::time::Instant;
 
 use anyhow::Result;
 use chrono::prelude::*;
 use command_group::CommandGroup;
 use handlebars::Handlebars;
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 
 use pueue_lib::log::*;
 use pueue_lib::network::message::*;
@@ -18,8 +20,10 @@ use pueue_lib::settings::Settings;
 use pueue_lib::state::{GroupStatus, SharedState};
 use pueue_lib::task::{Task, TaskResult, TaskStatus};
 
+use crate::daemon::events::EventSender;
 use crate::daemon::pid::cleanup_pid_file;
 use crate::daemon::state_helper::{reset_state, save_state};
+use crate::daemon::state_store::SharedStore;
 
 mod callback;
 /// A helper newtype struct, which implements convenience methods for our child process management
@@ -32,10 +36,23 @@ mod finish_task;
 /// This module contains all logic that's triggered by messages received via the mpsc channel.
 /// These messages are sent by the threads that handle the client messages.
 mod messages;
+/// Logic for writing the output of tasks to their log files.
+mod output;
+/// Logic for tracking tasks that kept running while the daemon has been restarted.
+mod reattach;
+/// Logic for automatically removing finished tasks.
+mod retention;
+/// Logic for creating tasks from recurring schedules.
+mod schedule;
 /// Everything regarding actually spawning task processes.
 mod spawn_task;
+/// Logic for terminating tasks that exceeded their timeout.
+mod timeout;
 
 use self::children::Children;
+use self::output::LogCompression;
+pub use self::reattach::check_reattach_shell;
+use self::reattach::ReattachedTask;
 
 /// This is a little helper macro, which looks at a critical result and shuts the
 /// TaskHandler down, if an error occurred. This is mostly used if the state cannot
@@ -86,12 +103,34 @@ pub struct TaskHandler {
     children: Children,
     /// These are the currently running callbacks. They're usually very short-lived.
     callbacks: Vec<Child>,
+    /// Tasks that kept running while the daemon has been restarted and to which we reattached.
+    /// These aren't our children, which is why only their process (group) id is known.
+    reattached: BTreeMap<usize, ReattachedTask>,
+    /// Tasks that exceeded their timeout and already received a SIGTERM.
+    /// The value is the point in time when the SIGTERM has been sent.
+    timed_out: HashMap<usize, DateTime<Local>>,
+    /// The threads that record the output of running tasks, whose output is piped through the
+    /// daemon.
+    output_recorders: HashMap<usize, Vec<JoinHandle<()>>>,
+    /// The log files of finished tasks that are currently compressed in the background.
+    log_compressions: HashMap<usize, LogCompression>,
+    /// The threads that move tasks, which have been removed due to a retention policy, to the
+    /// archive.
+    archive_writers: Vec<JoinHandle<()>>,
+    /// Whether a task finished since the retention policies have been applied the last time.
+    retention_check_due: bool,
+    /// The point in time at which the retention policies have been applied the last time.
+    last_retention_check: Instant,
     /// A simple flag which is used to signal that we're currently doing a full reset of the daemon.
     /// This flag prevents new tasks from being spawned.
     full_reset: bool,
     /// Whether we're currently in the process of a graceful shutdown.
     /// Depending on the shutdown type, we're exiting with different exitcodes.
     shutdown: Option<Shutdown>,
+    /// Notifies subscribed clients about the changes of the state.
+    events: EventSender,
+    /// Persists the state after each change.
+    store: SharedStore,
     /// The settings that are passed at program start.
     settings: Settings,
 
@@ -100,7 +139,13 @@ pub struct TaskHandler {
 }
 
 impl TaskHandler {
-    pub fn new(shared_state: SharedState, settings: Settings, receiver: Receiver<Message>) -> Self {
+    pub fn new(
+        shared_state: SharedState,
+        settings: Settings,
+        receiver: Receiver<Message>,
+        events: EventSender,
+        store: SharedStore,
+    ) -> Self {
         // Clone the pointer, as we need to regularly access it inside the TaskHandler.
         let state_clone = shared_state.clone();
         let state = state_clone.lock().unwrap();
@@ -111,14 +156,36 @@ impl TaskHandler {
             pools.insert(group.clone(), BTreeMap::new());
         }
 
+        // Reattach to all tasks that are still running from a previous session.
+        // `restore_state` only keeps such tasks running, if `reattach_tasks` is enabled.
+        let reattached = state
+            .tasks
+            .values()
+            .filter(|task| task.is_running())
+            .filter_map(|task| {
+                let pid = task.pid?;
+                let start = task.start;
+                Some((task.id, ReattachedTask { pid, start }))
+            })
+            .collect();
+
         TaskHandler {
             state: shared_state,
             receiver,
             children: Children(pools),
             callbacks: Vec::new(),
+            reattached,
+            timed_out: HashMap::new(),
+            output_recorders: HashMap::new(),
+            log_compressions: HashMap::new(),
+            archive_writers: Vec::new(),
+            retention_check_due: true,
+            last_retention_check: Instant::now(),
             full_reset: false,
             shutdown: None,
             pueue_directory: settings.shared.pueue_directory(),
+            events,
+            store,
             settings,
         }
     }
@@ -128,12 +195,18 @@ impl TaskHandler {
     ///
     /// - Receive and handle instructions from the client.
     /// - Handle finished tasks, i.e. cleanup processes, update statuses.
+    /// - Handle finished tasks we reattached to after a restart.
+    /// - Spawn the callbacks of tasks, whose logs have been compressed.
     /// - Callback handling logic. This is rather uncritical.
     /// - Enqueue any stashed processes which are ready for being queued.
+    /// - Create new tasks for due schedules.
+    /// - Terminate tasks that exceeded their timeout.
+    /// - Remove finished tasks according to the retention policies.
     /// - Ensure tasks with dependencies have no failed ancestors
     /// - Whether whe should perform a shutdown.
     /// - If the client requested a reset: reset the state if all children have been killed and handled.
     /// - Check whether we can spawn new tasks.
+    /// - Notify subscribed clients about all changes of the state.
     ///
     /// This first step waits for 200ms while receiving new messages.
     /// This prevents this loop from running hot, but also means that we only check if a new task
@@ -142,8 +215,14 @@ impl TaskHandler {
         loop {
             self.receive_messages();
             self.handle_finished_tasks();
+            self.handle_reattached_tasks();
+            self.check_log_compressions();
             self.check_callbacks();
             self.enqueue_delayed_tasks();
+            self.check_schedules();
+            self.check_timeouts();
+            self.rotate_logs();
+            self.apply_retention_policies();
             self.check_failed_dependencies();
 
             if self.shutdown.is_some() {
@@ -164,9 +243,15 @@ impl TaskHandler {
     /// Initiate shutdown, which includes killing all children and pausing all groups.
     /// We don't have to pause any groups, as no new tasks will be spawned during shutdown anyway.
     /// Any groups with queued tasks, will be automatically paused on state-restoration.
+    ///
+    /// If `reattach_tasks` is enabled, all tasks are kept running, so we can reattach to them
+    /// once the daemon is started again.
     fn initiate_shutdown(&mut self, shutdown: Shutdown) {
         self.shutdown = Some(shutdown);
 
+        if self.settings.daemon.reattach_tasks {
+            return;
+        }
         self.kill(TaskSelection::All, false, None);
     }
 
@@ -175,7 +260,20 @@ impl TaskHandler {
     /// Once they're, we do some cleanup and exit.
     fn handle_shutdown(&mut self) {
         // There are still active tasks. Continue waiting until they're killed and cleaned up.
-        if self.children.has_active_tasks() {
+        // Tasks that should be reattached to are left running on purpose.
+        if self.children.has_active_tasks() && !self.settings.daemon.reattach_tasks {
+            return;
+        }
+        // Don't leave any half-compressed logs behind.
+        if !self.log_compressions.is_empty() {
+            return;
+        }
+        // Don't leave any half-written archive behind.
+        if self
+            .archive_writers
+            .iter()
+            .any(|writer| !writer.is_finished())
+        {
             return;
         }
 
@@ -209,12 +307,15 @@ impl TaskHandler {
     /// If that's the case, completely reset the state
     fn handle_reset(&mut self) {
         // Don't do any reset logic, if we aren't in reset mode or if some children are still up.
-        if self.children.has_active_tasks() {
+        if self.children.has_active_tasks()
+            || !self.reattached.is_empty()
+            || !self.log_compressions.is_empty()
+        {
             return;
         }
 
         let mut state = self.state.lock().unwrap();
-        if let Err(error) = reset_state(&mut state, &self.settings) {
+        if let Err(error) = reset_state(&mut state, &self.store, &self.settings, &self.events) {
             error!("Failed to reset state with error: {error:?}");
         };
 
@@ -246,7 +347,7 @@ impl TaskHandler {
                 if time <= Local::now() {
                     info!("Enqueuing delayed task : {}", task.id);
 
-                    task.status = TaskStatus::Queued;
+                    self.events.set_task_status(task, TaskStatus::Queued);
                     task.enqueued_at = Some(Local::now());
                     changed = true;
                 }
@@ -254,7 +355,7 @@ impl TaskHandler {
         }
         // Save the state if a task has been enqueued
         if changed {
-            ok_or_shutdown!(self, save_state(&state, &self.settings));
+            ok_or_shutdown!(self, save_state(&state, &self.store));
         }
     }
 
@@ -268,6 +369,16 @@ impl TaskHandler {
 
                 Ok(true)
             }
+            None if self.reattached.contains_key(&id) => {
+                let Some(pgid) = self.reattached_process_group(id) else {
+                    warn!("Reattached task {id} has already finished");
+                    return Ok(false);
+                };
+                debug!("Executing action {action:?} to reattached task {id}");
+                send_signal_to_process_group(pgid, &action)?;
+
+                Ok(true)
+            }
             None => {
                 error!("Tried to execute action {action:?} to non existing task {id}");
                 Ok(false)
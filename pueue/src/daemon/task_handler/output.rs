@@ -0,0 +1,272 @@
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use std::process::Child;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use pueue_lib::error::Error;
+
+use super::*;
+
+/// The longest time we wait for the remaining output of a finished task to be recorded.
+/// Processes that have been forked by the task may still hold on to its output pipes.
+const RECORDER_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Lines longer than this are written in chunks.
+/// This way, output without any newlines doesn't pile up in memory.
+const MAX_LINE_LENGTH: u64 = 64 * 1024;
+
+type SharedLogWriter = Arc<Mutex<TimestampedLogWriter>>;
+
+/// The log files a new process writes its `(stdout, stderr)` to.
+pub struct TaskOutput {
+    pub stdout: Stdio,
+    pub stderr: Stdio,
+    /// Only set, if the output is piped through the daemon to record timestamps.
+    pub recorder: Option<OutputRecorder>,
+}
+
+impl TaskOutput {
+    /// Create the log files of a task.
+    /// Any existing log files of a previous run are removed.
+    pub fn create(
+        task_id: usize,
+        pueue_directory: &Path,
+        separate_output: bool,
+        record_timestamps: bool,
+        rotation: Option<LogRotation>,
+    ) -> Result<TaskOutput, Error> {
+        clean_log_handles(task_id, pueue_directory);
+
+        if !record_timestamps {
+            let (stdout, stderr) = if separate_output {
+                create_separate_log_file_handles(task_id, pueue_directory)?
+            } else {
+                create_log_file_handles(task_id, pueue_directory)?
+            };
+
+            return Ok(TaskOutput {
+                stdout: Stdio::from(stdout),
+                stderr: Stdio::from(stderr),
+                recorder: None,
+            });
+        }
+
+        // Both streams share a writer, if they're written to the same log file.
+        let (stdout, stderr) = if separate_output {
+            (
+                TimestampedLogWriter::create(task_id, pueue_directory, LogFile::Stdout, rotation)?,
+                Some(TimestampedLogWriter::create(
+                    task_id,
+                    pueue_directory,
+                    LogFile::Stderr,
+                    rotation,
+                )?),
+            )
+        } else {
+            let writer = TimestampedLogWriter::create(
+                task_id,
+                pueue_directory,
+                LogFile::Combined,
+                rotation,
+            )?;
+            (writer, None)
+        };
+        let stdout = Arc::new(Mutex::new(stdout));
+        let stderr = match stderr {
+            Some(writer) => Arc::new(Mutex::new(writer)),
+            None => stdout.clone(),
+        };
+
+        Ok(TaskOutput {
+            stdout: Stdio::piped(),
+            stderr: Stdio::piped(),
+            recorder: Some(OutputRecorder { stdout, stderr }),
+        })
+    }
+}
+
+/// Writes the piped output of a process to its log files, line by line.
+pub struct OutputRecorder {
+    stdout: SharedLogWriter,
+    stderr: SharedLogWriter,
+}
+
+impl OutputRecorder {
+    /// Start recording the output of the spawned process.
+    /// Each output pipe is read by its own thread, which runs until the pipe is closed.
+    pub fn start(self, child: &mut Child) -> Vec<JoinHandle<()>> {
+        let mut threads = Vec::new();
+        if let Some(stdout) = child.stdout.take() {
+            threads.push(record_lines(stdout, self.stdout));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            threads.push(record_lines(stderr, self.stderr));
+        }
+
+        threads
+    }
+}
+
+/// Read the output of a pipe line by line and write it to the log file.
+/// Lines longer than [MAX_LINE_LENGTH] are split.
+fn record_lines<R: Read + Send + 'static>(pipe: R, writer: SharedLogWriter) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(pipe);
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            match reader
+                .by_ref()
+                .take(MAX_LINE_LENGTH)
+                .read_until(b'\n', &mut line)
+            {
+                Ok(0) => return,
+                Ok(_) => {
+                    if let Err(err) = writer.lock().unwrap().write_line(&line) {
+                        error!("Failed to write task output to log file: {err}");
+                        return;
+                    }
+                }
+                Err(err) => {
+                    error!("Failed to read task output: {err}");
+                    return;
+                }
+            }
+        }
+    })
+}
+
+impl TaskHandler {
+    /// Wait until all output of the finished tasks has been recorded.
+    /// This ensures that their logs are complete, before they're handed to callbacks or clients.
+    ///
+    /// Don't call this while holding the state lock, as this waits up to [RECORDER_TIMEOUT].
+    pub(super) fn finish_output_recording(&mut self, task_ids: &[usize]) {
+        let threads: Vec<(usize, Vec<JoinHandle<()>>)> = task_ids
+            .iter()
+            .filter_map(|task_id| {
+                let threads = self.output_recorders.remove(task_id)?;
+                Some((*task_id, threads))
+            })
+            .collect();
+
+        // All tasks share the same timeout, as their output is recorded in parallel.
+        let start = Instant::now();
+        for (task_id, threads) in threads {
+            while threads.iter().any(|thread| !thread.is_finished()) {
+                if start.elapsed() > RECORDER_TIMEOUT {
+                    info!("Output of task {task_id} is still being recorded after it finished.");
+                    break;
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+        }
+    }
+}
+
+/// The log files of a finished task, which are being compressed in the background.
+pub struct LogCompression {
+    thread: JoinHandle<()>,
+    /// The finished task, whose callback is spawned once its logs have been compressed.
+    task: Task,
+}
+
+impl TaskHandler {
+    /// Compress the log files of a finished task, if the daemon is configured to do so, and
+    /// spawn its callback.
+    ///
+    /// Compressing large logs takes a while, which is why it's done by a background thread.
+    /// As callbacks may read the logs, they're only spawned once the compression is done.
+    pub(super) fn compress_logs_and_spawn_callback(&mut self, task: &Task) {
+        if !self.settings.daemon.compress_finished_logs {
+            self.spawn_callback(task);
+            return;
+        }
+
+        let task_id = task.id;
+        let files = LogFile::for_task(task, None);
+        let pueue_directory = self.pueue_directory.clone();
+        let thread = thread::spawn(move || {
+            for file in files {
+                if let Err(err) = compress_log_file(task_id, &pueue_directory, file) {
+                    error!("Failed to compress log of task {task_id}: {err}");
+                }
+            }
+        });
+
+        let compression = LogCompression {
+            thread,
+            task: task.clone(),
+        };
+        self.log_compressions.insert(task_id, compression);
+    }
+
+    /// Spawn the callbacks of all tasks, whose logs have been compressed in the meantime.
+    pub fn check_log_compressions(&mut self) {
+        let finished: Vec<usize> = self
+            .log_compressions
+            .iter()
+            .filter(|(_, compression)| compression.thread.is_finished())
+            .map(|(task_id, _)| *task_id)
+            .collect();
+
+        for task_id in finished {
+            self.finish_log_compression(task_id);
+        }
+    }
+
+    /// Wait until the logs of a task have been compressed and spawn its callback.
+    /// This has to be done before the task is started again, as that recreates its log files.
+    pub(super) fn finish_log_compression(&mut self, task_id: usize) {
+        let Some(compression) = self.log_compressions.remove(&task_id) else {
+            return;
+        };
+
+        if compression.thread.join().is_err() {
+            error!("Thread compressing the logs of task {task_id} panicked");
+        }
+        self.spawn_callback(&compression.task);
+    }
+
+    /// Rotate the log files of running tasks, that grew beyond the configured size.
+    ///
+    /// Tasks whose output is piped through the daemon are skipped, as their log files are
+    /// rotated while writing.
+    pub fn rotate_logs(&mut self) {
+        let Some(rotation) = self.settings.daemon.log_rotation() else {
+            return;
+        };
+
+        let tasks: Vec<Task> = {
+            let state = self.state.lock().unwrap();
+            state
+                .tasks
+                .values()
+                .filter(|task| task.is_running())
+                .filter(|task| !self.output_recorders.contains_key(&task.id))
+                .cloned()
+                .collect()
+        };
+
+        for task in tasks {
+            for file in LogFile::for_task(&task, None) {
+                let path = get_log_file_path(task.id, &self.pueue_directory, file);
+                let Ok(metadata) = path.metadata() else {
+                    continue;
+                };
+                if metadata.len() < rotation.max_size {
+                    continue;
+                }
+
+                debug!("Rotating {file:?} log of task {}", task.id);
+                if let Err(err) =
+                    rotate_log_file(task.id, &self.pueue_directory, file, rotation.count)
+                {
+                    error!("Failed to rotate log of task {}: {err}", task.id);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use std::fs::{read_to_string, remove_file};
+use std::path::Path;
+
+use anyhow::bail;
+use pueue_lib::state::State;
+
+use super::finish_task::schedule_retry;
+use super::*;
+
+use crate::daemon::metrics::record_finished_task;
+use crate::daemon::state_helper::{pause_on_failure, save_state};
+use crate::ok_or_shutdown;
+
+/// The maximum difference between the start of a task and the start of the process with
+/// the task's pid, for the process to be considered the task's process.
+/// The task's start is set right after its process has been spawned.
+const MAX_START_DIFFERENCE_MILLIS: i64 = 5000;
+
+/// The shells that understand the syntax of [wrap_command_with_exit_code_file].
+const POSIX_SHELLS: [&str; 7] = ["sh", "bash", "dash", "zsh", "ksh", "mksh", "ash"];
+
+/// A task that kept running while the daemon has been restarted and to which we reattached.
+pub struct ReattachedTask {
+    /// The id of the task's process, which is also the id of its process group.
+    pub pid: u32,
+    /// The point in time at which the task has been started.
+    pub start: Option<DateTime<Local>>,
+}
+
+/// Get the path to the file, into which the exit code of a task is written.
+pub fn get_exit_code_path(task_id: usize, path: &Path) -> PathBuf {
+    path.join("task_logs").join(format!("{task_id}.exit"))
+}
+
+/// Wrap the command of a task, so that its exit code is written to a file once it finishes.
+///
+/// Processes we reattached to after a restart are no longer children of the daemon,
+/// which is why we cannot `wait` on them to get their exit code.
+pub fn wrap_command_with_exit_code_file(command: &str, exit_code_path: &Path) -> String {
+    let exit_code_path = shell_escape::escape(exit_code_path.to_string_lossy());
+    format!(
+        "(\n{command}\n)\npueue_exit_code=$?\necho $pueue_exit_code > {exit_code_path}\nexit $pueue_exit_code"
+    )
+}
+
+/// Make sure that the configured `shell_command` understands the POSIX shell syntax of
+/// [wrap_command_with_exit_code_file], if tasks should survive a restart of the daemon.
+pub fn check_reattach_shell(settings: &Settings) -> Result<()> {
+    if !settings.daemon.reattach_tasks || !cfg!(unix) {
+        return Ok(());
+    }
+
+    let shell_command = get_shell_command(settings);
+    let shell = shell_command
+        .first()
+        .and_then(|program| Path::new(program).file_name())
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if !POSIX_SHELLS.contains(&shell.as_str()) {
+        bail!(
+            "The `reattach_tasks` setting requires a POSIX compatible `shell_command`, such as `sh` or `bash`. Got: {shell_command:?}"
+        );
+    }
+
+    Ok(())
+}
+
+/// Read the exit code of a task that has been written by [wrap_command_with_exit_code_file].
+fn read_exit_code(exit_code_path: &Path) -> Option<i32> {
+    read_to_string(exit_code_path).ok()?.trim().parse().ok()
+}
+
+/// Remove the exit code file of a task, if it exists.
+pub fn clean_exit_code_file(task_id: usize, path: &Path) {
+    let exit_code_path = get_exit_code_path(task_id, path);
+    if exit_code_path.exists() {
+        if let Err(err) = remove_file(exit_code_path) {
+            error!("Failed to remove exit code file for task {task_id} with error {err:?}");
+        };
+    }
+}
+
+impl TaskHandler {
+    /// Get the process group id of a task we reattached to, if it's still running.
+    ///
+    /// We only know the pid of such tasks, which might have been reused by an unrelated
+    /// process in the meantime, e.g. after a reboot.
+    /// Hence, a task is only considered running, if it didn't write its exit code yet and if
+    /// the process with its pid has been started together with the task.
+    /// Tasks for which this returns `None` must never be signaled.
+    pub fn reattached_process_group(&self, task_id: usize) -> Option<u32> {
+        let task = self.reattached.get(&task_id)?;
+        if get_exit_code_path(task_id, &self.pueue_directory).exists() {
+            return None;
+        }
+        if !process_exists(task.pid) {
+            return None;
+        }
+
+        // The start time of processes can't be determined on all platforms.
+        if let (Some(process_start), Some(task_start)) = (process_start_time(task.pid), task.start)
+        {
+            let difference = task_start.signed_duration_since(process_start);
+            if difference.num_milliseconds().abs() > MAX_START_DIFFERENCE_MILLIS {
+                warn!(
+                    "Process {} isn't the process of task {task_id}. The task has probably finished while the daemon was down",
+                    task.pid
+                );
+                return None;
+            }
+        }
+
+        Some(task.pid)
+    }
+
+    /// Get the amount of reattached tasks that're still running in a specific group.
+    pub fn reattached_in_group(&self, state: &State, group: &str) -> usize {
+        self.reattached
+            .keys()
+            .filter_map(|task_id| state.tasks.get(task_id))
+            .filter(|task| task.group == group)
+            .count()
+    }
+
+    /// Check whether any of the tasks we reattached to after a restart have finished.
+    ///
+    /// These processes aren't our children, so we can only check whether they're still alive.
+    /// Their result is then determined by the exit code file of the task.
+    /// Tasks without such a file are considered to be killed.
+    pub fn handle_reattached_tasks(&mut self) {
+        let finished: Vec<(usize, u32)> = self
+            .reattached
+            .iter()
+            .filter(|(task_id, _)| self.reattached_process_group(**task_id).is_none())
+            .map(|(task_id, task)| (*task_id, task.pid))
+            .collect();
+
+        // Nothing to do. Early return
+        if finished.is_empty() {
+            return;
+        }
+
+        // Clone the state ref, so we don't have two mutable borrows later on.
+        let state_ref = self.state.clone();
+        let mut state = state_ref.lock().unwrap();
+
+        for (task_id, pid) in finished {
+            info!("Reattached task {task_id} with pid {pid} just finished");
+            self.reattached.remove(&task_id);
+
+            let exit_code_path = get_exit_code_path(task_id, &self.pueue_directory);
+            let result = if self.timed_out.remove(&task_id).is_some() {
+                TaskResult::TimedOut
+            } else {
+                match read_exit_code(&exit_code_path) {
+                    Some(0) => TaskResult::Success,
+                    Some(exit_code) => TaskResult::Failed(exit_code),
+                    None => TaskResult::Killed,
+                }
+            };
+            clean_exit_code_file(task_id, &self.pueue_directory);
+
+            // The task might have been removed in the meantime.
+            let Some(task) = state.tasks.get_mut(&task_id) else {
+                continue;
+            };
+            task.pid = None;
+
+            if schedule_retry(task, &result, &self.events) {
+                continue;
+            }
+
+            self.events
+                .set_task_status(task, TaskStatus::Done(result.clone()));
+            task.end = Some(Local::now());
+            record_finished_task(task);
+            self.retention_check_due = true;
+            self.compress_logs_and_spawn_callback(task);
+
+            let group = task.group.clone();
+            if matches!(result, TaskResult::Failed(_) | TaskResult::TimedOut) {
+                pause_on_failure(&mut state, &self.settings, &group, &self.events);
+            }
+        }
+
+        ok_or_shutdown!(self, save_state(&state, &self.store));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn reattach_shell() {
+        let mut settings = Settings::default();
+        settings.daemon.reattach_tasks = true;
+        assert!(check_reattach_shell(&settings).is_ok());
+
+        settings.daemon.shell_command = Some(vec![
+            "/usr/bin/bash".into(),
+            "-c".into(),
+            "{{ pueue_command_string }}".into(),
+        ]);
+        assert!(check_reattach_shell(&settings).is_ok());
+
+        settings.daemon.shell_command = Some(vec![
+            "fish".into(),
+            "-c".into(),
+            "{{ pueue_command_string }}".into(),
+        ]);
+        assert!(check_reattach_shell(&settings).is_err());
+    }
+}
@@ -0,0 +1,130 @@
+use std::thread;
+use std::time::Duration;
+
+use pueue_lib::settings::Daemon;
+
+use super::*;
+
+use crate::daemon::state_helper::{
+    archive_removed_tasks, is_task_removable, save_state, LockedState,
+};
+use crate::ok_or_shutdown;
+
+/// How often the retention policies are applied, even if no task finished in the meantime.
+/// This is necessary to remove tasks that exceeded their maximum age.
+const RETENTION_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+impl TaskHandler {
+    /// Remove all finished tasks and their logs, that are no longer covered by the retention
+    /// policy of their group.
+    ///
+    /// Tasks that other unfinished tasks depend on are kept, just like `pueue clean` does.
+    /// The policies are only applied once a task finished or [RETENTION_CHECK_INTERVAL] passed.
+    pub fn apply_retention_policies(&mut self) {
+        if !self.retention_check_due
+            && self.last_retention_check.elapsed() < RETENTION_CHECK_INTERVAL
+        {
+            return;
+        }
+        self.retention_check_due = false;
+        self.last_retention_check = Instant::now();
+        self.archive_writers.retain(|writer| !writer.is_finished());
+
+        let daemon_settings = &self.settings.daemon;
+        if daemon_settings.retention.is_empty()
+            && daemon_settings
+                .group_retention
+                .values()
+                .all(|policy| policy.is_empty())
+        {
+            return;
+        }
+
+        let state_clone = self.state.clone();
+        let mut state = state_clone.lock().unwrap();
+
+        let removed: Vec<usize> = expired_tasks(&state, daemon_settings, Local::now())
+            .into_iter()
+            .filter(|task_id| is_task_removable(&state, task_id, &[]))
+            .collect();
+
+        // Nothing to do. Early return
+        if removed.is_empty() {
+            return;
+        }
+
+        // Keep the tasks, if they couldn't be archived.
+        let archive = match archive_removed_tasks(&state, &removed, &self.settings) {
+            Ok(archive) => archive,
+            Err(err) => {
+                error!("Failed to apply retention policies: {err:?}");
+                return;
+            }
+        };
+
+        for task_id in &removed {
+            state.tasks.remove(task_id);
+            self.events.task_removed(*task_id);
+            clean_log_handles(*task_id, &self.pueue_directory);
+        }
+
+        debug!("Removed tasks due to the retention policy: {removed:?}");
+        ok_or_shutdown!(self, save_state(&state, &self.store));
+        drop(state);
+
+        // Compressing the logs takes a while, which is why it's done in the background.
+        if let Some(archive) = archive {
+            let writer = thread::spawn(move || {
+                if let Err(err) = archive.write() {
+                    error!("Failed to archive removed tasks: {err}");
+                }
+            });
+            self.archive_writers.push(writer);
+        }
+    }
+}
+
+/// Get the ids of all finished tasks, that should be removed due to their group's retention
+/// policy.
+fn expired_tasks(state: &LockedState, settings: &Daemon, now: DateTime<Local>) -> Vec<usize> {
+    // Collect the finished tasks of each group, that may be removed at all.
+    let mut finished: BTreeMap<&str, Vec<&Task>> = BTreeMap::new();
+    for task in state.tasks.values() {
+        let TaskStatus::Done(result) = &task.status else {
+            continue;
+        };
+        let policy = settings.retention_policy(&task.group);
+        if policy.is_empty() || (policy.successful_only && !matches!(result, TaskResult::Success)) {
+            continue;
+        }
+
+        finished.entry(&task.group).or_default().push(task);
+    }
+
+    let mut expired = Vec::new();
+    for (group, mut tasks) in finished {
+        let policy = settings.retention_policy(group);
+
+        // Sort the tasks, so that the ones that finished last come first.
+        tasks.sort_by_key(|task| std::cmp::Reverse((task.end, task.id)));
+
+        for (index, task) in tasks.iter().enumerate() {
+            let too_many = policy
+                .max_finished_tasks
+                .map_or(false, |max_finished_tasks| index >= max_finished_tasks);
+            let too_old = match (policy.max_age_days, task.end) {
+                (Some(days), Some(end)) => {
+                    let age = now.signed_duration_since(end).num_days();
+                    u64::try_from(age).map_or(false, |age| age >= days)
+                }
+                _ => false,
+            };
+
+            if too_many || too_old {
+                expired.push(task.id);
+            }
+        }
+    }
+
+    expired
+}
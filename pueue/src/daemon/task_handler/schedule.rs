@@ -0,0 +1,73 @@
+use pueue_lib::state::PUEUE_DEFAULT_GROUP;
+
+use super::*;
+
+use crate::ok_or_shutdown;
+
+impl TaskHandler {
+    /// Check whether any schedules are due and create their tasks.
+    ///
+    /// Every run creates a fresh task from the schedule's template via [Task::from_task].
+    /// Runs are skipped, if the schedule should only run once at a time and the task of the
+    /// previous run is still queued or running.
+    pub fn check_schedules(&mut self) {
+        let state_clone = self.state.clone();
+        let mut state = state_clone.lock().unwrap();
+
+        let now = Local::now();
+        let due: Vec<usize> = state
+            .schedules
+            .values()
+            .filter(|schedule| !schedule.paused)
+            .filter(|schedule| schedule.next_run.map_or(false, |next_run| next_run <= now))
+            .map(|schedule| schedule.id)
+            .collect();
+
+        // Nothing to do. Early return
+        if due.is_empty() {
+            return;
+        }
+
+        for schedule_id in due {
+            let schedule = state.schedules.get(&schedule_id).unwrap().clone();
+
+            // Check if the previous instance is still active.
+            let previous_is_active = schedule
+                .last_task_id
+                .and_then(|task_id| state.tasks.get(&task_id))
+                .map_or(false, |task| task.is_running() || task.is_queued());
+
+            let task_id = if schedule.skip_if_running && previous_is_active {
+                info!(
+                    "Skipping run of schedule {schedule_id}, as its previous task is still active"
+                );
+                schedule.last_task_id
+            } else {
+                let mut task = Task::from_task(&schedule.task);
+                task.enqueued_at = Some(now);
+                // The group might have been removed in the meantime.
+                if !state.groups.contains_key(&task.group) {
+                    task.group = PUEUE_DEFAULT_GROUP.into();
+                }
+
+                let task_id = state.add_task(task);
+                self.events.task_added(&state.tasks[&task_id]);
+                info!("Schedule {schedule_id} created task {task_id}");
+                Some(task_id)
+            };
+
+            let schedule = state.schedules.get_mut(&schedule_id).unwrap();
+            schedule.last_run = Some(now);
+            schedule.last_task_id = task_id;
+            schedule.next_run = match schedule.trigger.next_after(now) {
+                Ok(next_run) => next_run,
+                Err(err) => {
+                    error!("Failed to determine next run of schedule {schedule_id}: {err}");
+                    None
+                }
+            };
+        }
+
+        ok_or_shutdown!(self, save_state(&state, &self.store));
+    }
+}
@@ -1,5 +1,13 @@
+use std::cmp::Ordering;
+
+use pueue_lib::resources::{resources_fit, Resources};
+use pueue_lib::state::State;
+
+use super::output::TaskOutput;
+use super::reattach::{get_exit_code_path, wrap_command_with_exit_code_file};
 use super::*;
 
+use crate::daemon::metrics::record_finished_task;
 use crate::daemon::state_helper::{pause_on_failure, save_state, LockedState};
 use crate::ok_or_shutdown;
 
@@ -8,6 +16,8 @@ impl TaskHandler {
     pub fn spawn_new(&mut self) {
         let cloned_state_mutex = self.state.clone();
         let mut state = cloned_state_mutex.lock().unwrap();
+        self.update_blocked_groups(&mut state);
+
         // Check whether a new task can be started.
         // Spawn tasks until we no longer have free slots available.
         while let Some(id) = self.get_next_task_id(&state) {
@@ -15,17 +25,74 @@ impl TaskHandler {
         }
     }
 
+    /// Check the system's load against the configured limits and remember for each group,
+    /// whether it's currently held back.
+    fn update_blocked_groups(&self, state: &mut LockedState) {
+        let daemon_settings = &self.settings.daemon;
+        let has_limits = !daemon_settings.system_limits.is_empty()
+            || daemon_settings
+                .group_system_limits
+                .values()
+                .any(|limits| !limits.is_empty());
+
+        // Only read the system's load, if there's anything to check it against.
+        let (load_average, available_memory) = if has_limits {
+            (load_average(), available_memory())
+        } else {
+            (None, None)
+        };
+
+        for (name, group) in state.groups.iter_mut() {
+            let blocked = daemon_settings
+                .system_limits
+                .exceeded(load_average, available_memory)
+                .or_else(|| {
+                    daemon_settings
+                        .group_system_limits
+                        .get(name)
+                        .and_then(|limits| limits.exceeded(load_average, available_memory))
+                });
+
+            if blocked != group.blocked {
+                match &blocked {
+                    Some(reason) => info!("Holding back tasks of group {name}: {reason}"),
+                    None => info!("Tasks of group {name} are no longer held back"),
+                }
+                group.blocked = blocked;
+            }
+        }
+    }
+
     /// Search and return the next task that can be started.
     /// Precondition for a task to be started:
     /// - is in Queued state
-    /// - There are free slots in the task's group
-    /// - The group is running
-    /// - has all its dependencies in `Done` state
+    /// - There are free slots in the task's group and in the daemon-wide limit
+    /// - The resources requested by the task are still available in its group and the daemon
+    /// - The group is running and isn't held back due to the system's load
+    /// - all its dependencies finished and fulfill their conditions
     ///
     /// Order at which tasks are picked (descending relevancy):
+    /// - Task of the group with the highest priority first
     /// - Task with highest priority first
     /// - Task with lowest ID first
     pub fn get_next_task_id(&mut self, state: &LockedState) -> Option<usize> {
+        // Don't start anything, if the daemon-wide limit has been reached.
+        if let Some(max_parallel_tasks) = state.max_parallel_tasks {
+            let running_tasks = self
+                .children
+                .0
+                .values()
+                .map(|children| children.len())
+                .sum::<usize>()
+                + self.reattached.len();
+            if running_tasks >= max_parallel_tasks {
+                return None;
+            }
+        }
+
+        let (group_usage, total_usage) = resource_usage(state);
+        let no_usage = Resources::new();
+
         // Get all tasks that could theoretically be started right now.
         let mut potential_tasks: Vec<&Task> = state
             .tasks
@@ -44,15 +111,16 @@ impl TaskHandler {
                     }
                 };
 
-                // Let's check if the group is running. If it isn't, simply return false.
-                if group.status != GroupStatus::Running {
+                // Let's check if the group is running and not held back. If it isn't, simply return false.
+                if group.status != GroupStatus::Running || group.blocked.is_some() {
                     return false;
                 }
 
                 // Get the currently running tasks by looking at the actually running processes.
                 // They're sorted by group, which makes this quite convenient.
+                // Tasks we reattached to after a restart occupy a slot as well.
                 let running_tasks = match self.children.0.get(&task.group) {
-                    Some(children) => children.len(),
+                    Some(children) => children.len() + self.reattached_in_group(state, &task.group),
                     None => {
                         error!(
                             "Got valid group {}, but no worker pool has been initialized. This is a bug!",
@@ -63,23 +131,47 @@ impl TaskHandler {
                 };
 
                 // Make sure there are free slots in the task's group
-                running_tasks < group.parallel_tasks
+                if running_tasks >= group.parallel_tasks {
+                    return false;
+                }
+
+                // Make sure the requested resources are still available.
+                let used_in_group = group_usage.get(&task.group).unwrap_or(&no_usage);
+                resources_fit(&task.resources, used_in_group, &group.resources)
+                    && resources_fit(&task.resources, &total_usage, &self.settings.daemon.resources)
             })
             .filter(|(_, task)| {
                 // Check whether all dependencies for this task are fulfilled.
                 task.dependencies
                     .iter()
                     .flat_map(|id| state.tasks.get(id))
-                    .all(|task| matches!(task.status, TaskStatus::Done(TaskResult::Success)))
+                    .all(|dependency| {
+                        task.dependency_condition(dependency.id)
+                            .is_fulfilled(&dependency.status)
+                    })
             })
             .map(|(_, task)| {task})
             .collect();
 
-        // Order the tasks based on their priortiy and their task id.
+        // Order the tasks based on the priority of their group, their priortiy and their task id.
+        // Tasks of groups with a higher priority go first, which decides which group gets
+        // the next free slot of the daemon-wide limit.
         // Tasks with higher priority go first.
         // Tasks with the same priorty are ordered by their id in ascending order, meaning that
         // tasks with smaller id will be processed first.
+        let group_priority = |task: &Task| {
+            state
+                .groups
+                .get(&task.group)
+                .map_or(0, |group| group.priority)
+        };
         potential_tasks.sort_by(|a, b| {
+            // Let the priority of the groups decide first.
+            let group_order = group_priority(b).cmp(&group_priority(a));
+            if group_order != Ordering::Equal {
+                return group_order;
+            }
+
             // If they have the same prio, decide the execution order by task_id!
             if a.priority == b.priority {
                 return a.id.cmp(&b.id);
@@ -113,11 +205,28 @@ impl TaskHandler {
             }
         };
 
+        // The logs of a previous run might still be compressed in the background.
+        self.finish_log_compression(task_id);
+
         // Try to get the log file to which the output of the process will be written to.
         // Panic if this doesn't work! This is unrecoverable.
-        let (stdout_log, stderr_log) = match create_log_file_handles(task_id, &self.pueue_directory)
-        {
-            Ok((out, err)) => (out, err),
+        // Recording timestamps requires the output to be piped through the daemon, which
+        // doesn't work with tasks that should survive a restart of the daemon.
+        let record_timestamps =
+            self.settings.daemon.log_timestamps && !self.settings.daemon.reattach_tasks;
+        let output = TaskOutput::create(
+            task_id,
+            &self.pueue_directory,
+            state.tasks[&task_id].separate_output,
+            record_timestamps,
+            self.settings.daemon.log_rotation(),
+        );
+        let TaskOutput {
+            stdout,
+            stderr,
+            recorder,
+        } = match output {
+            Ok(output) => output,
             Err(err) => {
                 panic!("Failed to create child log files: {err:?}");
             }
@@ -134,6 +243,15 @@ impl TaskHandler {
             )
         };
 
+        // Tasks might outlive the daemon, if we want to reattach to them after a restart.
+        // Their exit code is then written to a file, as we won't be able to `wait` on them.
+        let command = if self.settings.daemon.reattach_tasks && cfg!(unix) {
+            let exit_code_path = get_exit_code_path(task_id, &self.pueue_directory);
+            wrap_command_with_exit_code_file(&command, &exit_code_path)
+        } else {
+            command
+        };
+
         // Build the shell command that should be executed.
         let mut command = compile_shell_command(&self.settings, &command);
 
@@ -149,12 +267,12 @@ impl TaskHandler {
             .stdin(Stdio::piped())
             .env_clear()
             .envs(envs.clone())
-            .stdout(Stdio::from(stdout_log))
-            .stderr(Stdio::from(stderr_log))
+            .stdout(stdout)
+            .stderr(stderr)
             .group_spawn();
 
         // Check if the task managed to spawn
-        let child = match spawned_command {
+        let mut child = match spawned_command {
             Ok(child) => child,
             Err(err) => {
                 let error = format!("Failed to spawn child {task_id} with err: {err:?}");
@@ -164,31 +282,65 @@ impl TaskHandler {
                 // Update all necessary fields on the task.
                 let group = {
                     let task = state.tasks.get_mut(&task_id).unwrap();
-                    task.status = TaskStatus::Done(TaskResult::FailedToSpawn(error));
+                    self.events
+                        .set_task_status(task, TaskStatus::Done(TaskResult::FailedToSpawn(error)));
                     task.start = Some(Local::now());
                     task.end = Some(Local::now());
+                    record_finished_task(task);
+                    self.retention_check_due = true;
                     self.spawn_callback(task);
 
                     task.group.clone()
                 };
 
-                pause_on_failure(state, &self.settings, &group);
-                ok_or_shutdown!(self, save_state(state, &self.settings));
+                pause_on_failure(state, &self.settings, &group, &self.events);
+                ok_or_shutdown!(self, save_state(state, &self.store));
                 return;
             }
         };
 
+        // Start writing the piped output to the log files.
+        if let Some(recorder) = recorder {
+            let threads = recorder.start(child.inner());
+            self.output_recorders.insert(task_id, threads);
+        }
+
+        // Remember the process id, so we can reattach to the process after a restart.
+        let pid = child.id();
+
         // Save the process handle in our self.children datastructure.
         self.children.add_child(&group, worker_id, task_id, child);
 
         let task = state.tasks.get_mut(&task_id).unwrap();
+        task.pid = Some(pid);
         task.start = Some(Local::now());
-        task.status = TaskStatus::Running;
+        self.events.set_task_status(task, TaskStatus::Running);
         // Overwrite the task's environment variables with the new ones, containing the
         // PUEUE_WORKER_ID and PUEUE_GROUP variables.
         task.envs = envs;
 
         info!("Started task: {}", task.command);
-        ok_or_shutdown!(self, save_state(state, &self.settings));
+        ok_or_shutdown!(self, save_state(state, &self.store));
     }
 }
+
+/// Sum up the resources of all tasks that currently occupy them, i.e. running and paused tasks.
+/// Returns the usage of each group and the usage of the whole daemon.
+fn resource_usage(state: &State) -> (BTreeMap<String, Resources>, Resources) {
+    let mut group_usage: BTreeMap<String, Resources> = BTreeMap::new();
+    let mut total_usage = Resources::new();
+
+    for task in state.tasks.values() {
+        if !matches!(task.status, TaskStatus::Running | TaskStatus::Paused) {
+            continue;
+        }
+
+        let used_in_group = group_usage.entry(task.group.clone()).or_default();
+        for (name, amount) in task.resources.iter() {
+            *used_in_group.entry(name.clone()).or_default() += amount;
+            *total_usage.entry(name.clone()).or_default() += amount;
+        }
+    }
+
+    (group_usage, total_usage)
+}
@@ -0,0 +1,53 @@
+use pueue_lib::network::message::Signal;
+
+use super::*;
+
+impl TaskHandler {
+    /// Check whether any running tasks exceeded their timeout.
+    ///
+    /// Such tasks first receive a SIGTERM, which gives them a chance to shut down gracefully.
+    /// If they're still alive after the configured grace period, they're killed for good.
+    /// Their result is then set to [TaskResult::TimedOut] in `handle_finished_tasks`.
+    pub fn check_timeouts(&mut self) {
+        let now = Local::now();
+        let grace_period =
+            chrono::Duration::seconds(self.settings.daemon.timeout_grace_period as i64);
+
+        // Collect all tasks that exceeded their runtime.
+        // We need to release the state lock, before we start to send any signals.
+        let exceeded: Vec<usize> = {
+            let state = self.state.lock().unwrap();
+            state
+                .tasks
+                .values()
+                .filter(|task| task.is_running())
+                .filter(|task| match (task.timeout, task.start) {
+                    (Some(timeout), Some(start)) => {
+                        let timeout = timeout.min(i32::MAX as u64) as i64;
+                        start + chrono::Duration::seconds(timeout) <= now
+                    }
+                    _ => false,
+                })
+                .map(|task| task.id)
+                .collect()
+        };
+
+        for task_id in exceeded {
+            match self.timed_out.get(&task_id) {
+                // The task has already been asked to terminate. Kill it, once it's out of time.
+                Some(terminated_at) => {
+                    if *terminated_at + grace_period <= now {
+                        info!("Task {task_id} didn't exit after timeout grace period. Killing it.");
+                        self.kill_task(task_id);
+                    }
+                }
+                // The task just exceeded its timeout. Ask it to terminate gracefully.
+                None => {
+                    info!("Task {task_id} exceeded its timeout. Sending SIGTERM.");
+                    self.send_internal_signal(task_id, Signal::SigTerm);
+                    self.timed_out.insert(task_id, now);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use std::fs::write;
+
+use anyhow::Result;
+use pretty_assertions::assert_eq;
+
+use crate::client::helper::*;
+
+/// Tasks are read from a file with one JSON object per line.
+/// The options of the `add` command serve as defaults.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn add_tasks_from_file() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    let path = shared.pueue_directory().join("tasks.jsonl");
+    write(
+        &path,
+        r#"{"command": "ls", "label": "list", "priority": 2}
+
+{"command": "echo $KEY", "cwd": "/tmp", "env": {"KEY": "value"}, "after_entry": [0]}
+"#,
+    )?;
+    let path = path.to_string_lossy().to_string();
+    let output = run_client_command(
+        shared,
+        &[
+            "add",
+            "--stashed",
+            "--label",
+            "default",
+            "--from-file",
+            &path,
+        ],
+    )?;
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "New tasks added (ids 0, 1)."
+    );
+
+    let state = get_state(shared).await?;
+    let first = &state.tasks[&0];
+    assert_eq!(first.command, "ls");
+    assert_eq!(first.label, Some("list".to_string()));
+    assert_eq!(first.priority, 2);
+    assert!(first.is_stashed());
+
+    let second = &state.tasks[&1];
+    assert_eq!(second.label, Some("default".to_string()));
+    assert_eq!(second.path.to_string_lossy(), "/tmp");
+    assert_eq!(second.envs.get("KEY"), Some(&"value".to_string()));
+    assert_eq!(second.dependencies, vec![0]);
+    assert!(second.is_stashed());
+
+    Ok(())
+}
@@ -0,0 +1,136 @@
+use std::fs::write;
+
+use anyhow::{bail, Result};
+use pretty_assertions::assert_eq;
+
+use pueue_lib::settings::Shared;
+use pueue_lib::task::{TaskResult, TaskStatus};
+
+use crate::client::helper::*;
+
+/// Run a client command and make sure it succeeded.
+fn run_successful_command(shared: &Shared, args: &[&str]) -> Result<String> {
+    let output = run_client_command(shared, args)?;
+    if !output.status.success() {
+        bail!(
+            "Command {args:?} failed: {}",
+            String::from_utf8_lossy(&output.stdout)
+        );
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Parameters can be passed as ranges, lists and files.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn add_task_array_parameters() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    run_successful_command(
+        shared,
+        &["add", "--stashed", "--array", "0..10:5", "echo {{value}}"],
+    )?;
+    run_successful_command(
+        shared,
+        &["add", "--stashed", "--array", "a, b", "echo {{value}}"],
+    )?;
+
+    let path = shared.pueue_directory().join("parameters");
+    write(&path, "first\n\nsecond\n")?;
+    let path = path.to_string_lossy().to_string();
+    run_successful_command(
+        shared,
+        &["add", "--stashed", "--array-file", &path, "echo {{value}}"],
+    )?;
+
+    let state = get_state(shared).await?;
+    let commands: Vec<(usize, &str)> = state
+        .tasks
+        .values()
+        .map(|task| (task.array.as_ref().unwrap().id, task.command.as_str()))
+        .collect();
+    assert_eq!(
+        commands,
+        vec![
+            (0, "echo 0"),
+            (0, "echo 5"),
+            (0, "echo 10"),
+            (3, "echo a"),
+            (3, "echo b"),
+            (5, "echo first"),
+            (5, "echo second"),
+        ]
+    );
+
+    Ok(())
+}
+
+/// Arrays with too many parameters are rejected before they're expanded.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn reject_oversized_task_array() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    let output = run_client_command(
+        shared,
+        &["add", "--array", "1..1000000000", "echo {{value}}"],
+    )?;
+    assert!(!output.status.success());
+
+    let state = get_state(shared).await?;
+    assert!(state.tasks.is_empty());
+
+    Ok(())
+}
+
+/// Restarted tasks of an array stay members of that array.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn restart_task_array_member() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    run_successful_command(shared, &["add", "--immediate", "--array", "a,b", "true"])?;
+    for task_id in 0..2 {
+        wait_for_task_condition(shared, task_id, |task| task.is_done()).await?;
+    }
+
+    run_successful_command(shared, &["restart", "--stashed", "1"])?;
+
+    let state = get_state(shared).await?;
+    assert_eq!(state.tasks[&2].array, state.tasks[&1].array);
+
+    Ok(())
+}
+
+/// Task arrays can be killed and waited on as a unit and their status is summarized.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn handle_task_array_as_unit() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    run_successful_command(
+        shared,
+        &["add", "--immediate", "--array", "1..2", "sleep 60"],
+    )?;
+    for task_id in 0..2 {
+        wait_for_task_condition(shared, task_id, |task| task.is_running()).await?;
+    }
+
+    let output = run_successful_command(shared, &["status"])?;
+    assert!(output.contains("Array 0: 0/2 succeeded, 2 running"));
+
+    run_successful_command(shared, &["kill", "--array", "0"])?;
+    run_successful_command(shared, &["wait", "--array", "0"])?;
+
+    let state = get_state(shared).await?;
+    for task in state.tasks.values() {
+        assert_eq!(task.status, TaskStatus::Done(TaskResult::Killed));
+    }
+
+    // Unknown arrays are reported as such.
+    let output = run_client_command(shared, &["kill", "--array", "5"])?;
+    assert!(!output.status.success());
+
+    Ok(())
+}
@@ -0,0 +1,27 @@
+use anyhow::Result;
+use rstest::rstest;
+
+use crate::client::helper::*;
+
+/// A task can't be passed to more than one of the `--after*` options.
+#[rstest]
+#[case(&["--after", "0", "--after-failure", "0"])]
+#[case(&["--after-any", "0", "--after-exit-code", "0:1"])]
+#[case(&["--after-exit-code", "0:1", "--after-exit-code", "0:2"])]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn reject_multiple_conditions(#[case] options: &[&str]) -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+    assert_success(add_task(shared, "ls").await?);
+
+    let mut args = vec!["add"];
+    args.extend_from_slice(options);
+    args.extend_from_slice(&["--", "ls"]);
+    let output = run_client_command(shared, &args)?;
+    assert!(!output.status.success());
+
+    let state = get_state(shared).await?;
+    assert_eq!(state.tasks.len(), 1);
+
+    Ok(())
+}
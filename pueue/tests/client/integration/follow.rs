@@ -138,3 +138,31 @@ async fn fail_on_disappearing(#[case] read_local_logs: bool) -> Result<()> {
 
     Ok(())
 }
+
+/// Test that `follow --timestamps` prefixes each line with the time it has been written.
+#[rstest]
+#[case(true)]
+#[case(false)]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn timestamps(#[case] read_local_logs: bool) -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    settings.daemon.log_timestamps = true;
+    settings.client.read_local_logs = read_local_logs;
+    settings
+        .save(&Some(tempdir.path().join("pueue.yml")))
+        .context("Couldn't write pueue config to temporary directory")?;
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+    let shared = &daemon.settings.shared;
+
+    // Add a task and wait until it started.
+    assert_success(add_task(shared, "echo first && sleep 1 && printf second").await?);
+    wait_for_task_condition(shared, 0, |task| task.is_running()).await?;
+
+    let output = run_client_command(shared, &["follow", "--timestamps"])?;
+    assert_eq!(
+        super::log::timestamped_lines(&output.stdout),
+        vec!["first", "second"]
+    );
+
+    Ok(())
+}
@@ -75,3 +75,33 @@ async fn json() -> Result<()> {
 
     Ok(())
 }
+
+/// The daemon-wide limit and the priorities of groups are shown in the group overview.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn global_limit_and_priority() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    run_client_command(shared, &["parallel", "--global", "4"])?;
+    run_client_command(shared, &["group", "add", "urgent", "--priority", "5"])?;
+    wait_for_group(shared, "urgent").await?;
+    run_client_command(shared, &["group", "priority", "default", "-1"])?;
+
+    let output = run_client_command(shared, &["group"])?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Global limit: 4 parallel tasks across all groups"));
+    assert!(stdout.contains("Group \"urgent\" (1 parallel, priority 5)"));
+    assert!(stdout.contains("Group \"default\" (1 parallel, priority -1)"));
+
+    // Only the daemon-wide limit can be removed.
+    let output = run_client_command(shared, &["parallel", "0"])?;
+    assert!(
+        !output.status.success(),
+        "parallel 0 got an unexpected exit 0"
+    );
+    run_client_command(shared, &["parallel", "--global", "0"])?;
+    let state = get_state(shared).await?;
+    assert_eq!(state.max_parallel_tasks, None);
+
+    Ok(())
+}
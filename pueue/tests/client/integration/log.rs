@@ -149,3 +149,68 @@ async fn json() -> Result<()> {
 
     Ok(())
 }
+
+/// Get all lines of some output that are prefixed with a timestamp and strip the timestamps.
+pub fn timestamped_lines(stdout: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(stdout)
+        .lines()
+        .filter(|line| line.starts_with('['))
+        .filter_map(|line| line.split_once("] ").map(|(_, line)| line.to_string()))
+        .collect()
+}
+
+/// Calling `log --timestamps` prefixes each line of output with the time it has been written.
+#[rstest]
+#[case(true)]
+#[case(false)]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn timestamps(#[case] read_local_logs: bool) -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    settings.daemon.log_timestamps = true;
+    settings.client.read_local_logs = read_local_logs;
+    settings
+        .save(&Some(tempdir.path().join("pueue.yml")))
+        .context("Couldn't write pueue config to temporary directory")?;
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+    let shared = &daemon.settings.shared;
+
+    // Add a task and wait until it finishes.
+    assert_success(add_task(shared, "echo first && echo second").await?);
+    wait_for_task_condition(shared, 0, |task| task.is_done()).await?;
+
+    let output = run_client_command(shared, &["log", "--timestamps"])?;
+    assert_eq!(timestamped_lines(&output.stdout), vec!["first", "second"]);
+
+    // The plain log isn't affected.
+    let output = run_client_command(shared, &["log"])?;
+    assert!(timestamped_lines(&output.stdout).is_empty());
+
+    Ok(())
+}
+
+/// Compressed logs of finished tasks are read transparently, both locally and by the daemon.
+#[rstest]
+#[case(true)]
+#[case(false)]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn compressed(#[case] read_local_logs: bool) -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    settings.daemon.compress_finished_logs = true;
+    settings.client.read_local_logs = read_local_logs;
+    settings
+        .save(&Some(tempdir.path().join("pueue.yml")))
+        .context("Couldn't write pueue config to temporary directory")?;
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+    let shared = &daemon.settings.shared;
+
+    // Add a task and wait until it finishes.
+    assert_success(add_task(shared, "echo '1\n2\n3\n4\n5\n6\n7\n8\n9\n10'").await?);
+    wait_for_task_condition(shared, 0, |task| task.is_done()).await?;
+
+    let output = run_client_command(shared, &["log", "--lines=5"])?;
+
+    let context = get_task_context(&daemon.settings).await?;
+    assert_template_matches("log__last_lines", output.stdout, context)?;
+
+    Ok(())
+}
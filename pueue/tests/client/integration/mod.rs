@@ -1,5 +1,8 @@
+mod add_batch;
+mod array;
 mod completions;
 mod configuration;
+mod dependencies;
 mod edit;
 mod follow;
 mod group;
@@ -214,6 +214,30 @@ async fn filter_status(#[case] status: TaskStatus, #[case] match_count: usize) -
     Ok(())
 }
 
+/// Timed out tasks can be filtered explicitly, but they're also considered as failed.
+#[rstest]
+#[case("timed_out", 1)]
+#[case("failed", 2)]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn filter_status_timed_out(
+    #[case] status_filter: &'static str,
+    #[case] match_count: usize,
+) -> Result<()> {
+    let mut tasks = test_tasks();
+    let mut timed_out = build_task();
+    timed_out.id = 7;
+    timed_out.status = TaskStatus::Done(TaskResult::TimedOut);
+    tasks.push(timed_out);
+
+    let query_result = apply_query(&format!("status={status_filter}"))?;
+    let tasks = query_result.apply_filters(tasks);
+
+    assert_eq!(tasks.len(), match_count);
+    assert!(tasks.iter().any(|task| task.id == 7));
+
+    Ok(())
+}
+
 /// Filter tasks by label with the "contains" `%=` filter.
 #[rstest]
 #[case("%=", "label", 3)]
@@ -266,3 +290,47 @@ async fn filter_label(
 
     Ok(())
 }
+
+/// Filter tasks by their command.
+#[rstest]
+#[case("=", "sleep 60", 7)]
+#[case("!=", "sleep 60", 0)]
+#[case("%=", "sleep", 7)]
+#[case("%=", "echo", 0)]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn filter_command(
+    #[case] operator: &'static str,
+    #[case] command_filter: &'static str,
+    #[case] match_count: usize,
+) -> Result<()> {
+    let tasks = test_tasks_with_query(&format!("command{operator}{command_filter}"))?;
+
+    assert_eq!(
+        tasks.len(),
+        match_count,
+        "Got a different amount of tasks than expected for the command filter: {command_filter}."
+    );
+
+    Ok(())
+}
+
+/// Filter finished tasks by their exit code.
+/// Tasks without an exit code never match `=` and always match `!=`.
+#[rstest]
+#[case("=", "255", vec![0])]
+#[case("=", "0", vec![1])]
+#[case("!=", "0", vec![0, 2, 3, 4, 5, 6])]
+#[case("=", "-1", vec![])]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn filter_exit_code(
+    #[case] operator: &'static str,
+    #[case] exit_code_filter: &'static str,
+    #[case] expected_ids: Vec<usize>,
+) -> Result<()> {
+    let tasks = test_tasks_with_query(&format!("exit_code{operator}{exit_code_filter}"))?;
+
+    let ids: Vec<usize> = tasks.iter().map(|task| task.id).collect();
+    assert_eq!(ids, expected_ids);
+
+    Ok(())
+}
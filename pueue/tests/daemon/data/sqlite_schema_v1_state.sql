@@ -0,0 +1,12 @@
+PRAGMA user_version = 1;
+BEGIN TRANSACTION;
+CREATE TABLE groups (key TEXT PRIMARY KEY, data TEXT NOT NULL);
+INSERT INTO "groups" VALUES('default','{"status":"Running","parallel_tasks":1}');
+INSERT INTO "groups" VALUES('test','{"status":"Paused","parallel_tasks":2}');
+CREATE TABLE schedules (key INTEGER PRIMARY KEY, data TEXT NOT NULL);
+CREATE TABLE tasks (key INTEGER PRIMARY KEY, data TEXT NOT NULL);
+INSERT INTO "tasks" VALUES(0,'{"id":0,"original_command":"ls","command":"ls","path":"/home/nuke/.local/share/pueue","envs":{},"group":"default","dependencies":[],"label":null,"status":{"Done":"Success"},"prev_status":"Queued","start":"2022-05-09T18:41:29.273563806+02:00","end":"2022-05-09T18:41:29.473998692+02:00"}');
+INSERT INTO "tasks" VALUES(1,'{"id":1,"original_command":"ls","command":"ls","path":"/home/nuke/.local/share/pueue","envs":{"PUEUE_WORKER_ID":"0","PUEUE_GROUP":"test"},"group":"test","dependencies":[],"label":null,"status":{"Done":"Success"},"prev_status":"Queued","start":"2022-05-09T18:43:30.683677276+02:00","end":"2022-05-09T18:43:30.884243263+02:00"}');
+INSERT INTO "tasks" VALUES(2,'{"id":2,"original_command":"ls","command":"ls","path":"/home/nuke/.local/share/pueue","envs":{"PUEUE_WORKER_ID":"0","PUEUE_GROUP":"test"},"group":"test","dependencies":[],"label":null,"status":"Queued","prev_status":"Queued","start":null,"end":null}');
+INSERT INTO "tasks" VALUES(3,'{"id":3,"original_command":"ls stash_it","command":"ls stash_it","path":"/home/nuke/.local/share/pueue","envs":{},"group":"default","dependencies":[],"label":null,"status":{"Stashed":{"enqueue_at":null}},"prev_status":{"Stashed":{"enqueue_at":null}},"start":null,"end":null}');
+COMMIT;
@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use pretty_assertions::assert_eq;
+
+use pueue_lib::network::message::*;
+
+use crate::helper::*;
+
+/// Create a batch of the given messages without any shared environment variables.
+fn create_batch(tasks: Vec<AddMessage>) -> AddBatchMessage {
+    AddBatchMessage {
+        envs: HashMap::new(),
+        tasks,
+    }
+}
+
+/// All tasks of a batch are added at once and may depend on earlier entries of the batch.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_add_batch() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    // A task before the batch, so the ids of the batch's tasks differ from their positions.
+    assert_success(add_task(shared, "ls").await?);
+
+    let mut first = create_add_message(shared, "ls");
+    first.envs = HashMap::from([("KEY".into(), "task".into())]);
+    let mut array = create_add_message(shared, "echo {{value}}");
+    array.array = Some(vec!["a".into(), "b".into()]);
+    array.batch_dependencies = vec![0];
+    let mut last = create_add_message(shared, "ls");
+    last.dependencies = vec![0];
+    last.batch_dependencies = vec![0, 1];
+
+    let mut batch = create_batch(vec![first, array, last]);
+    batch.envs = HashMap::from([
+        ("KEY".into(), "shared".into()),
+        ("SHARED".into(), "shared".into()),
+    ]);
+    let response = send_message(shared, batch).await?;
+    assert_eq!(
+        response,
+        create_success_message("New tasks added (ids 1, 2, 3, 4).")
+    );
+
+    let state = get_state(shared).await?;
+    let dependencies: Vec<Vec<usize>> = state
+        .tasks
+        .values()
+        .map(|task| task.dependencies.clone())
+        .collect();
+    assert_eq!(
+        dependencies,
+        vec![vec![], vec![], vec![1], vec![1], vec![0, 1, 2, 3]]
+    );
+
+    // The shared environment variables are overwritten by those of the task.
+    let envs = &state.tasks[&1].envs;
+    assert_eq!(envs.get("KEY"), Some(&"task".to_string()));
+    assert_eq!(envs.get("SHARED"), Some(&"shared".to_string()));
+    let envs = &state.tasks[&4].envs;
+    assert_eq!(envs.get("KEY"), Some(&"shared".to_string()));
+
+    Ok(())
+}
+
+/// Invalid batches are rejected, without adding any tasks.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_invalid_batch() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    // Entries may only depend on earlier entries.
+    let mut first = create_add_message(shared, "ls");
+    first.batch_dependencies = vec![1];
+    let second = create_add_message(shared, "ls");
+    let message = create_batch(vec![first, second]);
+    assert_failure(send_message(shared, message).await?);
+
+    // All groups have to exist.
+    let first = create_add_message(shared, "ls");
+    let mut second = create_add_message(shared, "ls");
+    second.group = "doesnt_exist".into();
+    let message = create_batch(vec![first, second]);
+    assert_failure(send_message(shared, message).await?);
+
+    // Batches need tasks.
+    assert_failure(send_message(shared, create_batch(Vec::new())).await?);
+
+    // Single tasks cannot depend on entries of a batch.
+    let mut message = create_add_message(shared, "ls");
+    message.batch_dependencies = vec![0];
+    assert_failure(send_message(shared, message).await?);
+
+    let state = get_state(shared).await?;
+    assert!(state.tasks.is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use std::fs::read_to_string;
+
+use anyhow::{Context, Result};
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+
+use pueue_lib::network::message::*;
+
+use crate::helper::*;
+
+/// Operations that change the state are written to the audit log, together with the client's
+/// identity. Environment variables are redacted and read-only operations aren't logged.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_audit_log() -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    settings.daemon.audit_log = true;
+    settings
+        .save(&Some(tempdir.path().join("pueue.yml")))
+        .context("Couldn't write pueue config to temporary directory")?;
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+    let shared = &daemon.settings.shared;
+
+    assert_success(add_task(shared, "sleep 60").await?);
+    wait_for_task_condition(shared, 0, |task| task.is_running()).await?;
+    get_state(shared).await?;
+    let kill_message = KillMessage {
+        tasks: TaskSelection::TaskIds(vec![0]),
+        signal: None,
+    };
+    assert_success(send_message(shared, kill_message).await?);
+    // Failed operations are logged as well.
+    assert_failure(send_message(shared, Message::Remove(vec![5])).await?);
+
+    let audit_log = read_to_string(shared.pueue_directory().join("audit.jsonl"))?;
+    let entries: Vec<Value> = audit_log
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?;
+    // Skip the groups that are created by the test setup.
+    let entries: Vec<&Value> = entries
+        .iter()
+        .filter(|entry| entry["operation"] != "Group")
+        .collect();
+
+    let operations: Vec<(&str, bool)> = entries
+        .iter()
+        .map(|entry| {
+            (
+                entry["operation"].as_str().unwrap(),
+                entry["failed"].as_bool().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        operations,
+        vec![("Add", false), ("Kill", false), ("Remove", true)]
+    );
+
+    let add = entries[0];
+    assert_eq!(add["parameters"]["command"], "sleep 60");
+    assert_eq!(add["parameters"]["envs"], "[redacted]");
+    // The tests talk to the daemon via a unix socket from within this process.
+    #[cfg(not(target_os = "windows"))]
+    assert_eq!(add["client"]["pid"], std::process::id());
+
+    Ok(())
+}
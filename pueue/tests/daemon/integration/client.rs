@@ -0,0 +1,101 @@
+use anyhow::Result;
+use pretty_assertions::assert_eq;
+
+use pueue_lib::client::{BlockingPueueClient, PueueClient};
+use pueue_lib::error::Error;
+use pueue_lib::network::message::*;
+use pueue_lib::task::{TaskResult, TaskStatus};
+
+use crate::helper::*;
+
+/// The async client can manage tasks and follow their output.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_async_client() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    let mut client = PueueClient::new(shared).await?;
+    assert!(!client.daemon_version().is_empty());
+
+    let task_id = client
+        .add(create_add_message(shared, "echo client"))
+        .await?;
+    assert_eq!(task_id, 0);
+    wait_for_task_condition(shared, task_id, |task| task.is_done()).await?;
+
+    let state = client.status().await?;
+    assert_eq!(
+        state.tasks[&task_id].status,
+        TaskStatus::Done(TaskResult::Success)
+    );
+    assert!(client.groups().await?.contains_key(PUEUE_DEFAULT_GROUP));
+
+    let logs = client
+        .log(LogRequestMessage {
+            task_ids: vec![task_id],
+            send_logs: true,
+            lines: None,
+            stream: None,
+            timestamps: false,
+        })
+        .await?;
+    assert_eq!(logs.len(), 1);
+
+    // Failures of the daemon are passed on as errors.
+    let result = client
+        .pause(PauseMessage {
+            tasks: TaskSelection::Group("doesnt_exist".into()),
+            wait: false,
+        })
+        .await;
+    assert!(matches!(result, Err(Error::DaemonFailure(_))));
+
+    // Follow a task until it finishes.
+    let task_id = client
+        .add(create_add_message(shared, "echo follow"))
+        .await?;
+    let mut stream = client
+        .follow(StreamRequestMessage {
+            task_id: Some(task_id),
+            lines: None,
+            stream: None,
+            timestamps: false,
+        })
+        .await?;
+    let mut output = String::new();
+    while let Some(chunk) = stream.next().await {
+        output.push_str(&chunk?);
+    }
+    assert_eq!(output, "follow\n");
+
+    Ok(())
+}
+
+/// The blocking client works without an async runtime of the caller.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_blocking_client() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = daemon.settings.shared.clone();
+
+    let task_id = tokio::task::spawn_blocking(move || -> Result<usize> {
+        let mut client = BlockingPueueClient::new(&shared)?;
+        let task_id = client.add(create_add_message(&shared, "sleep 60"))?;
+        // Wait until the task runs, before killing it.
+        while client.status()?.tasks[&task_id].status != TaskStatus::Running {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+        client.kill(KillMessage {
+            tasks: TaskSelection::TaskIds(vec![task_id]),
+            signal: None,
+        })?;
+
+        Ok(task_id)
+    })
+    .await??;
+
+    let shared = &daemon.settings.shared;
+    let task = wait_for_task_condition(shared, task_id, |task| task.is_done()).await?;
+    assert_eq!(task.status, TaskStatus::Done(TaskResult::Killed));
+
+    Ok(())
+}
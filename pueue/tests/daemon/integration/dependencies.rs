@@ -0,0 +1,88 @@
+use anyhow::Result;
+use pretty_assertions::assert_eq;
+use rstest::rstest;
+
+use pueue_lib::network::message::*;
+use pueue_lib::settings::Shared;
+use pueue_lib::task::*;
+
+use crate::helper::*;
+
+/// Add a task that depends on the given task under the given condition.
+async fn add_dependent_task(
+    shared: &Shared,
+    dependency: usize,
+    condition: DependencyCondition,
+) -> Result<Message> {
+    let mut message = create_add_message(shared, "ls");
+    message.dependency_conditions.insert(dependency, condition);
+
+    send_message(shared, message).await
+}
+
+/// Dependents are started or failed depending on whether the result of their dependency
+/// fulfills their condition.
+#[rstest]
+#[case("ls", DependencyCondition::Success, TaskResult::Success)]
+#[case("failing", DependencyCondition::Success, TaskResult::DependencyFailed)]
+#[case("failing", DependencyCondition::Failure, TaskResult::Success)]
+#[case("ls", DependencyCondition::Failure, TaskResult::DependencyFailed)]
+#[case("ls", DependencyCondition::Any, TaskResult::Success)]
+#[case("failing", DependencyCondition::Any, TaskResult::Success)]
+#[case("exit 3", DependencyCondition::ExitCode(3), TaskResult::Success)]
+#[case(
+    "exit 2",
+    DependencyCondition::ExitCode(3),
+    TaskResult::DependencyFailed
+)]
+#[case("ls", DependencyCondition::ExitCode(0), TaskResult::Success)]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_dependency_conditions(
+    #[case] command: &str,
+    #[case] condition: DependencyCondition,
+    #[case] expected: TaskResult,
+) -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    assert_success(add_task(shared, command).await?);
+    assert_success(add_dependent_task(shared, 0, condition).await?);
+
+    // The condition is stored along with the dependency.
+    let state = get_state(shared).await?;
+    assert_eq!(state.tasks[&1].dependencies, vec![0]);
+    assert_eq!(state.tasks[&1].dependency_condition(0), condition);
+
+    let task = wait_for_task_condition(shared, 1, |task| task.is_done()).await?;
+    assert_eq!(task.status, TaskStatus::Done(expected));
+
+    Ok(())
+}
+
+/// Dependencies on unknown tasks are rejected, even if they have a condition.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_unknown_conditional_dependency() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    assert_failure(add_dependent_task(shared, 5, DependencyCondition::Any).await?);
+
+    Ok(())
+}
+
+/// A dependency can't have a condition and no condition at the same time.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_dependency_with_multiple_conditions() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+    assert_success(add_task(shared, "ls").await?);
+
+    let mut message = create_add_message(shared, "ls");
+    message.dependencies = vec![0];
+    message
+        .dependency_conditions
+        .insert(0, DependencyCondition::Failure);
+    assert_failure(send_message(shared, message).await?);
+
+    Ok(())
+}
@@ -17,6 +17,8 @@ async fn test_add_and_remove() -> Result<()> {
     let add_message = GroupMessage::Add {
         name: "testgroup".to_string(),
         parallel_tasks: None,
+        resources: Default::default(),
+        priority: 0,
     };
     assert_failure(send_message(shared, add_message).await?);
 
@@ -0,0 +1,95 @@
+use anyhow::{bail, Context, Result};
+use pretty_assertions::assert_eq;
+
+use pueue_lib::network::message::*;
+
+use crate::helper::*;
+
+/// Cleaned tasks are moved to the archive, including their output.
+/// Archive ids keep increasing, even though the task ids are reused.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_archive_cleaned_tasks() -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    settings.daemon.archive_removed_tasks = true;
+    settings
+        .save(&Some(tempdir.path().join("pueue.yml")))
+        .context("Couldn't write pueue config to temporary directory")?;
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+    let shared = &daemon.settings.shared;
+
+    for command in ["echo first", "echo second"] {
+        assert_success(add_task(shared, command).await?);
+        wait_for_task_condition(shared, 0, |task| task.is_done()).await?;
+
+        let clean_message = CleanMessage {
+            successful_only: false,
+            group: None,
+        };
+        assert_success(send_message(shared, clean_message).await?);
+    }
+
+    let archived = match send_message(shared, HistoryRequestMessage::default()).await? {
+        Message::HistoryResponse(archived) => archived,
+        other => bail!("Expected a history response, got {other:?}"),
+    };
+    let entries: Vec<(usize, &str)> = archived
+        .iter()
+        .map(|archived| (archived.archive_id, archived.task.command.as_str()))
+        .collect();
+    assert_eq!(entries, vec![(0, "echo first"), (1, "echo second")]);
+
+    // The logs are only staged until they've been written to the archive.
+    let pending = shared.pueue_directory().join("archive").join("pending");
+    assert_eq!(std::fs::read_dir(pending)?.count(), 0);
+
+    // The archive can be paged through from the newest to the oldest entry.
+    for (before, expected) in [(None, 1), (Some(1), 0)] {
+        let message = HistoryRequestMessage {
+            before,
+            limit: Some(1),
+        };
+        let archived = match send_message(shared, message).await? {
+            Message::HistoryResponse(archived) => archived,
+            other => bail!("Expected a history response, got {other:?}"),
+        };
+        let ids: Vec<usize> = archived
+            .iter()
+            .map(|archived| archived.archive_id)
+            .collect();
+        assert_eq!(ids, vec![expected]);
+    }
+
+    // The output of the archived task can still be read.
+    let message = HistoryLogRequestMessage {
+        archive_id: 1,
+        lines: None,
+    };
+    let mut logs = match send_message(shared, message).await? {
+        Message::LogResponse(logs) => logs,
+        other => bail!("Expected a log response, got {other:?}"),
+    };
+    let log = logs
+        .remove(&1)
+        .context("Missing log of the archived task")?;
+    assert_eq!(decompress_log(log.output.unwrap())?, "second\n");
+
+    Ok(())
+}
+
+/// Tasks aren't archived, unless the daemon is configured to do so.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_archive_disabled() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    assert_success(add_task(shared, "echo test").await?);
+    wait_for_task_condition(shared, 0, |task| task.is_done()).await?;
+    assert_success(send_message(shared, Message::Remove(vec![0])).await?);
+
+    match send_message(shared, HistoryRequestMessage::default()).await? {
+        Message::HistoryResponse(archived) => assert!(archived.is_empty()),
+        other => bail!("Expected a history response, got {other:?}"),
+    };
+
+    Ok(())
+}
@@ -0,0 +1,151 @@
+use anyhow::{Context, Result};
+use pretty_assertions::assert_eq;
+use serde_json::{json, Value};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use pueue_lib::network::secret::read_shared_secret;
+use pueue_lib::network::socket::get_client_stream;
+use pueue_lib::settings::Shared;
+use pueue_lib::task::{TaskResult, TaskStatus};
+
+use crate::helper::*;
+
+/// Spawn a daemon with an enabled HTTP API and an additional bearer token.
+/// Returns the daemon and the address of the HTTP API.
+async fn http_daemon() -> Result<(PueueDaemon, String)> {
+    // Let the OS pick a free port for us.
+    let address = std::net::TcpListener::bind("127.0.0.1:0")?
+        .local_addr()?
+        .to_string();
+
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    settings.shared.http_api_address = Some(address.clone());
+    settings.shared.http_api_token = Some("dashboard-token".into());
+    settings
+        .save(&Some(tempdir.path().join("pueue.yml")))
+        .context("Couldn't write pueue config to temporary directory")?;
+
+    Ok((daemon_with_settings(settings, tempdir).await?, address))
+}
+
+/// Send a HTTPS request and return the status code and the parsed JSON body of the response.
+async fn request(
+    shared: &Shared,
+    address: &str,
+    method: &str,
+    path: &str,
+    token: &str,
+    body: Option<Value>,
+) -> Result<(u16, Value)> {
+    let body = body.map(|body| body.to_string()).unwrap_or_default();
+    let request = format!(
+        "{method} {path} HTTP/1.1\r\nHost: {address}\r\nAuthorization: Bearer {token}\r\nContent-Length: {}\r\n\r\n{body}",
+        body.len()
+    );
+
+    // The API uses the same TLS setup as the default protocol over TCP.
+    let (host, port) = address.rsplit_once(':').context("Address has no port")?;
+    let tls_settings = Shared {
+        use_unix_socket: false,
+        host: host.to_string(),
+        port: port.to_string(),
+        ..shared.clone()
+    };
+    let mut stream = get_client_stream(&tls_settings).await?;
+    stream.write_all(request.as_bytes()).await?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await?;
+
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .context("Response has no body")?;
+    let status = head
+        .split_whitespace()
+        .nth(1)
+        .context("Response has no status")?
+        .parse()?;
+
+    Ok((status, serde_json::from_str(body)?))
+}
+
+/// Tasks can be added and inspected via the HTTP API with the shared secret.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_http_add_and_status() -> Result<()> {
+    let (daemon, address) = http_daemon().await?;
+    let shared = &daemon.settings.shared;
+    let secret = String::from_utf8(read_shared_secret(&shared.shared_secret_path())?)?;
+
+    let message = serde_json::to_value(create_add_message(shared, "echo http"))?;
+    let (status, response) =
+        request(shared, &address, "POST", "/api/add", &secret, Some(message)).await?;
+    assert_eq!(status, 200);
+    assert!(response.get("Success").is_some(), "Got {response}");
+
+    let task = wait_for_task_condition(shared, 0, |task| task.is_done()).await?;
+    assert_eq!(task.status, TaskStatus::Done(TaskResult::Success));
+
+    let (status, response) = request(shared, &address, "GET", "/api/status", &secret, None).await?;
+    assert_eq!(status, 200);
+    assert_eq!(
+        response["StatusResponse"]["tasks"]["0"]["command"],
+        json!("echo http")
+    );
+
+    // Log output is sent as plain text.
+    let message = json!({ "task_ids": [0], "send_logs": true, "lines": null });
+    let (status, response) =
+        request(shared, &address, "POST", "/api/log", &secret, Some(message)).await?;
+    assert_eq!(status, 200);
+    assert_eq!(response["LogResponse"]["0"]["output"], json!("http\n"));
+
+    Ok(())
+}
+
+/// The additional bearer token is accepted, while requests without a valid token are rejected.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_http_authentication() -> Result<()> {
+    let (daemon, address) = http_daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    let (status, _) = request(
+        shared,
+        &address,
+        "GET",
+        "/api/status",
+        "dashboard-token",
+        None,
+    )
+    .await?;
+    assert_eq!(status, 200);
+
+    let (status, response) =
+        request(shared, &address, "GET", "/api/status", "invalid", None).await?;
+    assert_eq!(status, 401);
+    assert!(response.get("Failure").is_some());
+
+    Ok(())
+}
+
+/// Failures of the daemon and invalid requests are reported with a proper status code.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_http_errors() -> Result<()> {
+    let (daemon, address) = http_daemon().await?;
+    let shared = &daemon.settings.shared;
+    let token = "dashboard-token";
+
+    // Pausing a non-existing group fails.
+    let message = json!({ "tasks": { "Group": "doesnt_exist" }, "wait": false });
+    let (status, response) =
+        request(shared, &address, "POST", "/api/pause", token, Some(message)).await?;
+    assert_eq!(status, 400);
+    assert!(response.get("Failure").is_some());
+
+    let (status, _) = request(shared, &address, "POST", "/api/unknown", token, None).await?;
+    assert_eq!(status, 404);
+
+    let message = json!({ "invalid": "message" });
+    let (status, _) = request(shared, &address, "POST", "/api/kill", token, Some(message)).await?;
+    assert_eq!(status, 400);
+
+    Ok(())
+}
@@ -3,7 +3,10 @@ use std::fs::File;
 use std::path::Path;
 
 use anyhow::{bail, Context, Result};
+use pueue_lib::log::LogStream;
 use pueue_lib::network::message::*;
+use pueue_lib::settings::Shared;
+use rstest::rstest;
 use tempfile::TempDir;
 
 use crate::helper::*;
@@ -98,6 +101,8 @@ async fn test_partial_log() -> Result<()> {
         task_ids: vec![0],
         send_logs: true,
         lines: Some(5),
+        stream: None,
+        timestamps: false,
     };
     let response = send_message(shared, Message::Log(log_message)).await?;
     let logs = match response {
@@ -135,6 +140,8 @@ async fn test_correct_log_order() -> Result<()> {
         task_ids: vec![0],
         send_logs: true,
         lines: None,
+        stream: None,
+        timestamps: false,
     };
     let response = send_message(shared, Message::Log(log_message)).await?;
     let logs = match response {
@@ -155,3 +162,280 @@ async fn test_correct_log_order() -> Result<()> {
 
     Ok(())
 }
+
+/// Request the logs of a single task and return its decompressed stdout/combined output and
+/// stderr.
+async fn get_separate_logs(
+    shared: &Shared,
+    stream: Option<LogStream>,
+) -> Result<(Option<String>, Option<String>)> {
+    let log_message = LogRequestMessage {
+        task_ids: vec![0],
+        send_logs: true,
+        lines: None,
+        stream,
+        timestamps: false,
+    };
+    let response = send_message(shared, log_message).await?;
+    let Message::LogResponse(mut logs) = response else {
+        bail!("Received non LogResponse: {:#?}", response);
+    };
+
+    let log = logs.remove(&0).context("Didn't find log of task")?;
+    let output = log.output.map(decompress_log).transpose()?;
+    let stderr = log.stderr.map(decompress_log).transpose()?;
+
+    Ok((output, stderr))
+}
+
+/// Tasks with separate output write stdout and stderr into their own files, which can be
+/// requested together or one at a time.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_separate_output() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    let mut message = create_add_message(shared, "echo out; echo err >&2");
+    message.separate_output = true;
+    assert_success(send_message(shared, message).await?);
+    let task = wait_for_task_condition(shared, 0, |task| task.is_done()).await?;
+    assert!(task.separate_output);
+
+    let (output, stderr) = get_separate_logs(shared, None).await?;
+    assert_eq!(output.as_deref(), Some("out\n"));
+    assert_eq!(stderr.as_deref(), Some("err\n"));
+
+    let (output, stderr) = get_separate_logs(shared, Some(LogStream::Stdout)).await?;
+    assert_eq!(output.as_deref(), Some("out\n"));
+    assert_eq!(stderr, None);
+
+    let (output, stderr) = get_separate_logs(shared, Some(LogStream::Stderr)).await?;
+    assert_eq!(output, None);
+    assert_eq!(stderr.as_deref(), Some("err\n"));
+
+    Ok(())
+}
+
+/// Tasks without separate output only have a combined log, which is returned regardless of
+/// the requested stream.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_combined_output_ignores_stream() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    assert_success(add_task(shared, "echo out; echo err >&2").await?);
+    wait_for_task_condition(shared, 0, |task| task.is_done()).await?;
+
+    let (output, stderr) = get_separate_logs(shared, Some(LogStream::Stderr)).await?;
+    assert_eq!(output.as_deref(), Some("out\nerr\n"));
+    assert_eq!(stderr, None);
+
+    Ok(())
+}
+
+/// If enabled, the daemon records a timestamp for every line of output, which can be requested
+/// together with the log.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_log_timestamps() -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    settings.daemon.log_timestamps = true;
+    settings
+        .save(&Some(tempdir.path().join("pueue.yml")))
+        .context("Couldn't write pueue config to temporary directory")?;
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+    let shared = &daemon.settings.shared;
+
+    // stdout and stderr are recorded independently, so give each line some time.
+    assert_success(
+        add_task(
+            shared,
+            "echo one; sleep 0.2; echo two >&2; sleep 0.2; printf three",
+        )
+        .await?,
+    );
+    wait_for_task_condition(shared, 0, |task| task.is_done()).await?;
+
+    let log_message = LogRequestMessage {
+        task_ids: vec![0],
+        send_logs: true,
+        lines: None,
+        stream: None,
+        timestamps: true,
+    };
+    let response = send_message(shared, log_message).await?;
+    let Message::LogResponse(mut logs) = response else {
+        bail!("Received non LogResponse: {:#?}", response);
+    };
+    let log = logs.remove(&0).context("Didn't find log of task")?;
+
+    // The output itself isn't changed, while each line got its own timestamp.
+    let output = decompress_log(log.output.context("Didn't get log output")?)?;
+    assert_eq!(output, "one\ntwo\nthree");
+    let timestamps = log
+        .output_timestamps
+        .context("Didn't get timestamps even though requested")?;
+    assert_eq!(timestamps.len(), 3);
+    let timestamps: Vec<_> = timestamps.into_iter().flatten().collect();
+    assert_eq!(timestamps.len(), 3, "Every line should have a timestamp");
+    // Lines that are recorded late due to load may still end up in the same millisecond.
+    assert!(timestamps[1] >= timestamps[0]);
+
+    // Only the last lines and their timestamps are sent, if requested.
+    let log_message = LogRequestMessage {
+        task_ids: vec![0],
+        send_logs: true,
+        lines: Some(1),
+        stream: None,
+        timestamps: true,
+    };
+    let response = send_message(shared, log_message).await?;
+    let Message::LogResponse(mut logs) = response else {
+        bail!("Received non LogResponse: {:#?}", response);
+    };
+    let log = logs.remove(&0).context("Didn't find log of task")?;
+    let output = decompress_log(log.output.context("Didn't get log output")?)?;
+    let line_timestamps = log.output_timestamps.context("Didn't get timestamps")?;
+    assert_eq!(line_timestamps.len(), output.lines().count());
+    assert_eq!(line_timestamps.last(), Some(&Some(timestamps[2])));
+
+    Ok(())
+}
+
+/// Output without any newlines is recorded in chunks, but still read as a single line.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_log_timestamps_long_line() -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    settings.daemon.log_timestamps = true;
+    settings
+        .save(&Some(tempdir.path().join("pueue.yml")))
+        .context("Couldn't write pueue config to temporary directory")?;
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+    let shared = &daemon.settings.shared;
+
+    assert_success(add_task(shared, "head -c 200000 /dev/zero | tr '\\0' a").await?);
+    wait_for_task_condition(shared, 0, |task| task.is_done()).await?;
+
+    let log_message = LogRequestMessage {
+        task_ids: vec![0],
+        send_logs: true,
+        lines: None,
+        stream: None,
+        timestamps: true,
+    };
+    let response = send_message(shared, log_message).await?;
+    let Message::LogResponse(mut logs) = response else {
+        bail!("Received non LogResponse: {:#?}", response);
+    };
+    let log = logs.remove(&0).context("Didn't find log of task")?;
+
+    let output = decompress_log(log.output.context("Didn't get log output")?)?;
+    assert_eq!(output, "a".repeat(200000));
+    let timestamps = log.output_timestamps.context("Didn't get timestamps")?;
+    assert_eq!(timestamps.len(), 1);
+    assert!(timestamps[0].is_some());
+
+    Ok(())
+}
+
+/// Logs of running tasks are rotated once they grow too large.
+/// Reading the log transparently includes the rotated segments that are kept.
+///
+/// Output that's piped through the daemon to record timestamps is rotated while writing,
+/// while all other logs are rotated by the daemon in regular intervals.
+#[rstest]
+#[case(false)]
+#[case(true)]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_log_rotation(#[case] log_timestamps: bool) -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    settings.daemon.log_timestamps = log_timestamps;
+    settings.daemon.max_log_size = Some(50);
+    settings.daemon.log_rotation_count = 1;
+    settings
+        .save(&Some(tempdir.path().join("pueue.yml")))
+        .context("Couldn't write pueue config to temporary directory")?;
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+    let shared = &daemon.settings.shared;
+
+    // Slowly write 30 lines of ~8 bytes each, so the log is rotated a few times.
+    let command = "for i in $(seq 1 30); do echo \"line $i\"; sleep 0.05; done";
+    assert_success(add_task(shared, command).await?);
+    sleep_ms(1500).await;
+    wait_for_task_condition(shared, 0, |task| task.is_done()).await?;
+
+    let segment = shared
+        .pueue_directory()
+        .join("task_logs")
+        .join("0.log.1.sz");
+    assert!(segment.exists(), "The log should have been rotated");
+
+    // Only a single segment is kept, so the first lines are gone for good.
+    let output = get_task_log(shared, 0, None).await?;
+    assert!(output.ends_with("line 30\n"));
+    assert!(!output.starts_with("line 1\n"));
+
+    // The last lines are taken from the rotated segment and the current log file.
+    let output = get_task_log(shared, 0, Some(5)).await?;
+    let expected: String = (26..=30).map(|line| format!("line {line}\n")).collect();
+    assert_eq!(output, expected);
+
+    Ok(())
+}
+
+/// Logs of finished tasks are compressed, if the daemon is configured to do so.
+/// Reading the log transparently decompresses it.
+#[rstest]
+#[case(false)]
+#[case(true)]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_compress_finished_logs(#[case] log_timestamps: bool) -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    settings.daemon.log_timestamps = log_timestamps;
+    settings.daemon.compress_finished_logs = true;
+    settings
+        .save(&Some(tempdir.path().join("pueue.yml")))
+        .context("Couldn't write pueue config to temporary directory")?;
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+    let shared = &daemon.settings.shared;
+
+    assert_success(add_task(shared, "seq 1 10").await?);
+    wait_for_task_condition(shared, 0, |task| task.is_done()).await?;
+
+    // Logs are compressed in the background, once the task finished.
+    let task_logs = shared.pueue_directory().join("task_logs");
+    let mut tries = 0;
+    while task_logs.join("0.log").exists() && tries < 20 {
+        sleep_ms(50).await;
+        tries += 1;
+    }
+    assert!(task_logs.join("0.log.sz").exists());
+    assert!(!task_logs.join("0.log").exists());
+
+    let output = get_task_log(shared, 0, None).await?;
+    let expected: String = (1..=10).map(|line| format!("{line}\n")).collect();
+    assert_eq!(output, expected);
+
+    let output = get_task_log(shared, 0, Some(3)).await?;
+    assert_eq!(output, "8\n9\n10\n");
+
+    // Timestamps are still available after compression.
+    if log_timestamps {
+        let log_message = LogRequestMessage {
+            task_ids: vec![0],
+            send_logs: true,
+            lines: Some(3),
+            stream: None,
+            timestamps: true,
+        };
+        let response = send_message(shared, log_message).await?;
+        let Message::LogResponse(mut logs) = response else {
+            bail!("Received non LogResponse: {:#?}", response);
+        };
+        let log = logs.remove(&0).context("Didn't find log of task")?;
+        let line_timestamps = log.output_timestamps.context("Didn't get timestamps")?;
+        assert_eq!(line_timestamps.len(), 3);
+        assert!(line_timestamps.iter().all(Option::is_some));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use anyhow::{Context, Result};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use crate::helper::*;
+
+/// Request a path of the metrics endpoint and return the raw response.
+async fn request<S>(mut stream: S, path: &str) -> Result<String>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let request = format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n");
+    stream.write_all(request.as_bytes()).await?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await?;
+
+    Ok(response)
+}
+
+/// The metrics contain the tasks of the state and the tasks that finished.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_metrics() -> Result<()> {
+    // Let the OS pick a free port for us.
+    let address = std::net::TcpListener::bind("127.0.0.1:0")?
+        .local_addr()?
+        .to_string();
+
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    settings.daemon.metrics_address = Some(address.clone());
+    settings
+        .save(&Some(tempdir.path().join("pueue.yml")))
+        .context("Couldn't write pueue config to temporary directory")?;
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+    let shared = &daemon.settings.shared;
+
+    // The finished tasks are counted for the whole process, which is why a dedicated group
+    // is used for this test.
+    add_group_with_slots(shared, "metrics", 1).await?;
+    assert_success(add_task_to_group(shared, "ls", "metrics").await?);
+    wait_for_task_condition(shared, 0, |task| task.is_done()).await?;
+    assert_success(add_task_to_group(shared, "sleep 60", "metrics").await?);
+    assert_success(add_task_to_group(shared, "sleep 60", "metrics").await?);
+    wait_for_task_condition(shared, 1, |task| task.is_running()).await?;
+
+    let response = request(TcpStream::connect(&address).await?, "/metrics").await?;
+    assert!(response.starts_with("HTTP/1.1 200 OK"), "Got {response}");
+    for line in [
+        "pueue_tasks{group=\"metrics\",status=\"done\"} 1",
+        "pueue_tasks{group=\"metrics\",status=\"running\"} 1",
+        "pueue_queue_depth{group=\"metrics\"} 1",
+        "pueue_running_tasks{group=\"metrics\"} 1",
+        "pueue_finished_tasks_total{group=\"metrics\",result=\"success\"} 1",
+        "pueue_task_duration_seconds_count{group=\"metrics\"} 1",
+        "# TYPE pueue_state_save_duration_seconds histogram",
+    ] {
+        assert!(
+            response.lines().any(|response_line| response_line == line),
+            "Missing '{line}' in:\n{response}"
+        );
+    }
+
+    let response = request(TcpStream::connect(&address).await?, "/status").await?;
+    assert!(response.starts_with("HTTP/1.1 404"), "Got {response}");
+
+    Ok(())
+}
+
+/// The metrics can also be served on a unix socket.
+#[cfg(not(target_os = "windows"))]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_metrics_socket() -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    let socket_path = tempdir.path().join("metrics.socket");
+    settings.daemon.metrics_socket_path = Some(socket_path.clone());
+    settings
+        .save(&Some(tempdir.path().join("pueue.yml")))
+        .context("Couldn't write pueue config to temporary directory")?;
+    let _daemon = daemon_with_settings(settings, tempdir).await?;
+
+    let stream = tokio::net::UnixStream::connect(&socket_path).await?;
+    let response = request(stream, "/metrics").await?;
+    assert!(response.starts_with("HTTP/1.1 200 OK"), "Got {response}");
+    assert!(response.contains("pueue_queue_depth{group=\"default\"} 0"));
+
+    Ok(())
+}
@@ -1,22 +1,61 @@
 mod add;
+/// Tests for adding many tasks at once.
+mod add_batch;
 mod aliases;
+/// Tests for the audit log of client operations.
+mod audit;
 mod clean;
+/// Tests for the typed client of pueue_lib.
+mod client;
+/// Tests for the conditions of dependencies.
+mod dependencies;
 mod edit;
 mod environment_variables;
 mod group;
+/// Tests for the archive of removed tasks.
+mod history;
+/// Tests for the HTTP/JSON API.
+mod http_api;
 mod kill;
 mod log;
+/// Tests for the Prometheus metrics endpoint.
+mod metrics;
 mod parallel_tasks;
 mod pause;
+/// Tests for authenticating unix socket clients via their credentials.
+#[cfg(not(target_os = "windows"))]
+mod peer_credentials;
 mod priority;
 mod remove;
 mod reset;
+/// Tests for resource-aware scheduling.
+mod resources;
 mod restart;
 /// Tests regarding state restoration from a previous run.
 mod restore;
+/// Tests for automatically removing finished tasks.
+mod retention;
+/// Tests for automatic retries of failed tasks.
+mod retry;
+/// Tests for recurring schedules.
+mod schedule;
 /// Tests for shutting down the daemon.
 mod shutdown;
 mod start;
 mod stashed;
+/// Tests for event subscriptions.
+mod subscribe;
+/// Tests for holding back tasks due to the system's load.
+/// Reading the system's load is only supported on Linux.
+#[cfg(target_os = "linux")]
+mod system_limits;
+/// Tests for task arrays, which consist of a task per parameter.
+mod task_array;
+/// Tests for task timeouts.
+mod timeout;
+/// Tests for principals with restricted permissions.
+mod tokens;
 /// Test that the worker pool environment variables are properly injected.
 mod worker_environment_variables;
+/// Tests for workflows, whose steps depend on each other.
+mod workflow;
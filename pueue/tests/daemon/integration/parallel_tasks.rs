@@ -1,6 +1,7 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use pretty_assertions::assert_eq;
 
+use pueue_lib::network::message::*;
 use pueue_lib::task::*;
 
 use crate::helper::*;
@@ -56,3 +57,101 @@ async fn test_parallel_tasks() -> Result<()> {
     }
     Ok(())
 }
+
+/// The daemon-wide limit caps the running tasks across all groups.
+/// It's initialized from the settings and can be adjusted and removed at runtime.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_global_limit() -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    settings.daemon.max_parallel_tasks = Some(2);
+    settings
+        .save(&Some(tempdir.path().join("pueue.yml")))
+        .context("Couldn't write pueue config to temporary directory")?;
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+    let shared = &daemon.settings.shared;
+
+    add_group_with_slots(shared, "first", 2).await?;
+    add_group_with_slots(shared, "second", 2).await?;
+
+    // Each group has two slots, but only two tasks may run in total.
+    for group in ["first", "first", "second", "second"] {
+        assert_success(add_task_to_group(shared, "sleep 60", group).await?);
+    }
+    wait_for_task_condition(shared, 1, |task| task.is_running()).await?;
+
+    sleep_ms(500).await;
+    let state = get_state(shared).await?;
+    assert_eq!(state.max_parallel_tasks, Some(2));
+    assert_eq!(state.tasks[&2].status, TaskStatus::Queued);
+    assert_eq!(state.tasks[&3].status, TaskStatus::Queued);
+
+    // Raise the limit, which allows a third task to start.
+    let message = ParallelMessage {
+        parallel_tasks: 3,
+        group: PUEUE_DEFAULT_GROUP.into(),
+        global: true,
+    };
+    assert_success(send_message(shared, message).await?);
+    wait_for_task_condition(shared, 2, |task| task.is_running()).await?;
+
+    sleep_ms(500).await;
+    let state = get_state(shared).await?;
+    assert_eq!(state.tasks[&3].status, TaskStatus::Queued);
+
+    // Remove the limit, after which only the slots of the groups count.
+    let message = ParallelMessage {
+        parallel_tasks: 0,
+        group: PUEUE_DEFAULT_GROUP.into(),
+        global: true,
+    };
+    assert_success(send_message(shared, message).await?);
+    wait_for_task_condition(shared, 3, |task| task.is_running()).await?;
+
+    let state = get_state(shared).await?;
+    assert_eq!(state.max_parallel_tasks, None);
+
+    Ok(())
+}
+
+/// Once the daemon-wide limit is reached, the group with the highest priority gets the next
+/// free slot, even if the other group's tasks have been queued earlier.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_global_limit_group_priority() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    let message = ParallelMessage {
+        parallel_tasks: 1,
+        group: PUEUE_DEFAULT_GROUP.into(),
+        global: true,
+    };
+    assert_success(send_message(shared, message).await?);
+
+    add_group_with_slots(shared, "low", 1).await?;
+    add_group_with_slots(shared, "high", 1).await?;
+    let message = GroupMessage::SetPriority {
+        name: "high".into(),
+        priority: 5,
+    };
+    assert_success(send_message(shared, message).await?);
+
+    // Occupy the only global slot, so the following tasks have to wait.
+    assert_success(add_task(shared, "sleep 60").await?);
+    wait_for_task_condition(shared, 0, |task| task.is_running()).await?;
+    assert_success(add_task_to_group(shared, "sleep 60", "low").await?);
+    assert_success(add_task_to_group(shared, "sleep 60", "high").await?);
+
+    // Free the slot. The task of the group with the higher priority is started.
+    let message = KillMessage {
+        tasks: TaskSelection::TaskIds(vec![0]),
+        signal: None,
+    };
+    assert_success(send_message(shared, message).await?);
+    wait_for_task_condition(shared, 2, |task| task.is_running()).await?;
+
+    sleep_ms(500).await;
+    let state = get_state(shared).await?;
+    assert_eq!(state.tasks[&1].status, TaskStatus::Queued);
+
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use std::collections::BTreeMap;
+use std::fs::metadata;
+use std::os::unix::fs::MetadataExt;
+
+use anyhow::{Context, Result};
+use pretty_assertions::assert_eq;
+
+use pueue_lib::network::message::*;
+use pueue_lib::network::tokens::Role;
+
+use crate::helper::*;
+
+/// Clients of the unix socket, that are allowed via their user id, don't need the secret.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_allowed_uid() -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    // The tests run as the owner of the temporary directory.
+    let uid = metadata(tempdir.path())?.uid();
+    settings.shared.unix_socket_allowed_uids = BTreeMap::from([(uid, Role::Submit)]);
+    settings
+        .save(&Some(tempdir.path().join("pueue.yml")))
+        .context("Couldn't write pueue config to temporary directory")?;
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+
+    let mut shared = daemon.settings.shared.clone();
+    shared.shared_secret_path = Some(shared.pueue_directory().join("missing_secret"));
+    assert_success(add_task(&shared, "ls").await?);
+
+    // The client is the owner of its tasks, but is restricted by its role.
+    let state = get_state(&shared).await?;
+    assert_eq!(state.tasks[&0].owner, Some(format!("uid={uid}")));
+    assert_failure(send_message(&shared, Shutdown::Graceful).await?);
+
+    Ok(())
+}
+
+/// Clients without the secret are rejected, if their user id isn't allowed.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_disallowed_uid() -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    let uid = metadata(tempdir.path())?.uid();
+    settings.shared.unix_socket_allowed_uids = BTreeMap::from([(uid + 1, Role::Admin)]);
+    settings
+        .save(&Some(tempdir.path().join("pueue.yml")))
+        .context("Couldn't write pueue config to temporary directory")?;
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+
+    let mut shared = daemon.settings.shared.clone();
+    shared.shared_secret_path = Some(shared.pueue_directory().join("missing_secret"));
+    assert!(send_message(&shared, Message::Status).await.is_err());
+
+    Ok(())
+}
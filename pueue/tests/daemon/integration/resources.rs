@@ -0,0 +1,115 @@
+use anyhow::{Context, Result};
+use pretty_assertions::assert_eq;
+
+use pueue_lib::network::message::*;
+use pueue_lib::resources::Resources;
+use pueue_lib::task::TaskStatus;
+
+use crate::helper::*;
+
+/// Create an AddMessage for a long running task that requests the given resources.
+fn create_resource_message(daemon: &PueueDaemon, resources: &[(&str, u64)]) -> AddMessage {
+    let mut message = create_add_message(&daemon.settings.shared, "sleep 60");
+    message.resources = resources
+        .iter()
+        .map(|(name, amount)| (name.to_string(), *amount))
+        .collect();
+
+    message
+}
+
+/// Tasks are only started as long as the daemon's capacity of a resource isn't exhausted.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_daemon_capacity() -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    settings.daemon.resources = Resources::from([("mem".into(), 4)]);
+    settings
+        .save(&Some(tempdir.path().join("pueue.yml")))
+        .context("Couldn't write pueue config to temporary directory")?;
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+    let shared = &daemon.settings.shared;
+
+    // There are enough slots, so only the resources limit the amount of running tasks.
+    let message = ParallelMessage {
+        parallel_tasks: 5,
+        group: PUEUE_DEFAULT_GROUP.into(),
+        global: false,
+    };
+    assert_success(send_message(shared, message).await?);
+
+    for _ in 0..3 {
+        assert_success(
+            send_message(shared, create_resource_message(&daemon, &[("mem", 2)])).await?,
+        );
+    }
+    wait_for_task_condition(shared, 1, |task| task.is_running()).await?;
+    // Tasks without any requests aren't affected.
+    assert_success(add_task(shared, "sleep 60").await?);
+    wait_for_task_condition(shared, 3, |task| task.is_running()).await?;
+
+    sleep_ms(500).await;
+    let state = get_state(shared).await?;
+    assert_eq!(state.tasks[&2].status, TaskStatus::Queued);
+
+    // Once a task finishes, its resources are freed for the next one.
+    assert_success(
+        send_message(
+            shared,
+            KillMessage {
+                tasks: TaskSelection::TaskIds(vec![0]),
+                signal: None,
+            },
+        )
+        .await?,
+    );
+    wait_for_task_condition(shared, 2, |task| task.is_running()).await?;
+
+    Ok(())
+}
+
+/// Groups limit the resources of their own tasks.
+/// Smaller tasks may start, while a bigger one is still waiting for its resources.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_group_capacity() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    let add_message = GroupMessage::Add {
+        name: "gpu".into(),
+        parallel_tasks: Some(5),
+        resources: Resources::from([("cpu".into(), 4)]),
+        priority: 0,
+    };
+    assert_success(send_message(shared, add_message).await?);
+    wait_for_group(shared, "gpu").await?;
+
+    let mut first = create_resource_message(&daemon, &[("cpu", 3)]);
+    first.group = "gpu".into();
+    let mut second = create_resource_message(&daemon, &[("cpu", 2)]);
+    second.group = "gpu".into();
+    let mut third = create_resource_message(&daemon, &[("cpu", 1)]);
+    third.group = "gpu".into();
+    for message in [first, second, third] {
+        assert_success(send_message(shared, message).await?);
+    }
+
+    wait_for_task_condition(shared, 0, |task| task.is_running()).await?;
+    wait_for_task_condition(shared, 2, |task| task.is_running()).await?;
+    let state = get_state(shared).await?;
+    assert_eq!(state.tasks[&1].status, TaskStatus::Queued);
+
+    // Tasks that request more than the group could ever provide are rejected.
+    let mut message = create_resource_message(&daemon, &[("cpu", 5)]);
+    message.group = "gpu".into();
+    assert_failure(send_message(shared, message).await?);
+
+    // Removing the limits of the group allows the waiting task to start.
+    let message = GroupMessage::SetResources {
+        name: "gpu".into(),
+        resources: Resources::new(),
+    };
+    assert_success(send_message(shared, message).await?);
+    wait_for_task_condition(shared, 1, |task| task.is_running()).await?;
+
+    Ok(())
+}
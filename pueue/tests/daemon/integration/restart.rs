@@ -30,6 +30,7 @@ async fn test_restart_in_place() -> Result<()> {
             path: Some(PathBuf::from("/tmp")),
             label: Some("test".to_owned()),
             delete_label: false,
+            timeout: None,
         }],
         start_immediately: false,
         stashed: false,
@@ -83,6 +84,7 @@ async fn test_cannot_restart_running() -> Result<()> {
             path: None,
             label: None,
             delete_label: false,
+            timeout: None,
         }],
         start_immediately: false,
         stashed: false,
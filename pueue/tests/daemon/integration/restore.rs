@@ -1,8 +1,13 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use chrono::{Duration, Local};
 use pretty_assertions::assert_eq;
+use serde_json::Value;
+use tempfile::TempDir;
 
-use pueue_lib::network::message::TaskSelection;
+use pueue_lib::network::message::{KillMessage, Message, TaskSelection};
+use pueue_lib::settings::{Settings, StateBackend};
 use pueue_lib::state::GroupStatus;
+use pueue_lib::task::{TaskResult, TaskStatus};
 
 use crate::helper::*;
 
@@ -60,3 +65,150 @@ async fn test_start_paused() -> Result<()> {
     child.kill()?;
     Ok(())
 }
+
+/// With the SQLite backend, the state is restored from the database.
+#[tokio::test]
+async fn test_restore_from_sqlite() -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    settings.daemon.state_backend = StateBackend::Sqlite;
+    settings.save(&Some(tempdir.path().join("pueue.yml")))?;
+    let mut child = standalone_daemon(&settings.shared).await?;
+    let shared = &settings.shared;
+
+    for _ in 0..3 {
+        assert_success(add_task(shared, "ls").await?);
+    }
+    wait_for_task_condition(shared, 2, |task| task.is_done()).await?;
+    assert_success(send_message(shared, Message::Remove(vec![1])).await?);
+    pause_tasks(shared, TaskSelection::All).await?;
+
+    // Kill the daemon and wait for it to shut down.
+    assert_success(shutdown_daemon(shared).await?);
+    wait_for_shutdown(&mut child).await?;
+
+    let pueue_directory = shared.pueue_directory();
+    assert!(pueue_directory.join("state.sqlite").exists());
+    assert!(!pueue_directory.join("state.json").exists());
+
+    // Boot it up again
+    let mut child = standalone_daemon(&settings.shared).await?;
+
+    // The removed task stays removed and the group is still paused.
+    let state = get_state(shared).await?;
+    assert_eq!(state.tasks.keys().copied().collect::<Vec<_>>(), vec![0, 2]);
+    assert_eq!(
+        state.tasks.get(&2).unwrap().status,
+        TaskStatus::Done(TaskResult::Success)
+    );
+    assert_eq!(
+        state.groups.get(PUEUE_DEFAULT_GROUP).unwrap().status,
+        GroupStatus::Paused
+    );
+
+    child.kill()?;
+    Ok(())
+}
+
+/// Create the settings for a standalone daemon that keeps its tasks running during shutdown.
+fn reattach_setup() -> Result<(Settings, TempDir)> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    settings.daemon.reattach_tasks = true;
+    settings.save(&Some(tempdir.path().join("pueue.yml")))?;
+
+    Ok((settings, tempdir))
+}
+
+/// Running tasks survive a daemon restart, if `reattach_tasks` is enabled.
+/// The daemon then reattaches to them and picks up their exit code once they finish.
+#[tokio::test]
+async fn test_reattach_running_task() -> Result<()> {
+    let (settings, _tempdir) = reattach_setup()?;
+    let mut child = standalone_daemon(&settings.shared).await?;
+    let shared = &settings.shared;
+
+    assert_success(add_task(shared, "sleep 2 && exit 3").await?);
+    wait_for_task_condition(shared, 0, |task| task.is_running()).await?;
+
+    // Restart the daemon while the task is still running.
+    assert_success(shutdown_daemon(shared).await?);
+    wait_for_shutdown(&mut child).await?;
+    let mut child = standalone_daemon(shared).await?;
+
+    // The task is still running and has been reattached to.
+    let task = get_task(shared, 0).await?;
+    assert_eq!(task.status, TaskStatus::Running);
+    assert!(task.pid.is_some());
+
+    // Once it finishes, its exit code is properly picked up.
+    sleep_ms(2000).await;
+    let task = wait_for_task_condition(shared, 0, |task| task.is_done()).await?;
+    assert_eq!(task.status, TaskStatus::Done(TaskResult::Failed(3)));
+    assert_eq!(task.pid, None);
+
+    child.kill()?;
+    Ok(())
+}
+
+/// Reattached tasks can still be killed via their process group.
+#[tokio::test]
+async fn test_kill_reattached_task() -> Result<()> {
+    let (settings, _tempdir) = reattach_setup()?;
+    let mut child = standalone_daemon(&settings.shared).await?;
+    let shared = &settings.shared;
+
+    assert_success(add_task(shared, "sleep 60").await?);
+    wait_for_task_condition(shared, 0, |task| task.is_running()).await?;
+
+    // Restart the daemon while the task is still running.
+    assert_success(shutdown_daemon(shared).await?);
+    wait_for_shutdown(&mut child).await?;
+    let mut child = standalone_daemon(shared).await?;
+
+    let message = KillMessage {
+        tasks: TaskSelection::TaskIds(vec![0]),
+        signal: None,
+    };
+    assert_success(send_message(shared, message).await?);
+
+    let task = wait_for_task_condition(shared, 0, |task| task.is_done()).await?;
+    assert_eq!(task.status, TaskStatus::Done(TaskResult::Killed));
+
+    child.kill()?;
+    Ok(())
+}
+
+/// The pid of a task might be reused by an unrelated process, e.g. after a reboot.
+/// Such processes aren't mistaken for the task, as they've been started at a different time.
+#[tokio::test]
+async fn test_reattach_reused_pid() -> Result<()> {
+    let (settings, _tempdir) = reattach_setup()?;
+    let mut child = standalone_daemon(&settings.shared).await?;
+    let shared = &settings.shared;
+
+    assert_success(add_task(shared, "sleep 60").await?);
+    let task = wait_for_task_condition(shared, 0, |task| task.is_running()).await?;
+    let pid = task.pid.context("Running task has no pid")?;
+
+    assert_success(shutdown_daemon(shared).await?);
+    wait_for_shutdown(&mut child).await?;
+
+    // Pretend that the task has been started long before its process.
+    let state_path = shared.pueue_directory().join("state.json");
+    let mut state: Value = serde_json::from_str(&std::fs::read_to_string(&state_path)?)?;
+    state["tasks"]["0"]["start"] = serde_json::to_value(Local::now() - Duration::hours(1))?;
+    std::fs::write(&state_path, serde_json::to_string(&state)?)?;
+
+    let mut child = standalone_daemon(shared).await?;
+
+    // The task is considered finished, while the unrelated process is left alone.
+    let task = wait_for_task_condition(shared, 0, |task| task.is_done()).await?;
+    assert_eq!(task.status, TaskStatus::Done(TaskResult::Killed));
+    assert!(pueue_lib::process_helper::process_exists(pid));
+
+    pueue_lib::process_helper::send_signal_to_process_group(
+        pid,
+        pueue_lib::network::message::Signal::SigKill,
+    )?;
+    child.kill()?;
+    Ok(())
+}
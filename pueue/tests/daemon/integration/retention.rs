@@ -0,0 +1,73 @@
+use anyhow::{Context, Result};
+use pretty_assertions::assert_eq;
+
+use pueue_lib::settings::RetentionPolicy;
+
+use crate::helper::*;
+
+/// Only the configured amount of finished tasks is kept.
+/// The tasks that finished first are removed together with their logs.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_max_finished_tasks() -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    settings.daemon.retention = RetentionPolicy {
+        max_finished_tasks: Some(2),
+        ..Default::default()
+    };
+    settings
+        .save(&Some(tempdir.path().join("pueue.yml")))
+        .context("Couldn't write pueue config to temporary directory")?;
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+    let shared = &daemon.settings.shared;
+
+    for _ in 0..4 {
+        assert_success(add_task(shared, "echo test").await?);
+    }
+    wait_for_task_condition(shared, 3, |task| task.is_done()).await?;
+    wait_for_task_absence(shared, 1).await?;
+
+    let state = get_state(shared).await?;
+    assert_eq!(state.tasks.keys().copied().collect::<Vec<_>>(), vec![2, 3]);
+
+    let task_logs = shared.pueue_directory().join("task_logs");
+    assert!(!task_logs.join("0.log").exists());
+    assert!(!task_logs.join("1.log").exists());
+    assert!(task_logs.join("3.log").exists());
+
+    Ok(())
+}
+
+/// Group policies replace the global one and may only remove successful tasks.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_group_retention_successful_only() -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    settings.daemon.retention = RetentionPolicy {
+        max_finished_tasks: Some(0),
+        ..Default::default()
+    };
+    settings.daemon.group_retention.insert(
+        PUEUE_DEFAULT_GROUP.into(),
+        RetentionPolicy {
+            max_finished_tasks: Some(1),
+            successful_only: true,
+            ..Default::default()
+        },
+    );
+    settings
+        .save(&Some(tempdir.path().join("pueue.yml")))
+        .context("Couldn't write pueue config to temporary directory")?;
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+    let shared = &daemon.settings.shared;
+
+    assert_success(add_task(shared, "exit 1").await?);
+    assert_success(add_task(shared, "echo test").await?);
+    assert_success(add_task(shared, "echo test").await?);
+    wait_for_task_condition(shared, 2, |task| task.is_done()).await?;
+    wait_for_task_absence(shared, 1).await?;
+
+    // The failed task is kept, as well as the last successful one.
+    let state = get_state(shared).await?;
+    assert_eq!(state.tasks.keys().copied().collect::<Vec<_>>(), vec![0, 2]);
+
+    Ok(())
+}
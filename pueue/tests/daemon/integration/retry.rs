@@ -0,0 +1,88 @@
+use anyhow::Result;
+
+use pueue_lib::network::message::*;
+use pueue_lib::task::*;
+
+use crate::helper::*;
+
+/// Create an AddMessage for a task that will be retried according to the given policy.
+fn create_retry_message(
+    daemon: &PueueDaemon,
+    command: &str,
+    max_retries: usize,
+    delay: u64,
+) -> AddMessage {
+    let mut message = create_add_message(&daemon.settings.shared, command);
+    message.retry_policy = Some(RetryPolicy {
+        max_retries,
+        delay,
+        backoff: RetryBackoff::Fixed,
+    });
+
+    message
+}
+
+/// A failing task is retried until it runs out of retries.
+/// Every finished run is recorded in the task's attempt history.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_retry_until_exhausted() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    let message = create_retry_message(&daemon, "exit 3", 2, 0);
+    assert_success(send_message(shared, message).await?);
+
+    let task = wait_for_task_condition(shared, 0, |task| task.is_done()).await?;
+    assert_eq!(task.status, TaskStatus::Done(TaskResult::Failed(3)));
+
+    // The initial run and the first retry are in the history, the last run is the task itself.
+    assert_eq!(task.attempts.len(), 2);
+    for attempt in task.attempts {
+        assert_eq!(attempt.result, TaskResult::Failed(3));
+        assert!(attempt.start.is_some());
+        assert!(attempt.end.is_some());
+    }
+
+    Ok(())
+}
+
+/// A task that succeeds on a retry finishes successfully and no further retries are done.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_retry_until_success() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    // This command fails on the first run and succeeds on the second.
+    let command = "if [ -f retry_marker ]; then exit 0; else touch retry_marker; exit 1; fi";
+    let message = create_retry_message(&daemon, command, 5, 0);
+    assert_success(send_message(shared, message).await?);
+
+    let task = wait_for_task_condition(shared, 0, |task| task.is_done()).await?;
+    assert_eq!(task.status, TaskStatus::Done(TaskResult::Success));
+    assert_eq!(task.attempts.len(), 1);
+    assert_eq!(task.attempts[0].result, TaskResult::Failed(1));
+
+    Ok(())
+}
+
+/// Retries with a delay are stashed until the delay elapsed.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_retry_with_delay() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    let message = create_retry_message(&daemon, "exit 1", 1, 1000);
+    assert_success(send_message(shared, message).await?);
+
+    let task = wait_for_task_condition(shared, 0, |task| task.is_stashed()).await?;
+    assert_eq!(task.attempts.len(), 1);
+    let TaskStatus::Stashed {
+        enqueue_at: Some(enqueue_at),
+    } = task.status
+    else {
+        panic!("Expected the task to be stashed with an enqueue date");
+    };
+    assert!(enqueue_at > chrono::Local::now() + chrono::Duration::seconds(900));
+
+    Ok(())
+}
@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+
+use pueue_lib::network::message::*;
+use pueue_lib::schedule::ScheduleTrigger;
+use pueue_lib::state::PUEUE_DEFAULT_GROUP;
+
+use crate::helper::*;
+
+/// Create a bare AddScheduleMessage for testing.
+fn create_schedule_message(
+    daemon: &PueueDaemon,
+    command: &str,
+    trigger: ScheduleTrigger,
+) -> AddScheduleMessage {
+    AddScheduleMessage {
+        trigger,
+        command: command.into(),
+        path: daemon.settings.shared.pueue_directory(),
+        envs: HashMap::new(),
+        group: PUEUE_DEFAULT_GROUP.to_string(),
+        label: None,
+        skip_if_running: false,
+        catch_up: false,
+    }
+}
+
+/// A schedule creates new tasks from its template, whenever it fires.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_schedule_creates_tasks() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    let message = create_schedule_message(&daemon, "ls", ScheduleTrigger::Interval(1));
+    assert_success(send_message(shared, ScheduleMessage::Add(message)).await?);
+
+    // The first task is created after a second.
+    sleep_ms(1500).await;
+    let task = wait_for_task_condition(shared, 0, |task| task.is_done()).await?;
+    assert_eq!(task.command, "ls");
+
+    let state = get_state(shared).await?;
+    let schedule = state.schedules.get(&0).expect("Schedule should exist");
+    assert!(schedule.last_run.is_some());
+    assert!(schedule.next_run.unwrap() > schedule.last_run.unwrap());
+
+    Ok(())
+}
+
+/// Schedules that skip runs while the previous task is active, don't create duplicates.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_schedule_skip_if_running() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    let mut message = create_schedule_message(&daemon, "sleep 60", ScheduleTrigger::Interval(1));
+    message.skip_if_running = true;
+    assert_success(send_message(shared, ScheduleMessage::Add(message)).await?);
+
+    // Wait for a few runs of the schedule.
+    sleep_ms(3500).await;
+    let state = get_state(shared).await?;
+    assert_eq!(state.tasks.len(), 1, "Only a single task should be created");
+    assert_eq!(state.schedules.get(&0).unwrap().last_task_id, Some(0));
+
+    Ok(())
+}
+
+/// Paused schedules don't create any tasks and can be removed.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_schedule_pause_and_remove() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    let message = create_schedule_message(&daemon, "ls", ScheduleTrigger::Interval(1));
+    assert_success(send_message(shared, ScheduleMessage::Add(message)).await?);
+    assert_success(send_message(shared, ScheduleMessage::Pause(0)).await?);
+
+    sleep_ms(1500).await;
+    let state = get_state(shared).await?;
+    assert!(
+        state.tasks.is_empty(),
+        "Paused schedules shouldn't create tasks"
+    );
+    assert!(state.schedules.get(&0).unwrap().paused);
+
+    assert_success(send_message(shared, ScheduleMessage::Remove(0)).await?);
+    let state = get_state(shared).await?;
+    assert!(state.schedules.is_empty());
+
+    // Removing it a second time fails.
+    assert_failure(send_message(shared, ScheduleMessage::Remove(0)).await?);
+
+    Ok(())
+}
+
+/// Invalid cron expressions are rejected.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_schedule_invalid_cron() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    let trigger = ScheduleTrigger::Cron("61 * * * *".into());
+    let message = create_schedule_message(&daemon, "ls", trigger);
+    assert_failure(send_message(shared, ScheduleMessage::Add(message)).await?);
+
+    Ok(())
+}
+
+/// Runs that have been missed while the daemon was down are only caught up, if requested.
+#[tokio::test]
+async fn test_schedule_catch_up_after_restart() -> Result<()> {
+    let (settings, _tempdir) = daemon_base_setup()?;
+    let mut child = standalone_daemon(&settings.shared).await?;
+    let shared = &settings.shared;
+
+    let add_message = |catch_up: bool| AddScheduleMessage {
+        trigger: ScheduleTrigger::Interval(2),
+        command: "ls".into(),
+        path: shared.pueue_directory(),
+        envs: HashMap::new(),
+        group: PUEUE_DEFAULT_GROUP.to_string(),
+        label: None,
+        skip_if_running: false,
+        catch_up,
+    };
+    assert_success(send_message(shared, ScheduleMessage::Add(add_message(true))).await?);
+    assert_success(send_message(shared, ScheduleMessage::Add(add_message(false))).await?);
+
+    // Shut the daemon down before any schedule fires and wait until the runs are missed.
+    assert_success(shutdown_daemon(shared).await?);
+    wait_for_shutdown(&mut child).await?;
+    sleep_ms(2500).await;
+
+    // Boot it up again and give it some time to handle the schedules.
+    let mut child = standalone_daemon(shared).await?;
+    sleep_ms(500).await;
+
+    let state = get_state(shared).await?;
+    let catch_up = state.schedules.get(&0).unwrap();
+    assert!(
+        catch_up.last_run.is_some(),
+        "The missed run should be caught up"
+    );
+    let skipped = state.schedules.get(&1).unwrap();
+    assert!(
+        skipped.last_run.is_none(),
+        "The missed run should be skipped"
+    );
+    assert_eq!(state.tasks.len(), 1);
+
+    child.kill()?;
+    Ok(())
+}
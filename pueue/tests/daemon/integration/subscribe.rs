@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use pretty_assertions::assert_eq;
+
+use pueue_lib::event::Event;
+use pueue_lib::network::message::*;
+use pueue_lib::network::protocol::{
+    receive_message, send_message as internal_send_message, GenericStream,
+};
+use pueue_lib::state::GroupStatus;
+use pueue_lib::task::{TaskResult, TaskStatus};
+
+use crate::helper::*;
+
+/// Receive the next event from a subscription.
+async fn next_event(stream: &mut GenericStream) -> Result<Event> {
+    let message = tokio::time::timeout(Duration::from_secs(2), receive_message(stream)).await??;
+    let Message::Event(event) = message else {
+        bail!("Expected an event, got {message:?}");
+    };
+
+    Ok(event)
+}
+
+/// Subscribed clients get notified about the lifecycle of tasks.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_subscribe_task_events() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    let mut stream = get_authenticated_stream(shared).await?;
+    internal_send_message(Message::Subscribe, &mut stream).await?;
+    // Give the daemon a moment to register the subscription.
+    sleep_ms(100).await;
+
+    assert_success(add_task(shared, "ls").await?);
+
+    let Event::TaskAdded(task) = next_event(&mut stream).await? else {
+        bail!("Expected the task to be added first");
+    };
+    assert_eq!(task.id, 0);
+    assert_eq!(
+        next_event(&mut stream).await?,
+        Event::TaskStatusChanged {
+            task_id: 0,
+            previous: TaskStatus::Queued,
+            status: TaskStatus::Running,
+        }
+    );
+    assert_eq!(
+        next_event(&mut stream).await?,
+        Event::TaskStatusChanged {
+            task_id: 0,
+            previous: TaskStatus::Running,
+            status: TaskStatus::Done(TaskResult::Success),
+        }
+    );
+
+    assert_success(send_message(shared, Message::Remove(vec![0])).await?);
+    assert_eq!(next_event(&mut stream).await?, Event::TaskRemoved(0));
+
+    Ok(())
+}
+
+/// Changes are published as they happen, even if they're reverted right away.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_subscribe_short_lived_task() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    let mut stream = get_authenticated_stream(shared).await?;
+    internal_send_message(Message::Subscribe, &mut stream).await?;
+    sleep_ms(100).await;
+
+    let mut message = create_add_message(shared, "ls");
+    message.stashed = true;
+    assert_success(send_message(shared, message).await?);
+    assert_success(send_message(shared, Message::Remove(vec![0])).await?);
+
+    let Event::TaskAdded(task) = next_event(&mut stream).await? else {
+        bail!("Expected the task to be added first");
+    };
+    assert_eq!(task.id, 0);
+    assert_eq!(next_event(&mut stream).await?, Event::TaskRemoved(0));
+
+    Ok(())
+}
+
+/// Subscribed clients get notified about changes of groups.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_subscribe_group_events() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    let mut stream = get_authenticated_stream(shared).await?;
+    internal_send_message(Message::Subscribe, &mut stream).await?;
+    sleep_ms(100).await;
+
+    pause_tasks(shared, TaskSelection::Group(PUEUE_DEFAULT_GROUP.into())).await?;
+    assert_eq!(
+        next_event(&mut stream).await?,
+        Event::GroupStatusChanged {
+            group: PUEUE_DEFAULT_GROUP.into(),
+            status: GroupStatus::Paused,
+        }
+    );
+
+    let message = ParallelMessage {
+        parallel_tasks: 3,
+        group: PUEUE_DEFAULT_GROUP.into(),
+        global: false,
+    };
+    assert_success(send_message(shared, message).await?);
+    assert_eq!(
+        next_event(&mut stream).await?,
+        Event::ParallelTasksChanged {
+            group: PUEUE_DEFAULT_GROUP.into(),
+            parallel_tasks: 3,
+        }
+    );
+
+    Ok(())
+}
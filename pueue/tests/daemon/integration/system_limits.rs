@@ -0,0 +1,46 @@
+use anyhow::{Context, Result};
+use pretty_assertions::assert_eq;
+
+use pueue_lib::settings::SystemLimits;
+use pueue_lib::task::TaskStatus;
+
+use crate::helper::*;
+
+/// More memory than any test machine could ever have available.
+const UNREACHABLE_MEMORY: u64 = 1 << 60;
+
+/// Groups are held back while the system doesn't have enough free memory.
+/// The reason is exposed to clients via the group's state.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_group_held_back_by_memory() -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    settings.daemon.group_system_limits.insert(
+        "heavy".into(),
+        SystemLimits {
+            max_load_average: None,
+            min_free_memory: Some(UNREACHABLE_MEMORY),
+        },
+    );
+    settings
+        .save(&Some(tempdir.path().join("pueue.yml")))
+        .context("Couldn't write pueue config to temporary directory")?;
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+    let shared = &daemon.settings.shared;
+
+    add_group_with_slots(shared, "heavy", 1).await?;
+    assert_success(add_task_to_group(shared, "ls", "heavy").await?);
+    // The default group isn't affected by the limits of other groups.
+    assert_success(add_task(shared, "ls").await?);
+    wait_for_task_condition(shared, 1, |task| task.is_done()).await?;
+
+    let state = get_state(shared).await?;
+    assert_eq!(state.tasks[&0].status, TaskStatus::Queued);
+    let reason = state.groups["heavy"]
+        .blocked
+        .clone()
+        .context("The group should be held back")?;
+    assert!(reason.contains("free memory"), "Got reason: {reason}");
+    assert_eq!(state.groups[PUEUE_DEFAULT_GROUP].blocked, None);
+
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use anyhow::Result;
+use pretty_assertions::assert_eq;
+
+use pueue_lib::network::message::*;
+use pueue_lib::settings::Shared;
+use pueue_lib::task::*;
+
+use crate::helper::*;
+
+/// Create an AddMessage for a task array with the given parameters.
+fn create_array_message(shared: &Shared, command: &str, parameters: &[&str]) -> AddMessage {
+    let mut message = create_add_message(shared, command);
+    message.array = Some(parameters.iter().map(ToString::to_string).collect());
+
+    message
+}
+
+/// A task is added for each parameter, whose command and label are rendered from the templates.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_add_task_array() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    // A task before the array, so the array's id isn't the default.
+    assert_success(add_task(shared, "ls").await?);
+
+    let mut message = create_array_message(shared, "echo {{value}} && echo '&'", &["a&b", "c"]);
+    message.label = Some("step {{index}}".into());
+    message.print_task_id = true;
+    let response = send_message(shared, message).await?;
+    assert_eq!(response, create_success_message("1 2"));
+
+    let state = get_state(shared).await?;
+    let task = &state.tasks[&1];
+    assert_eq!(task.command, "echo a&b && echo '&'");
+    assert_eq!(task.label, Some("step 0".to_string()));
+    assert_eq!(
+        task.array,
+        Some(TaskArrayRef {
+            id: 1,
+            index: 0,
+            value: "a&b".into()
+        })
+    );
+    let task = &state.tasks[&2];
+    assert_eq!(task.command, "echo c && echo '&'");
+    assert_eq!(task.label, Some("step 1".to_string()));
+    assert_eq!(
+        task.array,
+        Some(TaskArrayRef {
+            id: 1,
+            index: 1,
+            value: "c".into()
+        })
+    );
+
+    Ok(())
+}
+
+/// Invalid task arrays are rejected, without adding any tasks.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_invalid_task_array() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    // Arrays need parameters.
+    let message = create_array_message(shared, "echo {{value}}", &[]);
+    assert_failure(send_message(shared, message).await?);
+
+    // Unknown variables can't be rendered.
+    let message = create_array_message(shared, "echo {{unknown}}", &["a", "b"]);
+    assert_failure(send_message(shared, message).await?);
+
+    // The size of arrays is limited, even if the client doesn't check it.
+    let mut message = create_add_message(shared, "echo {{value}}");
+    message.array = Some(vec!["a".to_string(); MAX_ARRAY_SIZE + 1]);
+    assert_failure(send_message(shared, message).await?);
+
+    // Tasks may only join arrays of tasks that are restarted.
+    let mut message = create_add_message(shared, "echo test");
+    message.array_member = Some(TaskArrayRef {
+        id: 0,
+        index: 0,
+        value: "a".to_string(),
+    });
+    assert_failure(send_message(shared, message).await?);
+
+    let state = get_state(shared).await?;
+    assert!(state.tasks.is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,76 @@
+use anyhow::{Context, Result};
+
+use pueue_lib::network::message::*;
+use pueue_lib::task::*;
+
+use crate::helper::*;
+
+/// Create an AddMessage for a task with the given timeout in seconds.
+fn create_timeout_message(daemon: &PueueDaemon, command: &str, timeout: u64) -> AddMessage {
+    let mut message = create_add_message(&daemon.settings.shared, command);
+    message.timeout = Some(timeout);
+
+    message
+}
+
+/// A task that exceeds its timeout is terminated and marked as timed out.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_task_times_out() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    let message = create_timeout_message(&daemon, "sleep 60", 1);
+    assert_success(send_message(shared, message).await?);
+    wait_for_task_condition(shared, 0, |task| task.is_running()).await?;
+
+    // Give the task some time to exceed its timeout.
+    sleep_ms(1000).await;
+    let task = wait_for_task_condition(shared, 0, |task| task.is_done()).await?;
+    assert_eq!(task.status, TaskStatus::Done(TaskResult::TimedOut));
+
+    Ok(())
+}
+
+/// Tasks that finish in time aren't affected by their timeout.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_task_finishes_in_time() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    let message = create_timeout_message(&daemon, "ls", 60);
+    assert_success(send_message(shared, message).await?);
+
+    let task = wait_for_task_condition(shared, 0, |task| task.is_done()).await?;
+    assert_eq!(task.status, TaskStatus::Done(TaskResult::Success));
+
+    Ok(())
+}
+
+/// Tasks that ignore the SIGTERM are killed after the grace period.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_timeout_kill_after_grace_period() -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    settings.daemon.timeout_grace_period = 1;
+    settings
+        .save(&Some(tempdir.path().join("pueue.yml")))
+        .context("Couldn't write pueue config to temporary directory")?;
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+    let shared = &daemon.settings.shared;
+
+    // This task ignores SIGTERM, which means that it has to be killed.
+    let message = create_timeout_message(&daemon, "trap '' TERM; sleep 60", 1);
+    assert_success(send_message(shared, message).await?);
+    wait_for_task_condition(shared, 0, |task| task.is_running()).await?;
+
+    // The task should still be running after the SIGTERM.
+    sleep_ms(1500).await;
+    let task = get_task(shared, 0).await?;
+    assert!(task.is_running(), "The task should ignore SIGTERM");
+
+    // Give the grace period enough time to pass.
+    sleep_ms(1000).await;
+    let task = wait_for_task_condition(shared, 0, |task| task.is_done()).await?;
+    assert_eq!(task.status, TaskStatus::Done(TaskResult::TimedOut));
+
+    Ok(())
+}
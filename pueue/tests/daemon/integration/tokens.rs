@@ -0,0 +1,148 @@
+use std::fs::write;
+
+use anyhow::{bail, Context, Result};
+use pretty_assertions::assert_eq;
+
+use pueue_lib::network::message::*;
+use pueue_lib::settings::Shared;
+use pueue_lib::state::State;
+
+use crate::helper::*;
+
+const TOKENS: &str = r#"
+alice:
+  token: alice_token
+  role: submit
+bob:
+  token: bob_token
+  role: submit
+carol:
+  token: carol_token
+  role: read_only
+"#;
+
+/// Get the settings of a client, that authenticates as the principal with the given token.
+fn shared_with_token(shared: &Shared, token: &str) -> Result<Shared> {
+    let path = shared.pueue_directory().join(format!("{token}_secret"));
+    write(&path, format!("{token}\n"))?;
+
+    let mut shared = shared.clone();
+    shared.shared_secret_path = Some(path);
+    Ok(shared)
+}
+
+/// Principals may only do what their role allows and `submit` principals may only manage
+/// the tasks they added themselves.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_token_permissions() -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    let tokens_path = tempdir.path().join("tokens.yml");
+    write(&tokens_path, TOKENS)?;
+    settings.daemon.tokens_path = Some(tokens_path);
+    settings
+        .save(&Some(tempdir.path().join("pueue.yml")))
+        .context("Couldn't write pueue config to temporary directory")?;
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+    let shared = &daemon.settings.shared;
+    let alice = shared_with_token(shared, "alice_token")?;
+    let bob = shared_with_token(shared, "bob_token")?;
+    let carol = shared_with_token(shared, "carol_token")?;
+
+    // Tasks are owned by the principal that added them.
+    assert_success(add_task(&alice, "sleep 60").await?);
+    assert_success(add_task(shared, "sleep 60").await?);
+    let state = get_state(shared).await?;
+    assert_eq!(state.tasks[&0].owner, Some("alice".to_string()));
+    assert_eq!(state.tasks[&1].owner, None);
+    wait_for_task_condition(shared, 0, |task| task.is_running()).await?;
+
+    // Read-only principals may look, but not touch.
+    get_state(&carol).await?;
+    assert_failure(add_task(&carol, "ls").await?);
+
+    // Others may not manage the tasks of a principal.
+    let kill_alice = KillMessage {
+        tasks: TaskSelection::TaskIds(vec![0]),
+        signal: None,
+    };
+    assert_failure(send_message(&bob, kill_alice.clone()).await?);
+    assert_failure(send_message(&alice, Message::Remove(vec![1])).await?);
+    assert_success(send_message(&alice, kill_alice).await?);
+
+    // Operations that affect the tasks of others require the admin role.
+    let kill_all = KillMessage {
+        tasks: TaskSelection::All,
+        signal: None,
+    };
+    assert_failure(send_message(&alice, kill_all.clone()).await?);
+    assert_failure(send_message(&alice, Shutdown::Graceful).await?);
+
+    // The shared secret still grants full access.
+    assert_success(send_message(shared, kill_all).await?);
+
+    Ok(())
+}
+
+/// Principals only see the environment variables of the tasks they added themselves.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_foreign_envs_are_redacted() -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    let tokens_path = tempdir.path().join("tokens.yml");
+    write(&tokens_path, TOKENS)?;
+    settings.daemon.tokens_path = Some(tokens_path);
+    settings
+        .save(&Some(tempdir.path().join("pueue.yml")))
+        .context("Couldn't write pueue config to temporary directory")?;
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+    let shared = &daemon.settings.shared;
+    let alice = shared_with_token(shared, "alice_token")?;
+    let bob = shared_with_token(shared, "bob_token")?;
+    let carol = shared_with_token(shared, "carol_token")?;
+
+    let mut message = create_add_message(&alice, "ls");
+    message.stashed = true;
+    message
+        .envs
+        .insert("ALICE_SECRET".to_string(), "hunter2".to_string());
+    assert_success(send_message(&alice, message).await?);
+
+    let has_secret = |state: &State| state.tasks[&0].envs.contains_key("ALICE_SECRET");
+    assert!(has_secret(&*get_state(&alice).await?));
+    assert!(has_secret(&*get_state(shared).await?));
+    assert!(!has_secret(&*get_state(&bob).await?));
+    assert!(!has_secret(&*get_state(&carol).await?));
+
+    // The logs contain the task as well.
+    let log_request = LogRequestMessage {
+        task_ids: vec![0],
+        send_logs: false,
+        lines: None,
+        stream: None,
+        timestamps: false,
+    };
+    let response = send_message(&bob, log_request).await?;
+    let Message::LogResponse(logs) = response else {
+        bail!("Didn't get log response: {response:?}");
+    };
+    assert!(logs[&0].task.envs.is_empty());
+
+    Ok(())
+}
+
+/// Tokens that aren't in the token file are rejected.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_unknown_token() -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    let tokens_path = tempdir.path().join("tokens.yml");
+    write(&tokens_path, TOKENS)?;
+    settings.daemon.tokens_path = Some(tokens_path);
+    settings
+        .save(&Some(tempdir.path().join("pueue.yml")))
+        .context("Couldn't write pueue config to temporary directory")?;
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+    let mallory = shared_with_token(&daemon.settings.shared, "mallory_token")?;
+
+    assert!(send_message(&mallory, Message::Status).await.is_err());
+
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use pretty_assertions::assert_eq;
+
+use pueue_lib::network::message::*;
+use pueue_lib::settings::Shared;
+use pueue_lib::task::*;
+use pueue_lib::workflow::{Workflow, WorkflowRef};
+
+use crate::helper::*;
+
+const WORKFLOW: &str = r#"
+name: release
+steps:
+  publish:
+    command: "ls"
+    needs: [build, test]
+  build:
+    command: "ls"
+  test:
+    command: "ls"
+    needs: [build]
+"#;
+
+fn create_workflow_message(shared: &Shared, workflow: &str) -> Result<WorkflowMessage> {
+    Ok(WorkflowMessage {
+        workflow: serde_yaml::from_str::<Workflow>(workflow)?,
+        path: shared.pueue_directory(),
+        envs: HashMap::new(),
+        stashed: false,
+    })
+}
+
+/// A task is created for each step, which depends on the tasks of the steps it needs.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_workflow_run() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    let message = create_workflow_message(shared, WORKFLOW)?;
+    assert_success(send_message(shared, message).await?);
+
+    // The steps are created in the order of their dependencies.
+    let state = get_state(shared).await?;
+    let steps: Vec<(usize, Option<WorkflowRef>, Vec<usize>)> = state
+        .tasks
+        .values()
+        .map(|task| (task.id, task.workflow.clone(), task.dependencies.clone()))
+        .collect();
+    let workflow_ref = |step: &str| {
+        Some(WorkflowRef {
+            workflow: "release".into(),
+            run: 0,
+            step: step.into(),
+        })
+    };
+    assert_eq!(
+        steps,
+        vec![
+            (0, workflow_ref("build"), vec![]),
+            (1, workflow_ref("test"), vec![0]),
+            (2, workflow_ref("publish"), vec![0, 1]),
+        ]
+    );
+    assert_eq!(state.tasks[&2].label, Some("publish".to_string()));
+
+    // All steps run to completion.
+    for task_id in 0..3 {
+        let task = wait_for_task_condition(shared, task_id, |task| task.is_done()).await?;
+        assert_eq!(task.status, TaskStatus::Done(TaskResult::Success));
+    }
+
+    Ok(())
+}
+
+/// Invalid workflows are rejected, without creating any tasks.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_invalid_workflow() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    // The steps depend on each other in a cycle.
+    let cycle = r#"
+name: cycle
+steps:
+  a:
+    command: "ls"
+    needs: [b]
+  b:
+    command: "ls"
+    needs: [a]
+"#;
+    let message = create_workflow_message(shared, cycle)?;
+    assert_failure(send_message(shared, message).await?);
+
+    // The second step's group doesn't exist.
+    let unknown_group = r#"
+name: unknown_group
+steps:
+  a:
+    command: "ls"
+  b:
+    command: "ls"
+    group: doesnt_exist
+"#;
+    let message = create_workflow_message(shared, unknown_group)?;
+    assert_failure(send_message(shared, message).await?);
+
+    let state = get_state(shared).await?;
+    assert!(state.tasks.is_empty());
+
+    Ok(())
+}
@@ -2,10 +2,13 @@ use std::fs::File;
 use std::io::prelude::*;
 
 use anyhow::{Context, Result};
+use pretty_assertions::assert_eq;
 use pueue::daemon::state_helper::restore_state;
+use pueue::daemon::state_store::get_state_store;
+use rusqlite::Connection;
 use tempfile::TempDir;
 
-use pueue_lib::settings::Settings;
+use pueue_lib::settings::{Settings, StateBackend};
 
 /// From 0.12.2 on, we aim to have full backward compatibility.
 /// For this reason, an old v0.12.2 serialized state has been checked in.
@@ -31,10 +34,74 @@ fn test_restore_from_old_state() -> Result<()> {
     let mut settings = Settings::default();
     settings.shared.pueue_directory = Some(temp_path.to_path_buf());
 
-    let state = restore_state(&settings.shared.pueue_directory())
+    let state = restore_state(&get_state_store(&settings)?, &settings)
         .context("Failed to restore state in test")?;
 
     assert!(state.is_some());
 
     Ok(())
 }
+
+/// The SQLite backend imports an existing `state.json` on its first start.
+/// The imported file is renamed, so it isn't restored again by the JSON backend.
+#[test]
+fn test_import_old_state_into_sqlite() -> Result<()> {
+    better_panic::install();
+    let old_state = include_str!("data/v2.0.0_state.json");
+
+    let temp_dir = TempDir::new()?;
+    let temp_path = temp_dir.path();
+    std::fs::write(temp_path.join("state.json"), old_state)?;
+
+    let mut settings = Settings::default();
+    settings.shared.pueue_directory = Some(temp_path.to_path_buf());
+    settings.daemon.state_backend = StateBackend::Sqlite;
+
+    let imported = restore_state(&get_state_store(&settings)?, &settings)
+        .context("Failed to import state in test")?
+        .context("Expected the old state to be imported")?;
+    assert!(!temp_path.join("state.json").exists());
+    assert!(temp_path.join("state.json.imported").exists());
+
+    // The state is now restored from the database.
+    let restored = restore_state(&get_state_store(&settings)?, &settings)
+        .context("Failed to restore state in test")?
+        .context("Expected the imported state to be restored")?;
+    assert_eq!(imported.tasks, restored.tasks);
+    assert_eq!(imported.groups, restored.groups);
+
+    Ok(())
+}
+
+/// Databases created with the first schema version of the SQLite backend are migrated and
+/// restored.
+/// For this reason, a dump of such a database has been checked in.
+///
+/// Just like with the old JSON state, we have to be able to restore from it at all costs.
+#[test]
+fn test_restore_from_sqlite_schema_v1() -> Result<()> {
+    better_panic::install();
+    let old_database = include_str!("data/sqlite_schema_v1_state.sql");
+
+    let temp_dir = TempDir::new()?;
+    let temp_path = temp_dir.path();
+    Connection::open(temp_path.join("state.sqlite"))?.execute_batch(old_database)?;
+
+    let mut settings = Settings::default();
+    settings.shared.pueue_directory = Some(temp_path.to_path_buf());
+    settings.daemon.state_backend = StateBackend::Sqlite;
+
+    let state = restore_state(&get_state_store(&settings)?, &settings)
+        .context("Failed to restore state in test")?
+        .context("Expected the old state to be restored")?;
+    assert_eq!(
+        state.tasks.keys().copied().collect::<Vec<_>>(),
+        vec![0, 1, 2, 3]
+    );
+    assert_eq!(
+        state.groups.keys().collect::<Vec<_>>(),
+        vec!["default", "test"]
+    );
+
+    Ok(())
+}
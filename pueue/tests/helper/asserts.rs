@@ -52,6 +52,8 @@ pub async fn assert_worker_envs(
             task_ids: vec![task_id],
             send_logs: true,
             lines: None,
+            stream: None,
+            timestamps: false,
         },
     )
     .await?;
@@ -24,6 +24,14 @@ pub fn create_add_message(shared: &Shared, command: &str) -> AddMessage {
         priority: None,
         label: None,
         print_task_id: false,
+        retry_policy: None,
+        timeout: None,
+        resources: Default::default(),
+        separate_output: false,
+        dependency_conditions: Default::default(),
+        array: None,
+        array_member: None,
+        batch_dependencies: Vec::new(),
     }
 }
 
@@ -0,0 +1,334 @@
+use std::collections::VecDeque;
+use std::fs::{create_dir, create_dir_all, remove_dir_all, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Local};
+use log::error;
+use serde_derive::{Deserialize, Serialize};
+use snap::read::FrameDecoder;
+use snap::write::FrameEncoder;
+
+use crate::error::Error;
+use crate::log::{move_log_files, open_log_file, read_last_lines_of, LogFile};
+use crate::task::Task;
+
+/// Only one thread may append to the archive at a time, as the archive ids of new entries are
+/// determined by the last entry of the archive.
+static ARCHIVE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Used to get a unique staging directory for each [PendingArchive].
+static NEXT_STAGING_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// A finished task, which has been moved to the archive after it has been removed.
+#[derive(PartialEq, Eq, Clone, Debug, Deserialize, Serialize)]
+pub struct ArchivedTask {
+    /// The id of this entry in the archive.
+    /// Unlike the ids of tasks, these are never reused.
+    pub archive_id: usize,
+    /// The point in time at which the task has been archived.
+    pub archived_at: DateTime<Local>,
+    pub task: Task,
+}
+
+/// Get the path to the archive file.
+/// Each line of this file contains a single [ArchivedTask] as JSON.
+pub fn get_archive_path(path: &Path) -> PathBuf {
+    path.join("archive.jsonl")
+}
+
+/// Get the path to an archived log file.
+/// Archived logs are always compressed using [snap].
+pub fn get_archived_log_path(path: &Path, archive_id: usize, file: LogFile) -> PathBuf {
+    path.join("archive")
+        .join(format!("{archive_id}.{}.sz", file.extension()))
+}
+
+/// Read the newest tasks from the archive, ordered from the oldest to the newest entry.
+/// Entries that cannot be read are skipped.
+///
+/// - `before` Only read entries whose archive id is lower than this. This allows to page
+///   through the archive.
+/// - `limit` Only keep the last X matching entries.
+pub fn read_archive(
+    path: &Path,
+    before: Option<usize>,
+    limit: Option<usize>,
+) -> Result<Vec<ArchivedTask>, Error> {
+    let mut tasks = VecDeque::new();
+    for_each_entry(path, |task| {
+        if before.map_or(false, |before| task.archive_id >= before) {
+            return ControlFlow::Break(());
+        }
+
+        tasks.push_back(task);
+        if limit.map_or(false, |limit| tasks.len() > limit) {
+            tasks.pop_front();
+        }
+        ControlFlow::Continue(())
+    })?;
+
+    Ok(tasks.into())
+}
+
+/// Find a single task in the archive.
+pub fn find_archived_task(path: &Path, archive_id: usize) -> Result<Option<ArchivedTask>, Error> {
+    let mut found = None;
+    for_each_entry(path, |task| {
+        if task.archive_id != archive_id {
+            return ControlFlow::Continue(());
+        }
+
+        found = Some(task);
+        ControlFlow::Break(())
+    })?;
+
+    Ok(found)
+}
+
+/// Call `handle` for each valid entry of the archive, from the oldest to the newest entry,
+/// until it breaks.
+fn for_each_entry(
+    path: &Path,
+    mut handle: impl FnMut(ArchivedTask) -> ControlFlow<()>,
+) -> Result<(), Error> {
+    let archive_path = get_archive_path(path);
+    if !archive_path.exists() {
+        return Ok(());
+    }
+
+    let file = File::open(&archive_path)
+        .map_err(|err| Error::IoPathError(archive_path.clone(), "opening archive", err))?;
+    for line in BufReader::new(file).lines() {
+        let line =
+            line.map_err(|err| Error::IoPathError(archive_path.clone(), "reading archive", err))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match serde_json::from_str::<ArchivedTask>(&line) {
+            Ok(task) => {
+                if handle(task).is_break() {
+                    break;
+                }
+            }
+            Err(err) => error!("Skipping invalid entry in archive: {err}"),
+        }
+    }
+
+    Ok(())
+}
+
+/// Get the archive id of the next archived task.
+///
+/// Entries are only ever appended to the archive, so only its last line has to be read.
+/// If that line is invalid, the whole archive is searched for the latest valid entry instead.
+fn next_archive_id(path: &Path) -> Result<usize, Error> {
+    let archive_path = get_archive_path(path);
+    if !archive_path.exists() {
+        return Ok(0);
+    }
+
+    let mut file = File::open(&archive_path)
+        .map_err(|err| Error::IoPathError(archive_path.clone(), "opening archive", err))?;
+    let last_line = read_last_line(&mut file)
+        .map_err(|err| Error::IoPathError(archive_path, "reading archive", err))?;
+    if let Ok(task) = serde_json::from_slice::<ArchivedTask>(&last_line) {
+        return Ok(task.archive_id + 1);
+    }
+
+    Ok(read_archive(path, None, Some(1))?
+        .last()
+        .map_or(0, |task| task.archive_id + 1))
+}
+
+/// Read the last non-empty line of a file by reading it backwards in chunks.
+fn read_last_line(file: &mut File) -> io::Result<Vec<u8>> {
+    const CHUNK_SIZE: u64 = 16 * 1024;
+
+    let mut position = file.seek(SeekFrom::End(0))?;
+    let mut line: Vec<u8> = Vec::new();
+    while position > 0 {
+        let chunk_size = CHUNK_SIZE.min(position);
+        position -= chunk_size;
+        file.seek(SeekFrom::Start(position))?;
+        let mut chunk = vec![0; chunk_size as usize];
+        file.read_exact(&mut chunk)?;
+        chunk.append(&mut line);
+        line = chunk;
+
+        // Ignore trailing whitespace, such as the newline after the last entry.
+        let end = line
+            .iter()
+            .rposition(|byte| !byte.is_ascii_whitespace())
+            .map_or(0, |end| end + 1);
+        line.truncate(end);
+        if let Some(start) = line.iter().rposition(|byte| *byte == b'\n') {
+            return Ok(line.split_off(start + 1));
+        }
+    }
+
+    Ok(line)
+}
+
+/// Finished tasks, which are about to be moved to the archive.
+///
+/// Their logs have already been moved to a staging directory, so the tasks can be removed and
+/// their ids can be reused right away. The expensive part, compressing the logs into the archive,
+/// is done by [PendingArchive::write] afterwards.
+pub struct PendingArchive {
+    path: PathBuf,
+    staging_dir: PathBuf,
+    tasks: Vec<Task>,
+}
+
+/// Prepare finished tasks for being archived, by moving their logs to a staging directory.
+/// This is cheap, as the log files are only renamed.
+///
+/// If the logs cannot be moved, they're moved back and nothing is archived.
+pub fn prepare_archive(path: &Path, tasks: Vec<Task>) -> Result<PendingArchive, Error> {
+    let staging_dir = create_staging_dir(path)?;
+    for task in &tasks {
+        if let Err(err) = move_log_files(task.id, path, &staging_dir) {
+            for task in &tasks {
+                if let Err(err) = move_log_files(task.id, &staging_dir, path) {
+                    error!("Failed to restore logs of task {}: {err}", task.id);
+                }
+            }
+            let _ = remove_dir_all(&staging_dir);
+            return Err(err);
+        }
+    }
+
+    Ok(PendingArchive {
+        path: path.to_path_buf(),
+        staging_dir,
+        tasks,
+    })
+}
+
+/// Create a new, empty staging directory for the logs of tasks that're about to be archived.
+fn create_staging_dir(path: &Path) -> Result<PathBuf, Error> {
+    let pending_dir = path.join("archive").join("pending");
+    create_dir_all(&pending_dir).map_err(|err| {
+        Error::IoPathError(pending_dir.clone(), "creating archive directory", err)
+    })?;
+
+    loop {
+        let staging_id = NEXT_STAGING_ID.fetch_add(1, Ordering::Relaxed);
+        let staging_dir = pending_dir.join(staging_id.to_string());
+        match create_dir(&staging_dir) {
+            Ok(()) => return Ok(staging_dir),
+            // Left over by a previous run of the daemon.
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => continue,
+            Err(err) => {
+                return Err(Error::IoPathError(
+                    staging_dir,
+                    "creating staging directory",
+                    err,
+                ))
+            }
+        }
+    }
+}
+
+impl PendingArchive {
+    /// Append the tasks to the archive, together with their compressed logs.
+    /// The staging directory is removed afterwards, unless anything went wrong.
+    ///
+    /// Returns the archive ids of the tasks in the same order.
+    pub fn write(self) -> Result<Vec<usize>, Error> {
+        let _lock = ARCHIVE_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+        let first_id = next_archive_id(&self.path)?;
+
+        let log_dir = self.path.join("archive");
+        create_dir_all(&log_dir)
+            .map_err(|err| Error::IoPathError(log_dir, "creating archive directory", err))?;
+
+        let mut lines = String::new();
+        let mut archive_ids = Vec::new();
+        for (index, task) in self.tasks.iter().enumerate() {
+            let archive_id = first_id + index;
+
+            for file in LogFile::for_task(task, None) {
+                archive_log_file(&self.path, &self.staging_dir, task.id, archive_id, file)?;
+            }
+
+            let archived = ArchivedTask {
+                archive_id,
+                archived_at: Local::now(),
+                task: task.clone(),
+            };
+            let line = serde_json::to_string(&archived).map_err(|err| {
+                Error::Generic(format!("Failed to serialize archived task: {err}"))
+            })?;
+            lines.push_str(&line);
+            lines.push('\n');
+            archive_ids.push(archive_id);
+        }
+
+        // Write all entries at once, so they're either all appended or none.
+        let archive_path = get_archive_path(&self.path);
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&archive_path)
+            .and_then(|mut file| file.write_all(lines.as_bytes()))
+            .map_err(|err| Error::IoPathError(archive_path, "writing archive", err))?;
+
+        remove_dir_all(&self.staging_dir).map_err(|err| {
+            Error::IoPathError(self.staging_dir, "removing staging directory", err)
+        })?;
+
+        Ok(archive_ids)
+    }
+}
+
+/// Copy a log file of a task, including its rotated segments, from the staging directory into
+/// the archive. Missing log files are ignored.
+fn archive_log_file(
+    path: &Path,
+    staging_dir: &Path,
+    task_id: usize,
+    archive_id: usize,
+    file: LogFile,
+) -> Result<(), Error> {
+    let Ok((mut reader, _)) = open_log_file(task_id, staging_dir, file, None) else {
+        return Ok(());
+    };
+
+    let archived_path = get_archived_log_path(path, archive_id, file);
+    let archived = File::create(&archived_path)
+        .map_err(|err| Error::IoPathError(archived_path, "creating archived log file", err))?;
+    let mut compressor = FrameEncoder::new(archived);
+    io::copy(&mut reader, &mut compressor)
+        .map_err(|err| Error::IoError("archiving log file".to_string(), err))?;
+    compressor
+        .flush()
+        .map_err(|err| Error::IoError("archiving log file".to_string(), err))?;
+
+    Ok(())
+}
+
+/// Read an archived log file.
+/// If `lines` is given, only the last few lines are read.
+///
+/// Return type is `(Vec<u8>, bool)`
+/// - `Vec<u8>` The uncompressed output.
+/// - `bool` Whether the full output of the task has been read.
+pub fn read_archived_log(
+    path: &Path,
+    archive_id: usize,
+    file: LogFile,
+    lines: Option<usize>,
+) -> Result<(Vec<u8>, bool), Error> {
+    let archived_path = get_archived_log_path(path, archive_id, file);
+    let handle = File::open(&archived_path)
+        .map_err(|err| Error::IoPathError(archived_path, "opening archived log file", err))?;
+    let (output, position) = read_last_lines_of(FrameDecoder::new(handle), lines)?;
+
+    Ok((output, position == 0))
+}
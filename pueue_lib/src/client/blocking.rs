@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+
+use tokio::runtime::{Builder, Runtime};
+
+use super::{EventStream, FollowStream, PueueClient, TaskId};
+use crate::error::Error;
+use crate::event::Event;
+use crate::network::message::*;
+use crate::settings::Shared;
+use crate::state::{Group, State};
+
+/// A blocking wrapper around [PueueClient] for applications that don't use tokio.
+///
+/// It brings its own single-threaded runtime, which means that it mustn't be used from
+/// within an async context.
+pub struct BlockingPueueClient {
+    runtime: Runtime,
+    client: PueueClient,
+}
+
+impl BlockingPueueClient {
+    /// Connect to the daemon and authenticate via the shared secret.
+    pub fn new(shared: &Shared) -> Result<BlockingPueueClient, Error> {
+        let runtime = Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|err| Error::IoError("creating async runtime".to_string(), err))?;
+        let client = runtime.block_on(PueueClient::new(shared))?;
+
+        Ok(BlockingPueueClient { runtime, client })
+    }
+
+    /// See [PueueClient::daemon_version].
+    pub fn daemon_version(&self) -> &str {
+        self.client.daemon_version()
+    }
+
+    /// See [PueueClient::send].
+    pub fn send<T>(&mut self, message: T) -> Result<Message, Error>
+    where
+        T: Into<Message>,
+    {
+        self.runtime.block_on(self.client.send(message))
+    }
+
+    /// See [PueueClient::add].
+    pub fn add(&mut self, message: AddMessage) -> Result<TaskId, Error> {
+        self.runtime.block_on(self.client.add(message))
+    }
+
+    /// See [PueueClient::status].
+    pub fn status(&mut self) -> Result<State, Error> {
+        self.runtime.block_on(self.client.status())
+    }
+
+    /// See [PueueClient::log].
+    pub fn log(
+        &mut self,
+        message: LogRequestMessage,
+    ) -> Result<BTreeMap<TaskId, TaskLogMessage>, Error> {
+        self.runtime.block_on(self.client.log(message))
+    }
+
+    /// See [PueueClient::start].
+    pub fn start(&mut self, tasks: TaskSelection) -> Result<String, Error> {
+        self.runtime.block_on(self.client.start(tasks))
+    }
+
+    /// See [PueueClient::pause].
+    pub fn pause(&mut self, message: PauseMessage) -> Result<String, Error> {
+        self.runtime.block_on(self.client.pause(message))
+    }
+
+    /// See [PueueClient::kill].
+    pub fn kill(&mut self, message: KillMessage) -> Result<String, Error> {
+        self.runtime.block_on(self.client.kill(message))
+    }
+
+    /// See [PueueClient::remove].
+    pub fn remove(&mut self, task_ids: Vec<TaskId>) -> Result<String, Error> {
+        self.runtime.block_on(self.client.remove(task_ids))
+    }
+
+    /// See [PueueClient::groups].
+    pub fn groups(&mut self) -> Result<BTreeMap<String, Group>, Error> {
+        self.runtime.block_on(self.client.groups())
+    }
+
+    /// See [PueueClient::follow].
+    /// The log output is returned as an iterator over its chunks.
+    pub fn follow(self, message: StreamRequestMessage) -> Result<BlockingFollowStream, Error> {
+        let stream = self.runtime.block_on(self.client.follow(message))?;
+
+        Ok(BlockingFollowStream {
+            runtime: self.runtime,
+            stream,
+        })
+    }
+
+    /// See [PueueClient::subscribe].
+    /// The events are returned as an iterator.
+    pub fn subscribe(self) -> Result<BlockingEventStream, Error> {
+        let stream = self.runtime.block_on(self.client.subscribe())?;
+
+        Ok(BlockingEventStream {
+            runtime: self.runtime,
+            stream,
+        })
+    }
+}
+
+/// The blocking counterpart of [FollowStream].
+pub struct BlockingFollowStream {
+    runtime: Runtime,
+    stream: FollowStream,
+}
+
+impl Iterator for BlockingFollowStream {
+    type Item = Result<String, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.runtime.block_on(self.stream.next())
+    }
+}
+
+/// The blocking counterpart of [EventStream].
+pub struct BlockingEventStream {
+    runtime: Runtime,
+    stream: EventStream,
+}
+
+impl Iterator for BlockingEventStream {
+    type Item = Result<Event, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.runtime.block_on(self.stream.next())
+    }
+}
@@ -0,0 +1,221 @@
+//! A typed client to communicate with the Pueue daemon.
+//!
+//! [PueueClient] takes care of the connection and the handshake with the daemon and provides
+//! typed methods for the most common operations.
+//! Any other [Message] can be sent via [PueueClient::send].
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), pueue_lib::error::Error> {
+//! use pueue_lib::client::PueueClient;
+//! use pueue_lib::settings::Settings;
+//!
+//! let (settings, _) = Settings::read(&None)?;
+//! let mut client = PueueClient::new(&settings.shared).await?;
+//! let state = client.status().await?;
+//! println!("The daemon manages {} tasks", state.tasks.len());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! For applications that don't use tokio, there's a [BlockingPueueClient].
+use std::collections::BTreeMap;
+
+use crate::error::Error;
+use crate::event::Event;
+use crate::network::message::*;
+use crate::network::protocol::*;
+use crate::network::secret::read_client_secret;
+use crate::settings::Shared;
+use crate::state::{Group, State};
+
+mod blocking;
+
+pub use self::blocking::{BlockingEventStream, BlockingFollowStream, BlockingPueueClient};
+
+/// The id of a task.
+pub type TaskId = usize;
+
+/// An async client for the Pueue daemon.
+///
+/// Each client uses a single connection to the daemon.
+/// Long-living operations, such as [PueueClient::follow], take ownership of the client, as the
+/// connection can no longer be used for anything else.
+pub struct PueueClient {
+    stream: GenericStream,
+    daemon_version: String,
+}
+
+impl PueueClient {
+    /// Connect to the daemon and authenticate via the shared secret.
+    pub async fn new(shared: &Shared) -> Result<PueueClient, Error> {
+        let mut stream = get_client_stream(shared).await?;
+
+        // 1. Send the secret to the daemon.
+        // 2. If successful, the daemon responds with their version.
+        let secret = read_client_secret(shared)?;
+        send_bytes(&secret, &mut stream).await?;
+
+        let version_bytes = receive_bytes(&mut stream).await?;
+        if version_bytes.is_empty() {
+            return Err(Error::AuthenticationFailed);
+        }
+        let daemon_version = String::from_utf8(version_bytes)
+            .map_err(|_| Error::Connection("Daemon sent an invalid version".into()))?;
+
+        Ok(PueueClient {
+            stream,
+            daemon_version,
+        })
+    }
+
+    /// The version of the daemon we're connected to.
+    pub fn daemon_version(&self) -> &str {
+        &self.daemon_version
+    }
+
+    /// Send an arbitrary message to the daemon and return its response.
+    pub async fn send<T>(&mut self, message: T) -> Result<Message, Error>
+    where
+        T: Into<Message>,
+    {
+        send_message(message, &mut self.stream).await?;
+        receive_message(&mut self.stream).await
+    }
+
+    /// Send a message, for which the daemon responds with a success or failure message.
+    /// Returns the text of the success message.
+    async fn send_expect_success<T>(&mut self, message: T) -> Result<String, Error>
+    where
+        T: Into<Message>,
+    {
+        match self.send(message).await? {
+            Message::Success(text) => Ok(text),
+            other => Err(unexpected_response(other)),
+        }
+    }
+
+    /// Add a new task and return its id.
+    pub async fn add(&mut self, mut message: AddMessage) -> Result<TaskId, Error> {
+        // Let the daemon only respond with the id of the new task.
+        message.print_task_id = true;
+        let response = self.send_expect_success(message).await?;
+
+        response.trim().parse().map_err(|_| {
+            Error::UnexpectedResponse(format!("Expected the id of the new task, got: {response}"))
+        })
+    }
+
+    /// Get the current state of the daemon.
+    pub async fn status(&mut self) -> Result<State, Error> {
+        match self.send(Message::Status).await? {
+            Message::StatusResponse(state) => Ok(*state),
+            other => Err(unexpected_response(other)),
+        }
+    }
+
+    /// Get the log output of tasks.
+    /// The output is [snap](https://docs.rs/snap) compressed.
+    pub async fn log(
+        &mut self,
+        message: LogRequestMessage,
+    ) -> Result<BTreeMap<TaskId, TaskLogMessage>, Error> {
+        match self.send(message).await? {
+            Message::LogResponse(logs) => Ok(logs),
+            other => Err(unexpected_response(other)),
+        }
+    }
+
+    /// Start or resume tasks or groups.
+    pub async fn start(&mut self, tasks: TaskSelection) -> Result<String, Error> {
+        self.send_expect_success(StartMessage { tasks }).await
+    }
+
+    /// Pause tasks or groups.
+    pub async fn pause(&mut self, message: PauseMessage) -> Result<String, Error> {
+        self.send_expect_success(message).await
+    }
+
+    /// Kill tasks or groups, or send a signal to them.
+    pub async fn kill(&mut self, message: KillMessage) -> Result<String, Error> {
+        self.send_expect_success(message).await
+    }
+
+    /// Remove finished, queued or stashed tasks.
+    pub async fn remove(&mut self, task_ids: Vec<TaskId>) -> Result<String, Error> {
+        self.send_expect_success(Message::Remove(task_ids)).await
+    }
+
+    /// Get all groups.
+    pub async fn groups(&mut self) -> Result<BTreeMap<String, Group>, Error> {
+        match self.send(GroupMessage::List).await? {
+            Message::GroupResponse(response) => Ok(response.groups),
+            other => Err(unexpected_response(other)),
+        }
+    }
+
+    /// Follow the log output of a task.
+    /// This takes ownership of the client, as the connection is used exclusively for the stream.
+    pub async fn follow(mut self, message: StreamRequestMessage) -> Result<FollowStream, Error> {
+        send_message(message, &mut self.stream).await?;
+
+        Ok(FollowStream {
+            stream: self.stream,
+        })
+    }
+
+    /// Subscribe to all changes of the daemon's state.
+    /// This takes ownership of the client, as the connection is used exclusively for the stream.
+    pub async fn subscribe(mut self) -> Result<EventStream, Error> {
+        send_message(Message::Subscribe, &mut self.stream).await?;
+
+        Ok(EventStream {
+            stream: self.stream,
+        })
+    }
+}
+
+/// The continuous log output of a task, see [PueueClient::follow].
+pub struct FollowStream {
+    stream: GenericStream,
+}
+
+impl FollowStream {
+    /// Get the next chunk of the log output.
+    /// Returns `None`, once the task finished and all output has been sent.
+    pub async fn next(&mut self) -> Option<Result<String, Error>> {
+        match receive_message(&mut self.stream).await {
+            Ok(Message::Stream(text)) => Some(Ok(text)),
+            // The daemon closes the stream, once the task finished or its log went away.
+            Ok(Message::Close | Message::Success(_)) | Err(Error::EmptyPayload) => None,
+            Ok(other) => Some(Err(unexpected_response(other))),
+            Err(error) => Some(Err(error)),
+        }
+    }
+}
+
+/// The changes of the daemon's state, see [PueueClient::subscribe].
+pub struct EventStream {
+    stream: GenericStream,
+}
+
+impl EventStream {
+    /// Wait for the next change of the daemon's state.
+    /// Returns `None`, if the daemon closed the connection.
+    pub async fn next(&mut self) -> Option<Result<Event, Error>> {
+        match receive_message(&mut self.stream).await {
+            Ok(Message::Event(event)) => Some(Ok(event)),
+            Ok(Message::Close) | Err(Error::EmptyPayload) => None,
+            Ok(other) => Some(Err(unexpected_response(other))),
+            Err(error) => Some(Err(error)),
+        }
+    }
+}
+
+/// Convert a response we didn't expect into an error.
+/// Failure messages of the daemon are passed on as such.
+fn unexpected_response(message: Message) -> Error {
+    match message {
+        Message::Failure(text) => Error::DaemonFailure(text),
+        other => Error::UnexpectedResponse(format!("{other:?}")),
+    }
+}
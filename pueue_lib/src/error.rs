@@ -15,6 +15,22 @@ pub enum Error {
     #[error("Got an empty payload")]
     EmptyPayload,
 
+    /// The announced size of a payload exceeds the allowed maximum.
+    #[error("Payload of {} bytes exceeds the maximum of {} bytes", .0, .1)]
+    MessageTooBig(usize, usize),
+
+    /// The daemon closed the connection after receiving our secret.
+    #[error("The daemon rejected the connection. Did you use the correct secret?")]
+    AuthenticationFailed,
+
+    /// The daemon responded with a failure message.
+    #[error("{}", .0)]
+    DaemonFailure(String),
+
+    /// The daemon responded with a message we didn't expect.
+    #[error("Received unexpected response from daemon: {}", .0)]
+    UnexpectedResponse(String),
+
     #[error("Couldn't deserialize message:\n{}", .0)]
     MessageDeserialization(String),
 
@@ -27,6 +43,14 @@ pub enum Error {
     #[error("Some error occurred. {}", .0)]
     Generic(String),
 
+    /// A schedule couldn't be parsed or used to determine the next run.
+    #[error("Invalid schedule: {}", .0)]
+    InvalidSchedule(String),
+
+    /// A workflow couldn't be parsed or its steps can't be ordered.
+    #[error("Invalid workflow: {}", .0)]
+    InvalidWorkflow(String),
+
     #[error("I/O error while {}:\n{}", .0, .1)]
     IoError(String, std::io::Error),
 
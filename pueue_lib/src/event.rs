@@ -0,0 +1,33 @@
+use serde_derive::{Deserialize, Serialize};
+
+use crate::state::GroupStatus;
+use crate::task::{Task, TaskStatus};
+
+/// A change of the daemon's state, which is pushed to subscribed clients.
+#[derive(PartialEq, Eq, Clone, Debug, Deserialize, Serialize)]
+pub enum Event {
+    /// A new task has been added.
+    TaskAdded(Box<Task>),
+    /// The status of a task changed.
+    TaskStatusChanged {
+        task_id: usize,
+        previous: TaskStatus,
+        status: TaskStatus,
+    },
+    /// A task has been removed.
+    TaskRemoved(usize),
+    /// A new group has been created.
+    GroupAdded {
+        group: String,
+        parallel_tasks: usize,
+    },
+    /// A group has been paused or resumed.
+    GroupStatusChanged { group: String, status: GroupStatus },
+    /// The amount of parallel tasks of a group changed.
+    ParallelTasksChanged {
+        group: String,
+        parallel_tasks: usize,
+    },
+    /// A group has been removed.
+    GroupRemoved(String),
+}
@@ -13,14 +13,24 @@
 /// Shared module for internal logic!
 /// Contains helper for command aliasing.
 pub mod aliasing;
+/// The archive of finished tasks, which have been removed from the state.
+pub mod archive;
+/// A typed client to communicate with the daemon.
+pub mod client;
 /// Pueue lib's own Error implementation.
 pub mod error;
+/// Events about changes of the daemon's state, which are pushed to subscribed clients.
+pub mod event;
 /// Helper classes to read and write log files of Pueue's tasks.
 pub mod log;
 pub mod network;
 /// Shared module for internal logic!
 /// Contains helper to spawn shell commands and examine and interact with processes.
 pub mod process_helper;
+/// Abstract resources, which are requested by tasks and provided by groups and the daemon.
+pub mod resources;
+/// Recurring tasks, which are owned and instantiated by the daemon.
+pub mod schedule;
 /// This module contains all platform unspecific default values and helper functions for working
 /// with our setting representation.
 mod setting_defaults;
@@ -30,3 +40,5 @@ pub mod settings;
 pub mod state;
 /// Everything regarding Pueue's task
 pub mod task;
+/// Workflows of named steps, which depend on each other.
+pub mod workflow;
@@ -1,24 +1,128 @@
-use std::fs::{read_dir, remove_file, File};
+use std::collections::BTreeMap;
+use std::fs::{create_dir_all, read_dir, remove_file, rename, File, OpenOptions};
 use std::io::{self, prelude::*, Read, SeekFrom};
 use std::path::{Path, PathBuf};
 
+use chrono::{DateTime, Local, SecondsFormat};
 use log::error;
 use rev_buf_reader::RevBufReader;
+use serde_derive::{Deserialize, Serialize};
+use snap::read::FrameDecoder;
 use snap::write::FrameEncoder;
+use strum_macros::Display;
 
 use crate::error::Error;
+use crate::task::Task;
 
-/// Get the path to the log file of a task.
+/// The output streams of a task.
+/// Used to select a specific stream, if a task writes them to separate log files.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Display, Deserialize, Serialize)]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+/// The different log files a task can write to.
+///
+/// By default, stdout and stderr of a task are merged into a single log file.
+/// If the task has been configured to separate its output, two files are used instead.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum LogFile {
+    Combined,
+    Stdout,
+    Stderr,
+}
+
+impl LogFile {
+    /// The file extension of this log file.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            LogFile::Combined => "log",
+            LogFile::Stdout => "stdout",
+            LogFile::Stderr => "stderr",
+        }
+    }
+
+    /// Get the log files of a task, that contain the selected output streams.
+    /// If no stream is selected, all log files of the task are returned.
+    ///
+    /// Tasks with combined output only have a single log file, which is returned for any
+    /// selection.
+    pub fn for_task(task: &Task, stream: Option<LogStream>) -> Vec<LogFile> {
+        if !task.separate_output {
+            return vec![LogFile::Combined];
+        }
+
+        match stream {
+            None => vec![LogFile::Stdout, LogFile::Stderr],
+            Some(LogStream::Stdout) => vec![LogFile::Stdout],
+            Some(LogStream::Stderr) => vec![LogFile::Stderr],
+        }
+    }
+}
+
+/// Get the path to the combined log file of a task.
 pub fn get_log_path(task_id: usize, path: &Path) -> PathBuf {
+    get_log_file_path(task_id, path, LogFile::Combined)
+}
+
+/// Get the path to a specific log file of a task.
+pub fn get_log_file_path(task_id: usize, path: &Path, file: LogFile) -> PathBuf {
     let task_log_dir = path.join("task_logs");
-    task_log_dir.join(format!("{task_id}.log"))
+    task_log_dir.join(format!("{task_id}.{}", file.extension()))
+}
+
+/// Get the path to the file, in which the timestamps of a log file's lines are recorded.
+///
+/// Each line of this file has the form `<position> <timestamp>`, where `position` is the byte
+/// offset at which the respective line starts in the log file.
+pub fn get_timestamp_file_path(task_id: usize, path: &Path, file: LogFile) -> PathBuf {
+    let task_log_dir = path.join("task_logs");
+    task_log_dir.join(format!("{task_id}.{}.timestamps", file.extension()))
+}
+
+/// Get the path to a rotated segment of a log file.
+/// Segments are numbered from the newest (`1`) to the oldest and are compressed using [snap].
+pub fn get_log_segment_path(task_id: usize, path: &Path, file: LogFile, index: usize) -> PathBuf {
+    let task_log_dir = path.join("task_logs");
+    task_log_dir.join(format!("{task_id}.{}.{index}.sz", file.extension()))
+}
+
+/// Get the path to the compressed log file of a finished task.
+/// Log files are compressed using [snap], if the daemon is configured to do so.
+pub fn get_compressed_log_file_path(task_id: usize, path: &Path, file: LogFile) -> PathBuf {
+    let task_log_dir = path.join("task_logs");
+    task_log_dir.join(format!("{task_id}.{}.sz", file.extension()))
+}
+
+/// Get the paths to all rotated segments of a log file, ordered from the oldest to the newest.
+pub fn get_log_segment_paths(task_id: usize, path: &Path, file: LogFile) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    for index in 1.. {
+        let segment_path = get_log_segment_path(task_id, path, file, index);
+        if !segment_path.exists() {
+            break;
+        }
+        paths.push(segment_path);
+    }
+
+    paths.reverse();
+    paths
+}
+
+/// Create an empty log file, which is opened in append mode.
+/// Appending ensures that writes continue at the start of the file, after it has been
+/// truncated due to a rotation.
+fn create_log_file(path: &Path) -> io::Result<File> {
+    File::create(path)?;
+    OpenOptions::new().append(true).open(path)
 }
 
 /// Create and return the two file handles for the `(stdout, stderr)` log file of a task.
 /// These are two handles to the same file.
 pub fn create_log_file_handles(task_id: usize, path: &Path) -> Result<(File, File), Error> {
     let log_path = get_log_path(task_id, path);
-    let stdout_handle = File::create(&log_path)
+    let stdout_handle = create_log_file(&log_path)
         .map_err(|err| Error::IoPathError(log_path, "getting stdout handle", err))?;
     let stderr_handle = stdout_handle
         .try_clone()
@@ -27,25 +131,415 @@ pub fn create_log_file_handles(task_id: usize, path: &Path) -> Result<(File, Fil
     Ok((stdout_handle, stderr_handle))
 }
 
-/// Return the file handle for the log file of a task.
-pub fn get_log_file_handle(task_id: usize, path: &Path) -> Result<File, Error> {
-    let path = get_log_path(task_id, path);
-    let handle = File::open(&path)
-        .map_err(|err| Error::IoPathError(path, "getting log file handle", err))?;
+/// Create and return the file handles for the separate `(stdout, stderr)` log files of a task.
+pub fn create_separate_log_file_handles(
+    task_id: usize,
+    path: &Path,
+) -> Result<(File, File), Error> {
+    let stdout_path = get_log_file_path(task_id, path, LogFile::Stdout);
+    let stdout_handle = create_log_file(&stdout_path)
+        .map_err(|err| Error::IoPathError(stdout_path, "getting stdout handle", err))?;
+    let stderr_path = get_log_file_path(task_id, path, LogFile::Stderr);
+    let stderr_handle = create_log_file(&stderr_path)
+        .map_err(|err| Error::IoPathError(stderr_path, "getting stderr handle", err))?;
+
+    Ok((stdout_handle, stderr_handle))
+}
+
+/// A handle to a log file of a task, which may have been compressed.
+pub enum LogHandle {
+    /// The uncompressed log file.
+    Plain(File),
+    /// The compressed log file of a finished task.
+    Compressed(FrameDecoder<File>),
+}
+
+impl Read for LogHandle {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            LogHandle::Plain(handle) => handle.read(buf),
+            LogHandle::Compressed(decoder) => decoder.read(buf),
+        }
+    }
+}
+
+/// Return the handle for a log file of a task.
+/// If the log file has been compressed, the returned handle decompresses it while reading.
+pub fn get_log_file_handle(task_id: usize, path: &Path, file: LogFile) -> Result<LogHandle, Error> {
+    // The uncompressed log file is only removed, once the compressed one has been written.
+    // Hence, it has to be checked first.
+    let log_path = get_log_file_path(task_id, path, file);
+    let error = match File::open(&log_path) {
+        Ok(handle) => return Ok(LogHandle::Plain(handle)),
+        Err(error) => error,
+    };
 
-    Ok(handle)
+    let compressed_path = get_compressed_log_file_path(task_id, path, file);
+    match File::open(compressed_path) {
+        Ok(handle) => Ok(LogHandle::Compressed(FrameDecoder::new(handle))),
+        Err(_) => Err(Error::IoPathError(
+            log_path,
+            "getting log file handle",
+            error,
+        )),
+    }
+}
+
+/// Compress a log file of a finished task using [snap].
+/// The uncompressed log file is removed afterwards. Missing log files are ignored.
+pub fn compress_log_file(task_id: usize, path: &Path, file: LogFile) -> Result<(), Error> {
+    let log_path = get_log_file_path(task_id, path, file);
+    if !log_path.exists() {
+        return Ok(());
+    }
+
+    let mut handle = File::open(&log_path)
+        .map_err(|err| Error::IoPathError(log_path.clone(), "opening log file", err))?;
+    let compressed_path = get_compressed_log_file_path(task_id, path, file);
+    let compressed = File::create(&compressed_path)
+        .map_err(|err| Error::IoPathError(compressed_path, "creating compressed log file", err))?;
+    let mut compressor = FrameEncoder::new(compressed);
+    io::copy(&mut handle, &mut compressor)
+        .map_err(|err| Error::IoError("compressing log file".to_string(), err))?;
+    compressor
+        .flush()
+        .map_err(|err| Error::IoError("compressing log file".to_string(), err))?;
+
+    remove_file(&log_path)
+        .map_err(|err| Error::IoPathError(log_path, "removing uncompressed log file", err))?;
+
+    Ok(())
 }
 
-/// Remove the the log files of a task.
+/// Get the paths to all existing files of a task's logs, including their recorded timestamps,
+/// rotated segments and compressed versions.
+fn get_existing_log_paths(task_id: usize, path: &Path) -> Vec<(LogFile, PathBuf)> {
+    let mut existing = Vec::new();
+    for file in [LogFile::Combined, LogFile::Stdout, LogFile::Stderr] {
+        let mut paths = get_log_segment_paths(task_id, path, file);
+        paths.push(get_log_file_path(task_id, path, file));
+        paths.push(get_compressed_log_file_path(task_id, path, file));
+        paths.push(get_timestamp_file_path(task_id, path, file));
+        for path in paths {
+            if path.exists() {
+                existing.push((file, path));
+            }
+        }
+    }
+
+    existing
+}
+
+/// Remove the the log files of a task, including their recorded timestamps, rotated
+/// segments and compressed versions.
 pub fn clean_log_handles(task_id: usize, path: &Path) {
-    let path = get_log_path(task_id, path);
-    if path.exists() {
+    for (file, path) in get_existing_log_paths(task_id, path) {
         if let Err(err) = remove_file(path) {
-            error!("Failed to remove stdout file for task {task_id} with error {err:?}");
+            error!("Failed to remove {file:?} log file for task {task_id} with error {err:?}");
         };
     }
 }
 
+/// Move all log files of a task from the pueue directory at `path` to the one at `target`.
+/// Afterwards, the logs can be read as usual by passing `target` instead of `path`.
+///
+/// The files are only renamed, which is why both directories should be on the same filesystem.
+pub fn move_log_files(task_id: usize, path: &Path, target: &Path) -> Result<(), Error> {
+    let target_log_dir = target.join("task_logs");
+    create_dir_all(&target_log_dir)
+        .map_err(|err| Error::IoPathError(target_log_dir.clone(), "creating log directory", err))?;
+
+    for (_, log_path) in get_existing_log_paths(task_id, path) {
+        // All log files are direct children of the task log directory.
+        let Some(name) = log_path.file_name() else {
+            continue;
+        };
+        rename(&log_path, target_log_dir.join(name))
+            .map_err(|err| Error::IoPathError(log_path, "moving log file", err))?;
+    }
+
+    Ok(())
+}
+
+/// Limits for the size of a task's log files.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct LogRotation {
+    /// Log files are rotated, once they grow beyond this size in bytes.
+    pub max_size: u64,
+    /// The amount of rotated segments that're kept.
+    /// If this is `0`, log files are simply truncated.
+    pub count: usize,
+}
+
+/// Rotate a log file of a task.
+///
+/// The current content is compressed into a new segment, while the oldest segments are removed
+/// to keep at most `rotation_count` of them. Afterwards, the log file is truncated.
+/// Any output that's written by the task, while its log file is being rotated, may get lost.
+pub fn rotate_log_file(
+    task_id: usize,
+    path: &Path,
+    file: LogFile,
+    rotation_count: usize,
+) -> Result<(), Error> {
+    // Shift all segments by one and drop the ones that exceed the limit.
+    let segments = get_log_segment_paths(task_id, path, file);
+    for index in (1..=segments.len()).rev() {
+        let segment_path = get_log_segment_path(task_id, path, file, index);
+        if index >= rotation_count {
+            remove_file(&segment_path)
+                .map_err(|err| Error::IoPathError(segment_path, "removing log segment", err))?;
+            continue;
+        }
+
+        let new_path = get_log_segment_path(task_id, path, file, index + 1);
+        rename(&segment_path, new_path)
+            .map_err(|err| Error::IoPathError(segment_path, "moving log segment", err))?;
+    }
+
+    let log_path = get_log_file_path(task_id, path, file);
+    if rotation_count > 0 {
+        let mut handle = get_log_file_handle(task_id, path, file)?;
+        let segment_path = get_log_segment_path(task_id, path, file, 1);
+        let segment = File::create(&segment_path)
+            .map_err(|err| Error::IoPathError(segment_path, "creating log segment", err))?;
+        let mut compressor = FrameEncoder::new(segment);
+        io::copy(&mut handle, &mut compressor)
+            .map_err(|err| Error::IoError("compressing log segment".to_string(), err))?;
+        compressor
+            .flush()
+            .map_err(|err| Error::IoError("compressing log segment".to_string(), err))?;
+    }
+
+    OpenOptions::new()
+        .write(true)
+        .open(&log_path)
+        .and_then(|handle| handle.set_len(0))
+        .map_err(|err| Error::IoPathError(log_path, "truncating log file", err))?;
+
+    Ok(())
+}
+
+/// Read and decompress a rotated segment of a log file.
+fn read_log_segment(path: &Path) -> Result<Vec<u8>, Error> {
+    let handle = File::open(path)
+        .map_err(|err| Error::IoPathError(path.to_path_buf(), "opening log segment", err))?;
+    let mut content = Vec::new();
+    FrameDecoder::new(handle)
+        .read_to_end(&mut content)
+        .map_err(|err| Error::IoPathError(path.to_path_buf(), "reading log segment", err))?;
+
+    Ok(content)
+}
+
+/// Get the index at which the last `amount` lines of some output start.
+/// Returns `None`, if there are fewer lines than that.
+fn start_of_last_lines(content: &[u8], amount: usize) -> Option<usize> {
+    content
+        .iter()
+        .enumerate()
+        .rev()
+        .filter(|(_, byte)| **byte == b'\n')
+        .nth(amount)
+        .map(|(index, _)| index + 1)
+}
+
+/// Move the cursor of a log file to the start of the last `lines` lines.
+/// If the log file has fewer lines than that, the missing lines are read from its rotated
+/// segments instead.
+///
+/// Return type is `(Vec<u8>, bool)`
+/// - `Vec<u8>` The output from rotated segments, which precedes the content of the log file.
+/// - `bool` Whether the full output of the task has been read.
+fn seek_to_last_lines_with_segments(
+    task_id: usize,
+    path: &Path,
+    file: LogFile,
+    handle: &mut File,
+    lines: usize,
+) -> Result<(Vec<u8>, bool), Error> {
+    if !seek_to_last_lines(handle, lines)? {
+        return Ok((Vec::new(), false));
+    }
+
+    if !get_log_segment_path(task_id, path, file, 1).exists() {
+        return Ok((Vec::new(), true));
+    }
+
+    // The log file is shorter than requested.
+    // Count its lines, to determine how many lines are missing.
+    let mut content = Vec::new();
+    handle
+        .read_to_end(&mut content)
+        .map_err(|err| Error::IoError("reading log output".to_string(), err))?;
+    handle
+        .rewind()
+        .map_err(|err| Error::IoError("seeking to start of file".to_string(), err))?;
+
+    read_missing_segment_lines(task_id, path, file, lines, &content)
+}
+
+/// Read the lines from the rotated segments of a log file, that are missing from `content`
+/// to get `lines` lines in total.
+///
+/// Return type is `(Vec<u8>, bool)`, see [seek_to_last_lines_with_segments].
+fn read_missing_segment_lines(
+    task_id: usize,
+    path: &Path,
+    file: LogFile,
+    lines: usize,
+    content: &[u8],
+) -> Result<(Vec<u8>, bool), Error> {
+    let mut segments = get_log_segment_paths(task_id, path, file);
+    if segments.is_empty() {
+        return Ok((Vec::new(), true));
+    }
+
+    let lines_in_file = content.iter().filter(|byte| **byte == b'\n').count();
+    let missing = lines.saturating_sub(lines_in_file);
+    if missing == 0 {
+        return Ok((Vec::new(), false));
+    }
+
+    // Prepend segments, starting with the newest, until enough lines are found.
+    let mut rotated = Vec::new();
+    while let Some(segment) = segments.pop() {
+        let mut segment = read_log_segment(&segment)?;
+        segment.extend(rotated);
+        rotated = segment;
+
+        if let Some(start) = start_of_last_lines(&rotated, missing) {
+            return Ok((rotated.split_off(start), false));
+        }
+    }
+
+    Ok((rotated, true))
+}
+
+/// Read and decompress all rotated segments of a log file, from the oldest to the newest.
+fn read_log_segments(task_id: usize, path: &Path, file: LogFile) -> Result<Vec<u8>, Error> {
+    let mut rotated = Vec::new();
+    for segment_path in get_log_segment_paths(task_id, path, file) {
+        rotated.extend(read_log_segment(&segment_path)?);
+    }
+
+    Ok(rotated)
+}
+
+/// Read the output of a reader, which doesn't support seeking, e.g. a compressed log file.
+/// If `lines` is given, only the last few lines are kept in memory.
+///
+/// Return type is `(Vec<u8>, u64)`
+/// - `Vec<u8>` The (last lines of the) output.
+/// - `u64` The position at which the returned output starts. `0` means that all output has been
+///   returned.
+pub fn read_last_lines_of(
+    mut reader: impl Read,
+    lines: Option<usize>,
+) -> Result<(Vec<u8>, u64), Error> {
+    let mut content = Vec::new();
+    let mut position = 0;
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read_bytes = reader
+            .read(&mut buffer)
+            .map_err(|err| Error::IoError("reading log output".to_string(), err))?;
+        if read_bytes == 0 {
+            break;
+        }
+        content.extend_from_slice(&buffer[..read_bytes]);
+
+        // Drop everything before the requested lines, so the full output never has to fit
+        // into memory.
+        if let Some(start) = lines.and_then(|lines| start_of_last_lines(&content, lines)) {
+            content.drain(..start);
+            position += start as u64;
+        }
+    }
+
+    Ok((content, position))
+}
+
+/// The output of a compressed log file, which has been read into memory.
+struct DecompressedLog {
+    /// The output from rotated segments, which precedes the content of the log file.
+    rotated: Vec<u8>,
+    /// The content of the log file.
+    content: Vec<u8>,
+    /// The position at which `content` starts in the uncompressed log file.
+    position: u64,
+    /// Whether the full output of the task has been read.
+    complete: bool,
+}
+
+/// Read a compressed log file of a task, including its rotated segments.
+/// If `lines` is given, only the last few lines are kept in memory.
+fn read_compressed_log_file(
+    task_id: usize,
+    path: &Path,
+    file: LogFile,
+    decoder: FrameDecoder<File>,
+    lines: Option<usize>,
+) -> Result<DecompressedLog, Error> {
+    let (content, position) = read_last_lines_of(decoder, lines)?;
+
+    let (rotated, complete) = match lines {
+        None => (read_log_segments(task_id, path, file)?, true),
+        Some(_) if position > 0 => (Vec::new(), false),
+        Some(lines) => read_missing_segment_lines(task_id, path, file, lines, &content)?,
+    };
+
+    Ok(DecompressedLog {
+        rotated,
+        content,
+        position,
+        complete,
+    })
+}
+
+/// Open a log file of a task, including its rotated segments, for reading.
+/// If `lines` is given, only the last few lines are read.
+///
+/// Return type is `(Box<dyn Read>, bool)`
+/// - `Box<dyn Read>` A reader for the task's output.
+/// - `bool` Whether the full task's output is read.
+pub fn open_log_file(
+    task_id: usize,
+    path: &Path,
+    file: LogFile,
+    lines: Option<usize>,
+) -> Result<(Box<dyn Read>, bool), Error> {
+    let handle = get_log_file_handle(task_id, path, file)?;
+
+    let Some(lines) = lines else {
+        // Read all segments from the oldest to the newest, followed by the current log file.
+        let mut reader: Box<dyn Read> = Box::new(io::empty());
+        for segment_path in get_log_segment_paths(task_id, path, file) {
+            let segment = File::open(&segment_path)
+                .map_err(|err| Error::IoPathError(segment_path, "opening log segment", err))?;
+            reader = Box::new(reader.chain(FrameDecoder::new(segment)));
+        }
+        return Ok((Box::new(reader.chain(handle)), true));
+    };
+
+    let mut handle = match handle {
+        LogHandle::Plain(handle) => handle,
+        LogHandle::Compressed(decoder) => {
+            let log = read_compressed_log_file(task_id, path, file, decoder, Some(lines))?;
+            let reader = io::Cursor::new(log.rotated).chain(io::Cursor::new(log.content));
+            return Ok((Box::new(reader), log.complete));
+        }
+    };
+
+    let (rotated, output_complete) =
+        seek_to_last_lines_with_segments(task_id, path, file, &mut handle, lines)?;
+
+    Ok((
+        Box::new(io::Cursor::new(rotated).chain(handle)),
+        output_complete,
+    ))
+}
+
 /// Return the output of a task. \
 /// Task output is compressed using [snap] to save some memory and bandwidth.
 /// Return type is `(Vec<u8>, bool)`
@@ -55,42 +549,100 @@ pub fn clean_log_handles(task_id: usize, path: &Path) {
 pub fn read_and_compress_log_file(
     task_id: usize,
     path: &Path,
+    file: LogFile,
     lines: Option<usize>,
 ) -> Result<(Vec<u8>, bool), Error> {
-    let mut file = get_log_file_handle(task_id, path)?;
+    // Move the cursor to the last few lines of the output.
+    let (mut reader, output_complete) = open_log_file(task_id, path, file, lines)?;
 
     let mut content = Vec::new();
 
-    // Indicates whether the full log output is shown or just the last part of it.
-    let mut output_complete = true;
-
-    // Move the cursor to the last few lines of both files.
-    if let Some(lines) = lines {
-        output_complete = seek_to_last_lines(&mut file, lines)?;
-    }
-
     // Compress the full log input and pipe it into the snappy compressor
     {
         let mut compressor = FrameEncoder::new(&mut content);
-        io::copy(&mut file, &mut compressor)
+        io::copy(&mut reader, &mut compressor)
             .map_err(|err| Error::IoError("compressing log output".to_string(), err))?;
     }
 
     Ok((content, output_complete))
 }
 
+/// Return the output of a task together with the timestamps of its lines.
+/// The output is uncompressed and the timestamps belong to the lines at the same position.
+///
+/// Return type is `(Vec<u8>, bool, LineTimestamps)`, see [read_and_compress_log_file].
+pub fn read_log_file_with_timestamps(
+    task_id: usize,
+    path: &Path,
+    file: LogFile,
+    lines: Option<usize>,
+) -> Result<(Vec<u8>, bool, LineTimestamps), Error> {
+    // Rotated segments don't have any timestamps.
+    let (mut content, output_complete, position, file_content) =
+        match get_log_file_handle(task_id, path, file)? {
+            LogHandle::Plain(mut handle) => {
+                let (content, output_complete) = match lines {
+                    Some(lines) => {
+                        seek_to_last_lines_with_segments(task_id, path, file, &mut handle, lines)?
+                    }
+                    None => (read_log_segments(task_id, path, file)?, true),
+                };
+
+                let position = handle.stream_position().map_err(|err| {
+                    Error::IoError("getting position in log file".to_string(), err)
+                })?;
+                let mut file_content = Vec::new();
+                handle
+                    .read_to_end(&mut file_content)
+                    .map_err(|err| Error::IoError("reading log output".to_string(), err))?;
+
+                (content, output_complete, position, file_content)
+            }
+            LogHandle::Compressed(decoder) => {
+                let log = read_compressed_log_file(task_id, path, file, decoder, lines)?;
+                (log.rotated, log.complete, log.position, log.content)
+            }
+        };
+    let mut line_timestamps = vec![None; content.split_inclusive(|byte| *byte == b'\n').count()];
+
+    let timestamps = read_timestamps(task_id, path, file)?;
+    line_timestamps.extend(get_line_timestamps(&file_content, position, &timestamps));
+    content.extend(file_content);
+
+    Ok((content, output_complete, line_timestamps))
+}
+
+/// Compress some log output using [snap].
+pub fn compress_log_output(output: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut content = Vec::new();
+    {
+        let mut compressor = FrameEncoder::new(&mut content);
+        compressor
+            .write_all(output)
+            .map_err(|err| Error::IoError("compressing log output".to_string(), err))?;
+    }
+
+    Ok(content)
+}
+
 /// Return the last lines of of a task's output. \
 /// This output is uncompressed and may take a lot of memory, which is why we only read
 /// the last few lines.
 pub fn read_last_log_file_lines(
     task_id: usize,
     path: &Path,
+    file: LogFile,
     lines: usize,
 ) -> Result<String, Error> {
-    let mut file = get_log_file_handle(task_id, path)?;
+    let (mut reader, _) = open_log_file(task_id, path, file, Some(lines))?;
 
-    // Get the last few lines of both files
-    Ok(read_last_lines(&mut file, lines))
+    let mut content = Vec::new();
+    reader
+        .read_to_end(&mut content)
+        .map_err(|err| Error::IoError("reading log output".to_string(), err))?;
+    let output = String::from_utf8_lossy(&content);
+
+    Ok(output.strip_suffix('\n').unwrap_or(&output).to_string())
 }
 
 /// Remove all files in the log directory.
@@ -202,3 +754,424 @@ pub fn seek_to_last_lines(file: &mut File, amount: usize) -> Result<bool, Error>
 
     Ok(false)
 }
+
+/// The format in which timestamps are shown in front of log lines.
+pub const LOG_TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.3f";
+
+/// The timestamps of the lines of some log output.
+/// Each entry belongs to the line at the same position.
+/// Lines that have been written without recording timestamps don't have one.
+pub type LineTimestamps = Vec<Option<DateTime<Local>>>;
+
+/// Writes the output of a task to a log file and records the time at which each line has been
+/// written.
+pub struct TimestampedLogWriter {
+    task_id: usize,
+    path: PathBuf,
+    file: LogFile,
+    log: File,
+    timestamps: File,
+    /// The amount of bytes that have been written to the log file.
+    position: u64,
+    /// If set, the log file is rotated once it grows too large.
+    rotation: Option<LogRotation>,
+}
+
+impl TimestampedLogWriter {
+    /// Create the log file and the timestamp file of a task.
+    pub fn create(
+        task_id: usize,
+        path: &Path,
+        file: LogFile,
+        rotation: Option<LogRotation>,
+    ) -> Result<Self, Error> {
+        let log_path = get_log_file_path(task_id, path, file);
+        let log = create_log_file(&log_path)
+            .map_err(|err| Error::IoPathError(log_path, "creating log file", err))?;
+        let timestamp_path = get_timestamp_file_path(task_id, path, file);
+        let timestamps = create_log_file(&timestamp_path)
+            .map_err(|err| Error::IoPathError(timestamp_path, "creating timestamp file", err))?;
+
+        Ok(TimestampedLogWriter {
+            task_id,
+            path: path.to_path_buf(),
+            file,
+            log,
+            timestamps,
+            position: 0,
+            rotation,
+        })
+    }
+
+    /// Write a line of output, including its trailing newline, to the log file.
+    /// Overly long lines may also be written in several parts.
+    ///
+    /// The timestamp is written first, which guarantees that readers always find the
+    /// timestamp of any line they're able to see.
+    pub fn write_line(&mut self, line: &[u8]) -> io::Result<()> {
+        let timestamp = Local::now().to_rfc3339_opts(SecondsFormat::Millis, false);
+        writeln!(self.timestamps, "{} {timestamp}", self.position)?;
+        self.log.write_all(line)?;
+        self.position += line.len() as u64;
+
+        // Rotate the log file, once it became too large.
+        // As lines are written as a whole, the rotation only splits overly long lines.
+        if let Some(rotation) = self.rotation {
+            if self.position >= rotation.max_size {
+                rotate_log_file(self.task_id, &self.path, self.file, rotation.count)
+                    .map_err(|err| io::Error::new(io::ErrorKind::Other, err.to_string()))?;
+                // The timestamps of rotated lines are dropped.
+                self.timestamps.set_len(0)?;
+                self.position = 0;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse the content of a timestamp file into a map of line positions and their timestamps.
+fn parse_timestamps(content: &[u8], timestamps: &mut BTreeMap<u64, DateTime<Local>>) {
+    for line in String::from_utf8_lossy(content).lines() {
+        let Some((position, timestamp)) = line.split_once(' ') else {
+            continue;
+        };
+        let (Ok(position), Ok(timestamp)) = (
+            position.parse::<u64>(),
+            DateTime::parse_from_rfc3339(timestamp),
+        ) else {
+            continue;
+        };
+
+        timestamps.insert(position, timestamp.with_timezone(&Local));
+    }
+}
+
+/// Read the recorded timestamps of a log file.
+/// The timestamps are indexed by the position of the line they belong to.
+///
+/// Returns an empty map, if no timestamps have been recorded for this log file.
+pub fn read_timestamps(
+    task_id: usize,
+    path: &Path,
+    file: LogFile,
+) -> Result<BTreeMap<u64, DateTime<Local>>, Error> {
+    let path = get_timestamp_file_path(task_id, path, file);
+    let mut timestamps = BTreeMap::new();
+    if !path.exists() {
+        return Ok(timestamps);
+    }
+
+    let content = std::fs::read(&path)
+        .map_err(|err| Error::IoPathError(path, "reading timestamp file", err))?;
+    parse_timestamps(&content, &mut timestamps);
+
+    Ok(timestamps)
+}
+
+/// Look up the timestamps for each line of some log output, which starts at `position` in
+/// its log file.
+pub fn get_line_timestamps(
+    output: &[u8],
+    position: u64,
+    timestamps: &BTreeMap<u64, DateTime<Local>>,
+) -> LineTimestamps {
+    let mut line_start = position;
+    output
+        .split_inclusive(|byte| *byte == b'\n')
+        .map(|line| {
+            let timestamp = timestamps.get(&line_start).copied();
+            line_start += line.len() as u64;
+            timestamp
+        })
+        .collect()
+}
+
+/// Prefix each line of some log output with its timestamp.
+///
+/// The output is returned unchanged, if none of its lines have a timestamp.
+pub fn add_timestamps(output: &str, timestamps: &[Option<DateTime<Local>>]) -> String {
+    if timestamps.iter().all(Option::is_none) {
+        return output.to_string();
+    }
+
+    let mut timestamped = String::with_capacity(output.len());
+    for (index, line) in output.split_inclusive('\n').enumerate() {
+        match timestamps.get(index).copied().flatten() {
+            Some(timestamp) => {
+                timestamped.push_str(&format!("[{}] ", timestamp.format(LOG_TIMESTAMP_FORMAT)));
+            }
+            // Keep the lines aligned, even if some of them don't have a timestamp.
+            None => timestamped.push_str(&" ".repeat(26)),
+        }
+        timestamped.push_str(line);
+    }
+
+    timestamped
+}
+
+/// Continuously reads new output from a log file, e.g. to follow a running task.
+///
+/// Rotations of the log file are detected, as long as the follower reads more often than the
+/// log file is rotated.
+pub struct LogFollower {
+    task_id: usize,
+    directory: PathBuf,
+    file: LogFile,
+    path: PathBuf,
+    /// This isn't set, if the log file has already been compressed.
+    handle: Option<File>,
+    /// The position up to which the log file has been read.
+    position: u64,
+    /// Output from rotated segments, which hasn't been returned yet.
+    rotated: Vec<u8>,
+    /// Output from a compressed log file, which hasn't been returned yet.
+    decompressed: Vec<u8>,
+    /// Only set, if each line should be prefixed with its timestamp.
+    timestamps: Option<FollowedTimestamps>,
+}
+
+/// The state that's needed to prefix followed log lines with their timestamps.
+struct FollowedTimestamps {
+    path: PathBuf,
+    handle: Option<File>,
+    /// Timestamps of lines that haven't been read yet.
+    timestamps: BTreeMap<u64, DateTime<Local>>,
+    /// Incomplete lines, that have been read from the log and the timestamp file.
+    pending_output: Vec<u8>,
+    pending_timestamps: Vec<u8>,
+    /// The position at which `pending_output` starts in the log file.
+    position: u64,
+}
+
+impl LogFollower {
+    /// Open a log file of a task for following.
+    /// If `lines` is given, only the last few lines of existing output are read.
+    pub fn new(
+        task_id: usize,
+        path: &Path,
+        file: LogFile,
+        lines: Option<usize>,
+        timestamps: bool,
+    ) -> Result<Self, Error> {
+        let (handle, rotated, decompressed, position) =
+            match get_log_file_handle(task_id, path, file)? {
+                LogHandle::Plain(mut handle) => {
+                    // If `lines` is passed as an option, we only want to show the last `X` lines.
+                    // To achieve this, we seek the file handle to the start of the `Xth` line
+                    // from the end of the file. Missing lines are taken from rotated segments.
+                    let rotated = match lines {
+                        Some(lines) => {
+                            seek_to_last_lines_with_segments(
+                                task_id,
+                                path,
+                                file,
+                                &mut handle,
+                                lines,
+                            )?
+                            .0
+                        }
+                        None => read_log_segments(task_id, path, file)?,
+                    };
+
+                    let position = handle.stream_position().map_err(|err| {
+                        Error::IoError("getting position in log file".to_string(), err)
+                    })?;
+                    (Some(handle), rotated, Vec::new(), position)
+                }
+                // The task already finished, so there's nothing left to follow.
+                LogHandle::Compressed(decoder) => {
+                    let log = read_compressed_log_file(task_id, path, file, decoder, lines)?;
+                    (None, log.rotated, log.content, log.position)
+                }
+            };
+        let timestamps = if timestamps {
+            Some(FollowedTimestamps {
+                path: get_timestamp_file_path(task_id, path, file),
+                handle: None,
+                timestamps: BTreeMap::new(),
+                pending_output: Vec::new(),
+                pending_timestamps: Vec::new(),
+                position,
+            })
+        } else {
+            None
+        };
+
+        Ok(LogFollower {
+            task_id,
+            directory: path.to_path_buf(),
+            file,
+            path: get_log_file_path(task_id, path, file),
+            handle,
+            position,
+            rotated,
+            decompressed,
+            timestamps,
+        })
+    }
+
+    /// Check whether the log file still exists, either uncompressed or compressed.
+    /// It goes away, if the task is removed.
+    pub fn exists(&self) -> bool {
+        self.path.exists()
+            || get_compressed_log_file_path(self.task_id, &self.directory, self.file).exists()
+    }
+
+    /// Read all output that has been written since the last call.
+    ///
+    /// If timestamps are shown, only complete lines are returned, as a line's timestamp is
+    /// printed in front of it. Call [LogFollower::finish] to get the last incomplete line, once
+    /// the task finished.
+    pub fn read(&mut self) -> Result<Vec<u8>, Error> {
+        self.check_rotation()?;
+        let mut rotated = std::mem::take(&mut self.rotated);
+
+        let mut output = std::mem::take(&mut self.decompressed);
+        if let Some(handle) = self.handle.as_mut() {
+            handle
+                .read_to_end(&mut output)
+                .map_err(|err| Error::IoError("reading log file".to_string(), err))?;
+        }
+        self.position += output.len() as u64;
+
+        let Some(timestamps) = self.timestamps.as_mut() else {
+            rotated.extend(output);
+            return Ok(rotated);
+        };
+        timestamps.pending_output.extend_from_slice(&output);
+
+        // Only take complete lines, the rest has to wait for the next read.
+        let complete = match timestamps
+            .pending_output
+            .iter()
+            .rposition(|byte| *byte == b'\n')
+        {
+            Some(index) => index + 1,
+            None => return Ok(Vec::new()),
+        };
+        let lines: Vec<u8> = timestamps.pending_output.drain(..complete).collect();
+
+        // Rotated output doesn't have any timestamps.
+        rotated.extend(timestamps.format(lines)?);
+        Ok(rotated)
+    }
+
+    /// Check whether the log file has been rotated since the last read.
+    /// If it has, the output that hasn't been read yet is taken from the newest segment.
+    fn check_rotation(&mut self) -> Result<(), Error> {
+        let Some(handle) = self.handle.as_mut() else {
+            return Ok(());
+        };
+        let length = handle
+            .metadata()
+            .map_err(|err| Error::IoError("reading log file metadata".to_string(), err))?
+            .len();
+        if length >= self.position {
+            return Ok(());
+        }
+
+        // Incomplete lines from before the rotation are returned without their timestamps.
+        if let Some(timestamps) = self.timestamps.as_mut() {
+            self.rotated
+                .extend(std::mem::take(&mut timestamps.pending_output));
+            timestamps.reset()?;
+        }
+
+        let segment_path = get_log_segment_path(self.task_id, &self.directory, self.file, 1);
+        if segment_path.exists() {
+            let segment = read_log_segment(&segment_path)?;
+            let position = usize::try_from(self.position).unwrap_or(usize::MAX);
+            if let Some(unread) = segment.get(position..) {
+                self.rotated.extend_from_slice(unread);
+            }
+        }
+
+        handle
+            .rewind()
+            .map_err(|err| Error::IoError("seeking to start of file".to_string(), err))?;
+        self.position = 0;
+
+        Ok(())
+    }
+
+    /// Return any remaining incomplete line.
+    /// This should be called, once no more output will be written.
+    pub fn finish(&mut self) -> Result<Vec<u8>, Error> {
+        let mut output = self.read()?;
+        if let Some(timestamps) = self.timestamps.as_mut() {
+            let rest = std::mem::take(&mut timestamps.pending_output);
+            if !rest.is_empty() {
+                output.extend(timestamps.format(rest)?);
+            }
+        }
+
+        Ok(output)
+    }
+}
+
+impl FollowedTimestamps {
+    /// Start over, after the log file has been rotated.
+    fn reset(&mut self) -> Result<(), Error> {
+        self.timestamps.clear();
+        self.pending_timestamps.clear();
+        self.position = 0;
+        if let Some(handle) = self.handle.as_mut() {
+            handle
+                .rewind()
+                .map_err(|err| Error::IoError("seeking to start of file".to_string(), err))?;
+        }
+
+        Ok(())
+    }
+
+    /// Prefix the given lines with their timestamps.
+    /// The lines must start at `self.position`.
+    fn format(&mut self, lines: Vec<u8>) -> Result<Vec<u8>, Error> {
+        self.read_new_timestamps()?;
+
+        let line_timestamps = get_line_timestamps(&lines, self.position, &self.timestamps);
+        self.position += lines.len() as u64;
+        // Forget all timestamps of lines, that have been formatted.
+        self.timestamps = self.timestamps.split_off(&self.position);
+
+        let output = String::from_utf8_lossy(&lines);
+        Ok(add_timestamps(&output, &line_timestamps).into_bytes())
+    }
+
+    /// Read all timestamps that have been recorded since the last call.
+    fn read_new_timestamps(&mut self) -> Result<(), Error> {
+        if self.handle.is_none() {
+            // The task may not record any timestamps at all.
+            if !self.path.exists() {
+                return Ok(());
+            }
+            let handle = File::open(&self.path).map_err(|err| {
+                Error::IoPathError(self.path.clone(), "opening timestamp file", err)
+            })?;
+            self.handle = Some(handle);
+        }
+        let Some(handle) = self.handle.as_mut() else {
+            return Ok(());
+        };
+
+        handle
+            .read_to_end(&mut self.pending_timestamps)
+            .map_err(|err| Error::IoError("reading timestamp file".to_string(), err))?;
+        let Some(complete) = self
+            .pending_timestamps
+            .iter()
+            .rposition(|byte| *byte == b'\n')
+            .map(|index| index + 1)
+        else {
+            return Ok(());
+        };
+
+        let content: Vec<u8> = self.pending_timestamps.drain(..complete).collect();
+        parse_timestamps(&content, &mut self.timestamps);
+        // Timestamps of lines before the current position are no longer needed.
+        self.timestamps = self.timestamps.split_off(&self.position);
+
+        Ok(())
+    }
+}
@@ -5,8 +5,14 @@ use chrono::prelude::*;
 use serde_derive::{Deserialize, Serialize};
 use strum_macros::{Display, EnumString};
 
+use crate::archive::ArchivedTask;
+use crate::event::Event;
+use crate::log::{LineTimestamps, LogStream};
+use crate::resources::Resources;
+use crate::schedule::{Schedule, ScheduleTrigger};
 use crate::state::{Group, State};
-use crate::task::Task;
+use crate::task::{DependencyCondition, RetryPolicy, Task, TaskArrayRef};
+use crate::workflow::Workflow;
 
 /// Macro to simplify creating [From] implementations for each variant-contained
 /// struct; e.g. `impl_into_message!(AddMessage, Message::Add)` to make it possible
@@ -26,6 +32,8 @@ macro_rules! impl_into_message {
 #[derive(PartialEq, Eq, Clone, Debug, Deserialize, Serialize)]
 pub enum Message {
     Add(AddMessage),
+    /// Add the tasks of several messages at once.
+    AddBatch(AddBatchMessage),
     Remove(Vec<usize>),
     Switch(SwitchMessage),
     Stash(Vec<usize>),
@@ -53,16 +61,34 @@ pub enum Message {
     Group(GroupMessage),
     GroupResponse(GroupResponseMessage),
 
+    Schedule(ScheduleMessage),
+    ScheduleResponse(ScheduleResponseMessage),
+
     Status,
     StatusResponse(Box<State>),
     Log(LogRequestMessage),
     LogResponse(BTreeMap<usize, TaskLogMessage>),
 
+    /// The client requests (a page of) the tasks in the archive.
+    History(HistoryRequestMessage),
+    HistoryResponse(Vec<ArchivedTask>),
+    /// The client requests the log output of an archived task.
+    /// The daemon responds with a [LogResponse](Message::LogResponse), whose task ids are the
+    /// archive ids of the tasks.
+    HistoryLog(HistoryLogRequestMessage),
+
     /// The client requests a continuous stream of a task's log.
     StreamRequest(StreamRequestMessage),
     /// The next chunk of output, that's send to the client.
     Stream(String),
 
+    /// The client subscribes to changes of the daemon's state.
+    /// The connection is kept open and the daemon pushes an [Event](Message::Event)
+    /// for each change.
+    Subscribe,
+    /// A change of the daemon's state, that's pushed to subscribed clients.
+    Event(Event),
+
     Reset(ResetMessage),
     Clean(CleanMessage),
     DaemonShutdown(Shutdown),
@@ -74,6 +100,9 @@ pub enum Message {
     Close,
 
     Parallel(ParallelMessage),
+
+    /// Create the tasks of all steps of a workflow at once.
+    Workflow(WorkflowMessage),
 }
 
 /// This enum is used to express a selection of tasks.
@@ -99,6 +128,35 @@ pub struct AddMessage {
     pub priority: Option<i32>,
     pub label: Option<String>,
     pub print_task_id: bool,
+    #[serde(default = "Default::default")]
+    pub retry_policy: Option<RetryPolicy>,
+    /// The maximum runtime of the task in seconds.
+    #[serde(default = "Default::default")]
+    pub timeout: Option<u64>,
+    /// The resources the task needs while it's running.
+    #[serde(default = "Default::default")]
+    pub resources: Resources,
+    /// Write stdout and stderr of the task to separate log files.
+    #[serde(default = "Default::default")]
+    pub separate_output: bool,
+    /// The conditions of those dependencies that don't have to succeed.
+    #[serde(default = "Default::default")]
+    pub dependency_conditions: BTreeMap<usize, DependencyCondition>,
+    /// Add a task array with one task per parameter instead of a single task.
+    /// The command and the label are then handlebars templates,
+    /// which may use the `{{value}}` and the `{{index}}` of the parameter.
+    /// At most [MAX_ARRAY_SIZE] parameters are allowed.
+    #[serde(default = "Default::default")]
+    pub array: Option<Vec<String>>,
+    /// The task array a restarted task has been a member of.
+    /// The new task then keeps this membership, as long as the restarted task still exists
+    /// and may be managed by the client.
+    #[serde(default = "Default::default")]
+    pub array_member: Option<TaskArrayRef>,
+    /// The indices of earlier messages of the same [Message::AddBatch], whose tasks this task
+    /// depends on. This is only allowed inside of a batch.
+    #[serde(default = "Default::default")]
+    pub batch_dependencies: Vec<usize>,
 }
 
 /// We use a custom `Debug` implementation for [AddMessage], as the `envs` field just has
@@ -117,14 +175,73 @@ impl std::fmt::Debug for AddMessage {
             .field("group", &self.group)
             .field("enqueue_at", &self.enqueue_at)
             .field("dependencies", &self.dependencies)
+            .field("dependency_conditions", &self.dependency_conditions)
+            .field("array", &self.array)
+            .field("array_member", &self.array_member)
+            .field("batch_dependencies", &self.batch_dependencies)
             .field("label", &self.label)
             .field("print_task_id", &self.print_task_id)
+            .field("retry_policy", &self.retry_policy)
+            .field("timeout", &self.timeout)
+            .field("resources", &self.resources)
+            .field("separate_output", &self.separate_output)
             .finish()
     }
 }
 
 impl_into_message!(AddMessage, Message::Add);
 
+/// The maximum amount of tasks a single task array may contain.
+pub const MAX_ARRAY_SIZE: usize = 10_000;
+
+/// The tasks of a batch, which are added at once.
+///
+/// The environment variables that are shared by all tasks are only sent once.
+/// The `envs` of each [AddMessage] only contain the variables specific to that task, which
+/// take precedence over the shared ones.
+#[derive(PartialEq, Eq, Clone, Deserialize, Serialize)]
+pub struct AddBatchMessage {
+    pub envs: HashMap<String, String>,
+    pub tasks: Vec<AddMessage>,
+}
+
+/// Same as for [AddMessage], the `envs` are hidden in the log output.
+impl std::fmt::Debug for AddBatchMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AddBatchMessage")
+            .field("envs", &"hidden")
+            .field("tasks", &self.tasks)
+            .finish()
+    }
+}
+
+impl_into_message!(AddBatchMessage, Message::AddBatch);
+
+#[derive(PartialEq, Eq, Clone, Deserialize, Serialize)]
+pub struct WorkflowMessage {
+    pub workflow: Workflow,
+    /// The working directory of steps without an explicit path.
+    /// Relative paths of steps are relative to this directory.
+    pub path: PathBuf,
+    pub envs: HashMap<String, String>,
+    /// Stash all tasks of the workflow instead of queueing them.
+    pub stashed: bool,
+}
+
+/// Just like [AddMessage], the environment variables are hidden, as they might contain secrets.
+impl std::fmt::Debug for WorkflowMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WorkflowMessage")
+            .field("workflow", &self.workflow)
+            .field("path", &self.path)
+            .field("envs", &"hidden")
+            .field("stashed", &self.stashed)
+            .finish()
+    }
+}
+
+impl_into_message!(WorkflowMessage, Message::Workflow);
+
 #[derive(PartialEq, Eq, Clone, Debug, Deserialize, Serialize)]
 pub struct SwitchMessage {
     pub task_id_1: usize,
@@ -171,6 +288,9 @@ pub struct TaskToRestart {
     /// Cbor cannot represent Option<Option<T>> yet, which is why we have to utilize a
     /// boolean to indicate that the label should be released, rather than an `Some(None)`.
     pub delete_label: bool,
+    /// Restart the task with an updated timeout in seconds.
+    #[serde(default = "Default::default")]
+    pub timeout: Option<u64>,
 }
 
 #[derive(PartialEq, Eq, Clone, Debug, Deserialize, Serialize)]
@@ -245,6 +365,20 @@ pub enum GroupMessage {
     Add {
         name: String,
         parallel_tasks: Option<usize>,
+        #[serde(default = "Default::default")]
+        resources: Resources,
+        #[serde(default = "Default::default")]
+        priority: i32,
+    },
+    /// Replace the resource capacities of an existing group.
+    SetResources {
+        name: String,
+        resources: Resources,
+    },
+    /// Change the priority of an existing group.
+    SetPriority {
+        name: String,
+        priority: i32,
     },
     Remove(String),
     List,
@@ -255,10 +389,60 @@ impl_into_message!(GroupMessage, Message::Group);
 #[derive(PartialEq, Eq, Clone, Debug, Deserialize, Serialize)]
 pub struct GroupResponseMessage {
     pub groups: BTreeMap<String, Group>,
+    /// The daemon-wide limit of parallel tasks across all groups.
+    #[serde(default = "Default::default")]
+    pub max_parallel_tasks: Option<usize>,
 }
 
 impl_into_message!(GroupResponseMessage, Message::GroupResponse);
 
+#[derive(PartialEq, Eq, Clone, Debug, Deserialize, Serialize)]
+pub enum ScheduleMessage {
+    Add(AddScheduleMessage),
+    Remove(usize),
+    Pause(usize),
+    Resume(usize),
+    List,
+}
+
+impl_into_message!(ScheduleMessage, Message::Schedule);
+
+#[derive(PartialEq, Eq, Clone, Deserialize, Serialize)]
+pub struct AddScheduleMessage {
+    pub trigger: ScheduleTrigger,
+    pub command: String,
+    pub path: PathBuf,
+    pub envs: HashMap<String, String>,
+    pub group: String,
+    pub label: Option<String>,
+    pub skip_if_running: bool,
+    pub catch_up: bool,
+}
+
+/// We use a custom `Debug` implementation for [AddScheduleMessage], for the same reasons as
+/// for [AddMessage].
+impl std::fmt::Debug for AddScheduleMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AddScheduleMessage")
+            .field("trigger", &self.trigger)
+            .field("command", &self.command)
+            .field("path", &self.path)
+            .field("envs", &"hidden")
+            .field("group", &self.group)
+            .field("label", &self.label)
+            .field("skip_if_running", &self.skip_if_running)
+            .field("catch_up", &self.catch_up)
+            .finish()
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Debug, Deserialize, Serialize)]
+pub struct ScheduleResponseMessage {
+    pub schedules: BTreeMap<usize, Schedule>,
+}
+
+impl_into_message!(ScheduleResponseMessage, Message::ScheduleResponse);
+
 #[derive(PartialEq, Eq, Clone, Debug, Deserialize, Serialize)]
 pub struct ResetMessage {}
 
@@ -290,6 +474,12 @@ impl_into_message!(Shutdown, Message::DaemonShutdown);
 pub struct StreamRequestMessage {
     pub task_id: Option<usize>,
     pub lines: Option<usize>,
+    /// Only follow a specific output stream, if the task writes them to separate files.
+    #[serde(default = "Default::default")]
+    pub stream: Option<LogStream>,
+    /// Prefix each line with the time it has been written, if timestamps have been recorded.
+    #[serde(default = "Default::default")]
+    pub timestamps: bool,
 }
 
 impl_into_message!(StreamRequestMessage, Message::StreamRequest);
@@ -299,15 +489,45 @@ impl_into_message!(StreamRequestMessage, Message::StreamRequest);
 /// `task_ids` specifies the requested tasks. If none are given, all tasks are selected.
 /// `send_logs` Determines whether logs should be sent at all.
 /// `lines` Determines whether only a few lines of log should be returned.
+/// `stream` Only return a specific output stream of tasks that write them to separate files.
+/// `timestamps` Additionally return the recorded timestamps of each log line.
 #[derive(PartialEq, Eq, Clone, Debug, Deserialize, Serialize)]
 pub struct LogRequestMessage {
     pub task_ids: Vec<usize>,
     pub send_logs: bool,
     pub lines: Option<usize>,
+    #[serde(default = "Default::default")]
+    pub stream: Option<LogStream>,
+    #[serde(default = "Default::default")]
+    pub timestamps: bool,
 }
 
 impl_into_message!(LogRequestMessage, Message::Log);
 
+/// Request the newest tasks of the archive.
+///
+/// `before` Only return tasks whose archive id is lower than this.
+/// `limit` Only return the last X matching tasks.
+#[derive(PartialEq, Eq, Clone, Debug, Default, Deserialize, Serialize)]
+pub struct HistoryRequestMessage {
+    pub before: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+impl_into_message!(HistoryRequestMessage, Message::History);
+
+/// Request the log output of an archived task.
+///
+/// `archive_id` The id of the task in the archive.
+/// `lines` Only return the last few lines of the output.
+#[derive(PartialEq, Eq, Clone, Debug, Deserialize, Serialize)]
+pub struct HistoryLogRequestMessage {
+    pub archive_id: usize,
+    pub lines: Option<usize>,
+}
+
+impl_into_message!(HistoryLogRequestMessage, Message::HistoryLog);
+
 /// Helper struct for sending tasks and their log output to the client.
 #[derive(PartialEq, Eq, Clone, Deserialize, Serialize)]
 pub struct TaskLogMessage {
@@ -316,6 +536,16 @@ pub struct TaskLogMessage {
     /// Indicates whether the log output has been truncated or not.
     pub output_complete: bool,
     pub output: Option<Vec<u8>>,
+    /// The compressed stderr output of tasks that write it to a separate log file.
+    /// For such tasks, `output` only contains their stdout.
+    #[serde(default = "Default::default")]
+    pub stderr: Option<Vec<u8>>,
+    /// The timestamps of the lines in `output`, if they have been requested.
+    #[serde(default = "Default::default")]
+    pub output_timestamps: Option<LineTimestamps>,
+    /// The timestamps of the lines in `stderr`, if they have been requested.
+    #[serde(default = "Default::default")]
+    pub stderr_timestamps: Option<LineTimestamps>,
 }
 
 /// We use a custom `Debug` implementation for [TaskLogMessage], as the `output` field
@@ -326,6 +556,9 @@ impl std::fmt::Debug for TaskLogMessage {
             .field("task", &self.task)
             .field("output_complete", &self.output_complete)
             .field("output", &"hidden")
+            .field("stderr", &"hidden")
+            .field("output_timestamps", &"hidden")
+            .field("stderr_timestamps", &"hidden")
             .finish()
     }
 }
@@ -334,6 +567,10 @@ impl std::fmt::Debug for TaskLogMessage {
 pub struct ParallelMessage {
     pub parallel_tasks: usize,
     pub group: String,
+    /// Adjust the daemon-wide limit across all groups instead of the limit of the group.
+    /// In this case, `0` removes the limit.
+    #[serde(default = "Default::default")]
+    pub global: bool,
 }
 
 impl_into_message!(ParallelMessage, Message::Parallel);
@@ -52,6 +52,8 @@ pub mod certificate;
 /// This contains the main [Message](message::Message) enum and all its structs used to
 /// communicate with the daemon or client.
 pub mod message;
+/// The identity of connected clients.
+pub mod peer;
 /// This is probably the most interesting part for you.
 pub mod protocol;
 /// Functions to write and read the secret to/from a file.
@@ -59,4 +61,6 @@ pub mod secret;
 /// Low-level socket handling code.
 pub mod socket;
 /// Helper functions for reading and handling TLS files.
-mod tls;
+pub mod tls;
+/// Named principals with restricted permissions, that authenticate via their own token.
+pub mod tokens;
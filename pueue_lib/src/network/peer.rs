@@ -0,0 +1,39 @@
+use std::fmt::{self, Display};
+
+use serde_derive::{Deserialize, Serialize};
+
+/// Everything the daemon knows about the identity of a connected client.
+///
+/// Unix sockets provide the credentials of the client's process.
+/// For TCP connections, only the address of the client is known.
+#[derive(PartialEq, Eq, Clone, Debug, Default, Deserialize, Serialize)]
+pub struct PeerIdentity {
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    pub pid: Option<i32>,
+    pub address: Option<String>,
+}
+
+impl Display for PeerIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(uid) = self.uid {
+            parts.push(format!("uid={uid}"));
+        }
+        if let Some(gid) = self.gid {
+            parts.push(format!("gid={gid}"));
+        }
+        if let Some(pid) = self.pid {
+            parts.push(format!("pid={pid}"));
+        }
+        if let Some(address) = &self.address {
+            parts.push(format!("address={address}"));
+        }
+
+        if parts.is_empty() {
+            write!(f, "unknown")
+        } else {
+            write!(f, "{}", parts.join(", "))
+        }
+    }
+}
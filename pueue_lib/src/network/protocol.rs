@@ -66,6 +66,16 @@ pub async fn send_bytes(payload: &[u8], stream: &mut GenericStream) -> Result<()
 ///    the length of the payload we're going to receive.
 /// 2. Receive chunks of [PACKET_SIZE] bytes until we finished all expected bytes.
 pub async fn receive_bytes(stream: &mut GenericStream) -> Result<Vec<u8>, Error> {
+    receive_bytes_with_max_size(stream, None).await
+}
+
+/// The same as [receive_bytes], but payloads larger than `max_size` bytes are rejected
+/// before any memory is allocated for them.
+/// Use this, whenever the other side hasn't been authenticated yet.
+pub async fn receive_bytes_with_max_size(
+    stream: &mut GenericStream,
+    max_size: Option<usize>,
+) -> Result<Vec<u8>, Error> {
     // Receive the header with the overall message size
     let mut header = vec![0; 8];
     stream
@@ -75,6 +85,12 @@ pub async fn receive_bytes(stream: &mut GenericStream) -> Result<Vec<u8>, Error>
     let mut header = Cursor::new(header);
     let message_size = ReadBytesExt::read_u64::<BigEndian>(&mut header)? as usize;
 
+    if let Some(max_size) = max_size {
+        if message_size > max_size {
+            return Err(Error::MessageTooBig(message_size, max_size));
+        }
+    }
+
     // Buffer for the whole payload
     let mut payload_bytes = Vec::with_capacity(message_size);
 
@@ -5,6 +5,12 @@ use std::path::Path;
 use rand::{distributions::Alphanumeric, Rng};
 
 use crate::error::Error;
+use crate::settings::Shared;
+
+/// Sent by clients of the unix socket instead of the secret, if they don't have access to it.
+/// The daemon accepts these clients nonetheless, if it allows them via their credentials.
+#[cfg(not(target_os = "windows"))]
+const PEER_CREDENTIALS_PLACEHOLDER: &[u8] = b"peer_credentials";
 
 /// Read the shared secret from a file.
 pub fn read_shared_secret(path: &Path) -> Result<Vec<u8>, Error> {
@@ -22,6 +28,37 @@ pub fn read_shared_secret(path: &Path) -> Result<Vec<u8>, Error> {
     Ok(buffer)
 }
 
+/// Compare a secret that has been received from a client with the expected one.
+///
+/// The comparison takes the same time, no matter how many bytes match, which prevents
+/// timing attacks.
+pub fn secrets_match(received: &[u8], expected: &[u8]) -> bool {
+    if received.len() != expected.len() {
+        return false;
+    }
+
+    received
+        .iter()
+        .zip(expected)
+        .fold(0, |difference, (a, b)| difference | (a ^ b))
+        == 0
+}
+
+/// Read the secret, that's sent by clients during the handshake.
+///
+/// If the secret file doesn't exist and the unix socket is used, a placeholder is sent
+/// instead. This way, clients don't need the secret, if the daemon allows them via the
+/// credentials of their process.
+pub fn read_client_secret(settings: &Shared) -> Result<Vec<u8>, Error> {
+    let path = settings.shared_secret_path();
+    #[cfg(not(target_os = "windows"))]
+    if settings.use_unix_socket && !path.exists() {
+        return Ok(PEER_CREDENTIALS_PLACEHOLDER.to_vec());
+    }
+
+    read_shared_secret(&path)
+}
+
 /// Generate a random secret and write it to a file.
 pub fn init_shared_secret(path: &Path) -> Result<(), Error> {
     if path.exists() {
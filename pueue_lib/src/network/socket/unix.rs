@@ -1,14 +1,16 @@
 use std::convert::TryFrom;
 
 use async_trait::async_trait;
-use log::info;
+use log::{info, warn};
 use rustls::ServerName;
 use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net::{TcpListener, TcpStream, UnixListener, UnixStream};
 use tokio_rustls::TlsAcceptor;
 
 use crate::error::Error;
+use crate::network::peer::PeerIdentity;
 use crate::network::tls::{get_tls_connector, get_tls_listener};
+use crate::network::tokens::Principal;
 use crate::settings::Shared;
 
 /// Unix specific cleanup handling when getting a SIGINT/SIGTERM.
@@ -21,6 +23,35 @@ pub fn socket_cleanup(settings: &Shared) -> Result<(), std::io::Error> {
     Ok(())
 }
 
+/// The principal of a client that is allowed to connect without the shared secret.
+///
+/// Clients of the unix socket are identified by the credentials of their process, which are
+/// provided by the kernel (`SO_PEERCRED`) and thereby can't be forged.
+/// Their permissions are restricted by the role that's configured for their user or group id.
+pub fn peer_principal(settings: &Shared, peer: &PeerIdentity) -> Option<Principal> {
+    let uid_principal = peer.uid.and_then(|uid| {
+        settings
+            .unix_socket_allowed_uids
+            .get(&uid)
+            .map(|role| Principal {
+                name: format!("uid={uid}"),
+                role: *role,
+            })
+    });
+
+    uid_principal.or_else(|| {
+        peer.gid.and_then(|gid| {
+            settings
+                .unix_socket_allowed_gids
+                .get(&gid)
+                .map(|role| Principal {
+                    name: format!("gid={gid}"),
+                    role: *role,
+                })
+        })
+    })
+}
+
 /// A new trait, which can be used to represent Unix- and TcpListeners. \
 /// This is necessary to easily write generic functions where both types can be used.
 #[async_trait]
@@ -69,9 +100,44 @@ impl Listener for UnixListener {
 
 /// A new trait, which can be used to represent Unix- and Tls encrypted TcpStreams. \
 /// This is necessary to write generic functions where both types can be used.
-pub trait Stream: AsyncRead + AsyncWrite + Unpin + Send {}
-impl Stream for UnixStream {}
-impl Stream for tokio_rustls::server::TlsStream<TcpStream> {}
+pub trait Stream: AsyncRead + AsyncWrite + Unpin + Send {
+    /// The identity of the client on the other end of this connection, as far as it's known.
+    fn peer_identity(&self) -> PeerIdentity {
+        PeerIdentity::default()
+    }
+}
+
+impl Stream for UnixStream {
+    fn peer_identity(&self) -> PeerIdentity {
+        match self.peer_cred() {
+            Ok(credentials) => PeerIdentity {
+                uid: Some(credentials.uid()),
+                gid: Some(credentials.gid()),
+                pid: credentials.pid(),
+                address: None,
+            },
+            Err(err) => {
+                warn!("Failed to get credentials of unix socket client: {err}");
+                PeerIdentity::default()
+            }
+        }
+    }
+}
+
+impl Stream for tokio_rustls::server::TlsStream<TcpStream> {
+    fn peer_identity(&self) -> PeerIdentity {
+        PeerIdentity {
+            address: self
+                .get_ref()
+                .0
+                .peer_addr()
+                .ok()
+                .map(|addr| addr.to_string()),
+            ..Default::default()
+        }
+    }
+}
+
 impl Stream for tokio_rustls::client::TlsStream<TcpStream> {}
 
 /// Convenience type, so we don't have type write `Box<dyn Listener>` all the time.
@@ -143,7 +209,18 @@ pub async fn get_listener(settings: &Shared) -> Result<GenericListener, Error> {
         }
 
         let unix_listener = UnixListener::bind(&socket_path)
-            .map_err(|err| Error::IoPathError(socket_path, "creating unix socket", err))?;
+            .map_err(|err| Error::IoPathError(socket_path.clone(), "creating unix socket", err))?;
+
+        // Other users have to be able to connect, if they're allowed via their credentials.
+        if !settings.unix_socket_allowed_uids.is_empty()
+            || !settings.unix_socket_allowed_gids.is_empty()
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let permissions = std::fs::Permissions::from_mode(0o666);
+            std::fs::set_permissions(&socket_path, permissions).map_err(|err| {
+                Error::IoPathError(socket_path, "setting unix socket permissions", err)
+            })?;
+        }
         return Ok(Box::new(unix_listener));
     }
 
@@ -7,7 +7,9 @@ use tokio::net::{TcpListener, TcpStream};
 use tokio_rustls::TlsAcceptor;
 
 use crate::error::Error;
+use crate::network::peer::PeerIdentity;
 use crate::network::tls::{get_tls_connector, get_tls_listener};
+use crate::network::tokens::Principal;
 use crate::settings::Shared;
 
 /// Windowsspecific cleanup handling when getting a SIGINT/SIGTERM.
@@ -15,6 +17,12 @@ pub fn socket_cleanup(_settings: &Shared) -> Result<(), Error> {
     Ok(())
 }
 
+/// The principal of a client that is allowed to connect without the shared secret.
+/// Clients can only be identified via the credentials of unix sockets, which don't exist here.
+pub fn peer_principal(_settings: &Shared, _peer: &PeerIdentity) -> Option<Principal> {
+    None
+}
+
 /// This is a helper struct for TCP connections.
 /// TCP should always be used in conjunction with TLS.
 /// That's why this helper exists, which encapsulates the logic of accepting a new
@@ -42,8 +50,27 @@ impl Listener for TlsTcpListener {
 
 /// A new trait, which can be used to represent Unix- and Tls encrypted TcpStreams.
 /// This is necessary to write generic functions where both types can be used.
-pub trait Stream: AsyncRead + AsyncWrite + Unpin + Send {}
-impl Stream for tokio_rustls::server::TlsStream<TcpStream> {}
+pub trait Stream: AsyncRead + AsyncWrite + Unpin + Send {
+    /// The identity of the client on the other end of this connection, as far as it's known.
+    fn peer_identity(&self) -> PeerIdentity {
+        PeerIdentity::default()
+    }
+}
+
+impl Stream for tokio_rustls::server::TlsStream<TcpStream> {
+    fn peer_identity(&self) -> PeerIdentity {
+        PeerIdentity {
+            address: self
+                .get_ref()
+                .0
+                .peer_addr()
+                .ok()
+                .map(|addr| addr.to_string()),
+            ..Default::default()
+        }
+    }
+}
+
 impl Stream for tokio_rustls::client::TlsStream<TcpStream> {}
 
 /// Two convenient types, so we don't have type write Box<dyn ...> all the time.
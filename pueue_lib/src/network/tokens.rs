@@ -0,0 +1,105 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::error::Error;
+use crate::network::secret::secrets_match;
+
+/// The roles of principals, ordered by the amount of permissions they grant.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Inspect the state and the logs of tasks.
+    ReadOnly,
+    /// Additionally add new tasks and manage the tasks one added.
+    Submit,
+    /// Full access, just like clients that authenticate via the shared secret.
+    Admin,
+}
+
+impl Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Role::ReadOnly => write!(f, "read_only"),
+            Role::Submit => write!(f, "submit"),
+            Role::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+/// The entry of a single principal in the token file.
+#[derive(PartialEq, Eq, Clone, Debug, Deserialize, Serialize)]
+pub struct TokenEntry {
+    pub token: String,
+    pub role: Role,
+}
+
+/// A principal that has been authenticated via its token.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Principal {
+    pub name: String,
+    pub role: Role,
+}
+
+/// All principals of the token file by their name.
+///
+/// The file is a YAML map, e.g.:
+/// ```yaml
+/// alice:
+///   token: some_long_random_token
+///   role: admin
+/// bob:
+///   token: another_long_random_token
+///   role: submit
+/// ```
+///
+/// Clients use a principal's token just like the shared secret,
+/// i.e. by pointing their `shared_secret_path` to a file that contains the token.
+#[derive(PartialEq, Eq, Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Tokens(pub BTreeMap<String, TokenEntry>);
+
+impl Tokens {
+    /// Read and validate the token file.
+    pub fn read(path: &Path) -> Result<Tokens, Error> {
+        let file = File::open(path)
+            .map_err(|err| Error::IoPathError(path.to_path_buf(), "opening token file", err))?;
+        let tokens: Tokens = serde_yaml::from_reader(BufReader::new(file))
+            .map_err(|err| Error::ConfigDeserialization(format!("Invalid token file: {err}")))?;
+
+        // Tokens have to be unique, otherwise we couldn't tell the principals apart.
+        let mut seen = Vec::new();
+        for (name, entry) in &tokens.0 {
+            let token = entry.token.trim();
+            if token.is_empty() {
+                return Err(Error::ConfigDeserialization(format!(
+                    "The token of principal '{name}' is empty"
+                )));
+            }
+            if seen.contains(&token) {
+                return Err(Error::ConfigDeserialization(format!(
+                    "The token of principal '{name}' is used more than once"
+                )));
+            }
+            seen.push(token);
+        }
+
+        Ok(tokens)
+    }
+
+    /// Find the principal that a client authenticated as.
+    /// Surrounding whitespace, such as a trailing newline of a token file, is ignored.
+    pub fn principal(&self, token: &[u8]) -> Option<Principal> {
+        let token = std::str::from_utf8(token).ok()?.trim();
+        self.0
+            .iter()
+            .find(|(_, entry)| secrets_match(entry.token.trim().as_bytes(), token.as_bytes()))
+            .map(|(name, entry)| Principal {
+                name: name.clone(),
+                role: entry.role,
+            })
+    }
+}
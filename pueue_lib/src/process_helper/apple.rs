@@ -1,6 +1,28 @@
-use libproc::libproc::{proc_pid, task_info};
+use chrono::{DateTime, Local, TimeZone};
+use libproc::libproc::{bsd_info::BSDInfo, proc_pid, task_info};
 
 /// Check, whether a specific process exists or not
 pub fn process_exists(pid: u32) -> bool {
     proc_pid::pidinfo::<task_info::TaskInfo>(pid.try_into().unwrap(), 0).is_ok()
 }
+
+/// Get the point in time at which a specific process has been started.
+pub fn process_start_time(pid: u32) -> Option<DateTime<Local>> {
+    let info = proc_pid::pidinfo::<BSDInfo>(pid.try_into().ok()?, 0).ok()?;
+    Local
+        .timestamp_opt(
+            info.pbi_start_tvsec.try_into().ok()?,
+            (info.pbi_start_tvusec * 1000).try_into().ok()?,
+        )
+        .single()
+}
+
+/// Getting the load average isn't supported on this platform yet.
+pub fn load_average() -> Option<f64> {
+    None
+}
+
+/// Getting the available memory isn't supported on this platform yet.
+pub fn available_memory() -> Option<u64> {
+    None
+}
@@ -1,3 +1,4 @@
+use chrono::{DateTime, Local, TimeZone};
 use procfs::process;
 
 /// Check, whether a specific process is exists or not
@@ -10,3 +11,28 @@ pub fn process_exists(pid: u32) -> bool {
         },
     }
 }
+
+/// Get the point in time at which a specific process has been started.
+pub fn process_start_time(pid: u32) -> Option<DateTime<Local>> {
+    let process = process::Process::new(pid.try_into().ok()?).ok()?;
+    // The start time is given in clock ticks since the system booted.
+    let ticks = process.stat().ok()?.starttime;
+    let boot_time = procfs::boot_time_secs().ok()?;
+    let millis = boot_time * 1000 + ticks * 1000 / procfs::ticks_per_second();
+
+    Local.timestamp_millis_opt(millis.try_into().ok()?).single()
+}
+
+/// Get the one-minute load average of the system.
+pub fn load_average() -> Option<f64> {
+    procfs::LoadAverage::new()
+        .ok()
+        .map(|load_average| load_average.one as f64)
+}
+
+/// Get the amount of memory in bytes, that's available for new processes.
+pub fn available_memory() -> Option<u64> {
+    procfs::Meminfo::new()
+        .ok()
+        .and_then(|meminfo| meminfo.mem_available)
+}
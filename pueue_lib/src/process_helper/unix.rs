@@ -4,6 +4,8 @@
 use anyhow::Result;
 use command_group::{GroupChild, Signal, UnixChildExt};
 use log::info;
+use nix::sys::signal::killpg;
+use nix::unistd::Pid;
 
 use crate::settings::Settings;
 
@@ -28,6 +30,18 @@ where
     Ok(())
 }
 
+/// Send a signal to a process group by its id.
+///
+/// This is used for processes that aren't children of the current daemon, e.g. tasks that
+/// kept running while the daemon has been restarted.
+pub fn send_signal_to_process_group<T>(pgid: u32, signal: T) -> Result<()>
+where
+    T: Into<Signal>,
+{
+    killpg(Pid::from_raw(pgid.try_into()?), signal.into())?;
+    Ok(())
+}
+
 /// This is a helper function to safely kill a child process group.
 /// Its purpose is to properly kill all processes and prevent any dangling processes.
 pub fn kill_child(task_id: usize, child: &mut GroupChild) -> std::io::Result<()> {
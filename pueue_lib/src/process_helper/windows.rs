@@ -75,6 +75,15 @@ where
     Ok(())
 }
 
+/// Send a signal to a process group by its id.
+/// Windows doesn't have process groups, so this isn't supported.
+pub fn send_signal_to_process_group<T>(_pgid: u32, _signal: T) -> Result<()>
+where
+    T: Into<Signal>,
+{
+    bail!("Signaling process groups isn't supported on Windows.");
+}
+
 /// Kill a child process
 pub fn kill_child(task_id: usize, child: &mut GroupChild) -> std::io::Result<()> {
     match child.kill() {
@@ -253,6 +262,21 @@ pub fn process_exists(pid: u32) -> bool {
     false
 }
 
+/// Getting the start time of processes isn't supported on this platform yet.
+pub fn process_start_time(_pid: u32) -> Option<chrono::DateTime<chrono::Local>> {
+    None
+}
+
+/// Getting the load average isn't supported on this platform yet.
+pub fn load_average() -> Option<f64> {
+    None
+}
+
+/// Getting the available memory isn't supported on this platform yet.
+pub fn available_memory() -> Option<u64> {
+    None
+}
+
 #[cfg(test)]
 mod test {
     use std::process::Command;
@@ -0,0 +1,204 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Deserializer};
+use serde_derive::Deserialize;
+
+/// Abstract resources, such as `cpu` or `mem`, and their amounts.
+///
+/// Tasks use this to declare what they need, while groups and the daemon use it to declare
+/// their capacities. The names are arbitrary and only have a meaning to the user.
+/// Resources that aren't declared as a capacity aren't limited.
+pub type Resources = BTreeMap<String, u64>;
+
+/// Parse an amount such as "2", "512M" or "4G".
+/// The suffixes `K`, `M`, `G` and `T` are binary multiples (`1K = 1024`).
+pub fn parse_resource_amount(src: &str) -> Result<u64, String> {
+    let src = src.trim();
+    let (number, exponent) = match src.char_indices().last() {
+        Some((index, 'k' | 'K')) => (&src[..index], 1),
+        Some((index, 'm' | 'M')) => (&src[..index], 2),
+        Some((index, 'g' | 'G')) => (&src[..index], 3),
+        Some((index, 't' | 'T')) => (&src[..index], 4),
+        _ => (src, 0),
+    };
+
+    number
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .and_then(|number| number.checked_mul(1024u64.pow(exponent)))
+        .ok_or_else(|| format!("could not parse \"{src}\" as amount, e.g. \"2\" or \"4G\""))
+}
+
+/// Parse a resource in the form of `name=amount`, e.g. `mem=4G`.
+pub fn parse_resource(src: &str) -> Result<(String, u64), String> {
+    let (name, amount) = src
+        .split_once('=')
+        .ok_or_else(|| format!("expected \"name=amount\", got \"{src}\""))?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("the name of a resource mustn't be empty".into());
+    }
+
+    Ok((name.to_string(), parse_resource_amount(amount)?))
+}
+
+/// Format an amount in the most compact binary unit without losing precision.
+pub fn format_resource_amount(amount: u64) -> String {
+    for (exponent, suffix) in [(4, "T"), (3, "G"), (2, "M"), (1, "K")] {
+        let unit = 1024u64.pow(exponent);
+        if amount != 0 && amount % unit == 0 {
+            return format!("{}{suffix}", amount / unit);
+        }
+    }
+
+    amount.to_string()
+}
+
+/// Format resources as a comma separated list, e.g. `cpu=2, mem=4G`.
+pub fn format_resources(resources: &Resources) -> String {
+    resources
+        .iter()
+        .map(|(name, amount)| format!("{name}={}", format_resource_amount(*amount)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Check whether the requested resources fit into the capacity, given what's already in use.
+pub fn resources_fit(requested: &Resources, used: &Resources, capacity: &Resources) -> bool {
+    requested.iter().all(|(name, amount)| {
+        let Some(capacity) = capacity.get(name) else {
+            return true;
+        };
+        let used = used.get(name).copied().unwrap_or(0);
+
+        used.saturating_add(*amount) <= *capacity
+    })
+}
+
+/// Return the first requested resource that exceeds the capacity, even if nothing else is
+/// running. Tasks with such requests can never be started.
+pub fn exceeded_capacity<'a>(
+    requested: &'a Resources,
+    capacity: &Resources,
+) -> Option<(&'a String, u64)> {
+    requested.iter().find_map(|(name, amount)| {
+        capacity
+            .get(name)
+            .filter(|capacity| *amount > **capacity)
+            .map(|capacity| (name, *capacity))
+    })
+}
+
+/// An amount in the configuration file.
+/// It may either be a plain number or a string with a unit, e.g. `"64G"`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ConfigAmount {
+    Number(u64),
+    Text(String),
+}
+
+impl ConfigAmount {
+    fn parse<E: serde::de::Error>(self) -> Result<u64, E> {
+        match self {
+            ConfigAmount::Number(amount) => Ok(amount),
+            ConfigAmount::Text(text) => parse_resource_amount(&text).map_err(E::custom),
+        }
+    }
+}
+
+/// Deserialize capacities from the configuration file, e.g. `mem: "64G"`.
+pub(crate) fn deserialize_resources<'de, D>(deserializer: D) -> Result<Resources, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    BTreeMap::<String, ConfigAmount>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(name, amount)| Ok((name, amount.parse()?)))
+        .collect()
+}
+
+/// Deserialize an optional amount from the configuration file, e.g. `"2G"`.
+pub(crate) fn deserialize_optional_amount<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<ConfigAmount>::deserialize(deserializer)?
+        .map(ConfigAmount::parse)
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_resource() {
+        assert_eq!(parse_resource("cpu=2"), Ok(("cpu".into(), 2)));
+        assert_eq!(
+            parse_resource("mem=4G"),
+            Ok(("mem".into(), 4 * 1024 * 1024 * 1024))
+        );
+        assert_eq!(
+            parse_resource("mem=512m"),
+            Ok(("mem".into(), 512 * 1024 * 1024))
+        );
+        assert!(parse_resource("mem").is_err());
+        assert!(parse_resource("=4").is_err());
+        assert!(parse_resource("mem=4X").is_err());
+        assert!(parse_resource("mem=99999999T").is_err());
+    }
+
+    #[test]
+    fn test_format_resource_amount() {
+        assert_eq!(format_resource_amount(0), "0");
+        assert_eq!(format_resource_amount(3), "3");
+        assert_eq!(format_resource_amount(1536), "1536");
+        assert_eq!(format_resource_amount(3072), "3K");
+        assert_eq!(format_resource_amount(4 * 1024 * 1024 * 1024), "4G");
+    }
+
+    #[test]
+    fn test_deserialize_resources() {
+        #[derive(Deserialize)]
+        struct Capacities {
+            #[serde(deserialize_with = "deserialize_resources")]
+            resources: Resources,
+        }
+
+        let capacities: Capacities =
+            serde_yaml::from_str("resources:\n  cpu: 8\n  mem: 64G\n").unwrap();
+        assert_eq!(
+            capacities.resources,
+            Resources::from([("cpu".into(), 8), ("mem".into(), 64 * 1024 * 1024 * 1024)])
+        );
+
+        assert!(serde_yaml::from_str::<Capacities>("resources:\n  mem: lots\n").is_err());
+    }
+
+    #[test]
+    fn test_resources_fit() {
+        let capacity = Resources::from([("cpu".into(), 4), ("mem".into(), 8)]);
+        let used = Resources::from([("cpu".into(), 3)]);
+
+        let request = Resources::from([("cpu".into(), 1), ("mem".into(), 8)]);
+        assert!(resources_fit(&request, &used, &capacity));
+
+        let request = Resources::from([("cpu".into(), 2)]);
+        assert!(!resources_fit(&request, &used, &capacity));
+
+        // Resources without a capacity aren't limited.
+        let request = Resources::from([("gpu".into(), 100)]);
+        assert!(resources_fit(&request, &used, &capacity));
+        assert_eq!(exceeded_capacity(&request, &capacity), None);
+
+        let request = Resources::from([("mem".into(), 9)]);
+        assert_eq!(
+            exceeded_capacity(&request, &capacity),
+            Some((&"mem".into(), 8))
+        );
+    }
+}
@@ -0,0 +1,324 @@
+use chrono::prelude::*;
+use chrono::{Duration, LocalResult};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::error::Error;
+use crate::task::Task;
+
+/// Determines when a [Schedule] fires.
+#[derive(PartialEq, Eq, Clone, Debug, Deserialize, Serialize)]
+pub enum ScheduleTrigger {
+    /// A classic cron expression with five fields, e.g. `*/5 * * * *`.
+    Cron(String),
+    /// Fire every n seconds.
+    Interval(u64),
+}
+
+impl ScheduleTrigger {
+    /// Make sure that the trigger can actually be used to compute run times.
+    pub fn validate(&self) -> Result<(), Error> {
+        match self {
+            ScheduleTrigger::Cron(expression) => CronExpression::parse(expression).map(|_| ()),
+            ScheduleTrigger::Interval(0) => Err(Error::InvalidSchedule(
+                "The interval must be at least one second".into(),
+            )),
+            ScheduleTrigger::Interval(_) => Ok(()),
+        }
+    }
+
+    /// Compute the next point in time this trigger fires after the given date.
+    /// Returns `Ok(None)`, if the trigger never fires again.
+    pub fn next_after(&self, after: DateTime<Local>) -> Result<Option<DateTime<Local>>, Error> {
+        match self {
+            ScheduleTrigger::Cron(expression) => {
+                Ok(CronExpression::parse(expression)?.next_after(after))
+            }
+            ScheduleTrigger::Interval(seconds) => {
+                let seconds = (*seconds).min(i32::MAX as u64) as i64;
+                Ok(after.checked_add_signed(Duration::seconds(seconds)))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ScheduleTrigger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScheduleTrigger::Cron(expression) => write!(f, "cron \"{expression}\""),
+            ScheduleTrigger::Interval(seconds) => write!(f, "every {seconds}s"),
+        }
+    }
+}
+
+/// A recurring job that's owned by the daemon.
+///
+/// Each time the schedule fires, a fresh copy of `task` is created via [Task::from_task]
+/// and added to the state.
+#[derive(PartialEq, Eq, Clone, Debug, Deserialize, Serialize)]
+pub struct Schedule {
+    pub id: usize,
+    pub trigger: ScheduleTrigger,
+    /// The template from which new tasks are instantiated.
+    pub task: Task,
+    /// Paused schedules don't create any new tasks.
+    pub paused: bool,
+    /// Don't create a new task, if the task of the previous run is still queued or running.
+    pub skip_if_running: bool,
+    /// If runs have been missed while the daemon was down, run once as soon as the daemon is
+    /// up again. Otherwise, missed runs are simply skipped.
+    pub catch_up: bool,
+    /// The next point in time at which this schedule fires.
+    pub next_run: Option<DateTime<Local>>,
+    /// The last point in time at which this schedule fired.
+    pub last_run: Option<DateTime<Local>>,
+    /// The id of the task that has been created by the last run.
+    pub last_task_id: Option<usize>,
+}
+
+/// A parsed cron expression with the classic five fields:
+/// `minute hour day-of-month month day-of-week`
+///
+/// Each field supports `*`, single values, ranges (`1-5`), lists (`1,3,5`) and steps (`*/15`).
+/// Months and weekdays can also be given by their three letter english names.
+/// Furthermore, the macros `@hourly`, `@daily`, `@weekly`, `@monthly` and `@yearly` are supported.
+///
+/// Each field is represented as a bit set of the allowed values.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct CronExpression {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    /// Whether the day-of-month and day-of-week fields have been restricted.
+    /// If both are restricted, a day matches if **either** field matches (classic cron behavior).
+    days_of_month_restricted: bool,
+    days_of_week_restricted: bool,
+}
+
+const MONTH_NAMES: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+impl CronExpression {
+    /// Parse a cron expression.
+    pub fn parse(expression: &str) -> Result<CronExpression, Error> {
+        let expression = match expression.trim() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            expression => expression,
+        };
+
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(Error::InvalidSchedule(format!(
+                "Expected 5 fields in cron expression, found {}",
+                fields.len()
+            )));
+        }
+
+        // Sunday can be represented by both `0` and `7`.
+        let mut days_of_week = parse_field(fields[4], 0, 7, &WEEKDAY_NAMES, 0)?;
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week |= 1;
+        }
+
+        Ok(CronExpression {
+            minutes: parse_field(fields[0], 0, 59, &[], 0)?,
+            hours: parse_field(fields[1], 0, 23, &[], 0)?,
+            days_of_month: parse_field(fields[2], 1, 31, &[], 0)?,
+            months: parse_field(fields[3], 1, 12, &MONTH_NAMES, 1)?,
+            days_of_week,
+            days_of_month_restricted: fields[2] != "*",
+            days_of_week_restricted: fields[4] != "*",
+        })
+    }
+
+    /// Get the first point in time after `after` that matches this expression.
+    ///
+    /// We look at most five years into the future, which covers expressions such as
+    /// `0 0 29 2 *` (leap days). Expressions that never match return `None`.
+    pub fn next_after(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        let start = after.date_naive();
+        for day_offset in 0..(366 * 5) {
+            let date = start + Duration::days(day_offset);
+            if !self.matches_date(date) {
+                continue;
+            }
+
+            for hour in 0..24 {
+                if self.hours & (1 << hour) == 0 {
+                    continue;
+                }
+                for minute in 0..60 {
+                    if self.minutes & (1 << minute) == 0 {
+                        continue;
+                    }
+
+                    let naive = date.and_hms_opt(hour, minute, 0)?;
+                    // Local times that don't exist (DST gaps) are skipped.
+                    let time = match Local.from_local_datetime(&naive) {
+                        LocalResult::Single(time) => time,
+                        LocalResult::Ambiguous(earliest, _) => earliest,
+                        LocalResult::None => continue,
+                    };
+
+                    if time > after {
+                        return Some(time);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Check whether the month, day-of-month and day-of-week fields match the given date.
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        if self.months & (1 << date.month()) == 0 {
+            return false;
+        }
+
+        let day_of_month = self.days_of_month & (1 << date.day()) != 0;
+        let day_of_week = self.days_of_week & (1 << date.weekday().num_days_from_sunday()) != 0;
+
+        if self.days_of_month_restricted && self.days_of_week_restricted {
+            day_of_month || day_of_week
+        } else {
+            day_of_month && day_of_week
+        }
+    }
+}
+
+/// Parse a single field of a cron expression into a bit set of allowed values.
+///
+/// `names` are alternative names for the values, starting at `name_offset`.
+fn parse_field(
+    field: &str,
+    min: u32,
+    max: u32,
+    names: &[&str],
+    name_offset: u32,
+) -> Result<u64, Error> {
+    let parse_value = |value: &str| -> Result<u32, Error> {
+        let lowercase = value.to_lowercase();
+        if let Some(index) = names.iter().position(|name| *name == lowercase) {
+            return Ok(index as u32 + name_offset);
+        }
+
+        let number = value
+            .parse::<u32>()
+            .map_err(|_| Error::InvalidSchedule(format!("Invalid value \"{value}\"")))?;
+        if number < min || number > max {
+            return Err(Error::InvalidSchedule(format!(
+                "Value {number} is out of range {min}-{max}"
+            )));
+        }
+
+        Ok(number)
+    };
+
+    let mut bits = 0;
+    for part in field.split(',') {
+        // Split off an optional step.
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step = step
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| Error::InvalidSchedule(format!("Invalid step \"{step}\"")))?;
+                (range, Some(step))
+            }
+            None => (part, None),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (parse_value(start)?, parse_value(end)?)
+        } else {
+            let value = parse_value(range)?;
+            // A single value with a step, such as `5/15`, means `5-max/15`.
+            if step.is_some() {
+                (value, max)
+            } else {
+                (value, value)
+            }
+        };
+
+        if start > end {
+            return Err(Error::InvalidSchedule(format!("Invalid range \"{range}\"")));
+        }
+
+        let step = step.unwrap_or(1);
+        let mut value = start;
+        while value <= end {
+            bits |= 1 << value;
+            value += step;
+        }
+    }
+
+    Ok(bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn local(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(year, month, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_cron_every_fifteen_minutes() {
+        let cron = CronExpression::parse("*/15 * * * *").unwrap();
+        let next = cron.next_after(local(2023, 1, 1, 10, 7)).unwrap();
+        assert_eq!(next, local(2023, 1, 1, 10, 15));
+
+        // A matching time isn't returned, if it's the time we start at.
+        let next = cron.next_after(local(2023, 1, 1, 10, 15)).unwrap();
+        assert_eq!(next, local(2023, 1, 1, 10, 30));
+    }
+
+    #[test]
+    fn test_cron_weekdays_and_names() {
+        // 2023-01-06 is a friday, so the next weekday is monday the 9th.
+        let cron = CronExpression::parse("30 8 * * mon-fri").unwrap();
+        let next = cron.next_after(local(2023, 1, 6, 9, 0)).unwrap();
+        assert_eq!(next, local(2023, 1, 9, 8, 30));
+
+        let cron = CronExpression::parse("0 0 1 jan *").unwrap();
+        let next = cron.next_after(local(2023, 6, 1, 0, 0)).unwrap();
+        assert_eq!(next, local(2024, 1, 1, 0, 0));
+    }
+
+    #[test]
+    fn test_cron_day_of_month_or_day_of_week() {
+        // If both day fields are restricted, either of them has to match.
+        // 2023-01-01 is a sunday.
+        let cron = CronExpression::parse("0 12 15 * 0").unwrap();
+        let next = cron.next_after(local(2023, 1, 2, 0, 0)).unwrap();
+        assert_eq!(next, local(2023, 1, 8, 12, 0));
+    }
+
+    #[test]
+    fn test_cron_invalid_expressions() {
+        assert!(CronExpression::parse("* * * *").is_err());
+        assert!(CronExpression::parse("60 * * * *").is_err());
+        assert!(CronExpression::parse("*/0 * * * *").is_err());
+        assert!(CronExpression::parse("5-1 * * * *").is_err());
+        assert!(CronExpression::parse("0 0 31 2 *")
+            .unwrap()
+            .next_after(local(2023, 1, 1, 0, 0))
+            .is_none());
+    }
+}
@@ -23,3 +23,7 @@ pub(crate) fn default_status_datetime_format() -> String {
 pub(crate) fn default_callback_log_lines() -> usize {
     10
 }
+
+pub(crate) fn default_timeout_grace_period() -> u64 {
+    10
+}
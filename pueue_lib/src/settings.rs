@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs::{create_dir_all, File};
 use std::io::{prelude::*, BufReader};
 use std::path::{Path, PathBuf};
@@ -8,6 +8,12 @@ use serde_derive::{Deserialize, Serialize};
 use shellexpand::tilde;
 
 use crate::error::Error;
+use crate::log::LogRotation;
+#[cfg(not(target_os = "windows"))]
+use crate::network::tokens::Role;
+use crate::resources::{
+    deserialize_optional_amount, deserialize_resources, format_resource_amount, Resources,
+};
 use crate::setting_defaults::*;
 
 /// The environment variable that can be set to overwrite pueue's config path.
@@ -46,6 +52,18 @@ pub struct Shared {
     /// The path to the unix socket.
     #[cfg(not(target_os = "windows"))]
     pub unix_socket_path: Option<PathBuf>,
+    /// Clients of the unix socket, whose process runs with one of these user ids, are
+    /// authenticated via their credentials and don't need the shared secret. \
+    /// Each user id maps to the role the client gets, e.g. `{1000: submit}`. \
+    /// The socket is then accessible for all users, so make sure its directory is as well.
+    #[cfg(not(target_os = "windows"))]
+    #[serde(default = "Default::default")]
+    pub unix_socket_allowed_uids: BTreeMap<u32, Role>,
+    /// The same as `unix_socket_allowed_uids`, but for the group id of the client's process.
+    /// The user id takes precedence, if both are allowed.
+    #[cfg(not(target_os = "windows"))]
+    #[serde(default = "Default::default")]
+    pub unix_socket_allowed_gids: BTreeMap<u32, Role>,
 
     /// The TCP hostname/ip address.
     #[serde(default = "default_host")]
@@ -74,6 +92,18 @@ pub struct Shared {
     ///
     /// The path to the file containing the shared secret used to authenticate the client.
     pub shared_secret_path: Option<PathBuf>,
+
+    /// If this is set, the daemon additionally serves a HTTP/JSON API on this address,
+    /// e.g. `127.0.0.1:6925`. \
+    /// The API is only served via HTTPS with the daemon's certificate.
+    /// Requests have to be authenticated via an `Authorization: Bearer <token>` header.
+    /// The token is either the shared secret or the `http_api_token`.
+    #[serde(default = "Default::default")]
+    pub http_api_address: Option<String>,
+    /// An additional bearer token that's accepted by the HTTP API.
+    /// This allows to give scripts or dashboards access without sharing the secret.
+    #[serde(default = "Default::default")]
+    pub http_api_token: Option<String>,
 }
 
 /// All settings which are used by the client
@@ -109,7 +139,7 @@ pub struct Client {
 }
 
 /// All settings which are used by the daemon
-#[derive(PartialEq, Eq, Clone, Debug, Deserialize, Serialize)]
+#[derive(PartialEq, Clone, Debug, Deserialize, Serialize)]
 pub struct Daemon {
     /// Whether a group should be paused as soon as a single task fails
     #[serde(default = "Default::default")]
@@ -134,6 +164,202 @@ pub struct Daemon {
     /// Windows default:
     /// `vec!["powershell", "-c", "[Console]::OutputEncoding = [Text.UTF8Encoding]::UTF8; {{ pueue_command_string }}"]`
     pub shell_command: Option<Vec<String>>,
+    /// The amount of seconds a task gets to gracefully exit after receiving a SIGTERM due to
+    /// its timeout. The task is killed with SIGKILL once this grace period is over.
+    #[serde(default = "default_timeout_grace_period")]
+    pub timeout_grace_period: u64,
+    /// If this is set, running tasks aren't killed when the daemon shuts down.
+    /// Instead, the daemon reattaches to them once it's started again and keeps tracking them
+    /// until they finish. This allows restarting or upgrading the daemon without losing work.
+    /// The exit code of such tasks is written to a file via POSIX shell syntax, which is why
+    /// this requires a POSIX compatible `shell_command`, such as `sh` or `bash`.
+    #[serde(default = "Default::default")]
+    pub reattach_tasks: bool,
+    /// The resources that're available to all tasks of all groups, e.g. `mem: "64G"`.
+    /// Tasks are only started, if their requested resources are still available.
+    #[serde(
+        default = "Default::default",
+        deserialize_with = "deserialize_resources"
+    )]
+    pub resources: Resources,
+    /// The maximum amount of tasks that may run at the same time across all groups.
+    /// This can be adjusted at runtime via `pueue parallel --global`.
+    #[serde(default = "Default::default")]
+    pub max_parallel_tasks: Option<usize>,
+    /// Write stdout and stderr of all new tasks to separate log files.
+    #[serde(default = "Default::default")]
+    pub separate_output: bool,
+    /// Record the time at which each line of task output has been written.
+    /// The output is then piped through the daemon, which means that tasks can't keep running
+    /// while the daemon restarts. Hence, this is ignored if `reattach_tasks` is enabled.
+    #[serde(default = "Default::default")]
+    pub log_timestamps: bool,
+    /// Rotate the log files of running tasks, once they grow beyond this size, e.g. `"100M"`.
+    #[serde(
+        default = "Default::default",
+        deserialize_with = "deserialize_optional_amount"
+    )]
+    pub max_log_size: Option<u64>,
+    /// The amount of rotated log segments that're kept for each log file.
+    /// Rotated segments are compressed. If this is `0`, log files are simply truncated.
+    #[serde(default = "Default::default")]
+    pub log_rotation_count: usize,
+    /// Compress the log files of tasks, once they finished.
+    /// Compressed logs are stored next to the usual log files with an additional `.sz` extension.
+    #[serde(default = "Default::default")]
+    pub compress_finished_logs: bool,
+    /// Hold back queued tasks of all groups, while the system is too busy.
+    #[serde(flatten)]
+    pub system_limits: SystemLimits,
+    /// Additional limits for specific groups, which apply on top of the global ones.
+    #[serde(default = "Default::default")]
+    pub group_system_limits: BTreeMap<String, SystemLimits>,
+    /// Automatically remove finished tasks of all groups, together with their logs.
+    #[serde(default = "Default::default")]
+    pub retention: RetentionPolicy,
+    /// Retention policies for specific groups, which replace the global one.
+    #[serde(default = "Default::default")]
+    pub group_retention: BTreeMap<String, RetentionPolicy>,
+    /// Move finished tasks and their logs to the archive, instead of deleting them, once
+    /// they're removed via `clean`, `remove` or a retention policy.
+    #[serde(default = "Default::default")]
+    pub archive_removed_tasks: bool,
+    /// Where the daemon persists its state.
+    #[serde(default = "Default::default")]
+    pub state_backend: StateBackend,
+    /// Write all operations of clients that change the state to the `audit.jsonl` file in the
+    /// pueue directory.
+    #[serde(default = "Default::default")]
+    pub audit_log: bool,
+    /// Don't access this property directly, but rather use the getter with the same name.
+    ///
+    /// The path to a file with the tokens of additional principals, that may connect to the
+    /// daemon with restricted permissions. \
+    /// Clients that authenticate with the shared secret always have full access.
+    #[serde(default = "Default::default")]
+    pub tokens_path: Option<PathBuf>,
+    /// If this is set, the daemon serves metrics in the Prometheus text format via HTTP on
+    /// this address, e.g. `127.0.0.1:9469`.
+    #[serde(default = "Default::default")]
+    pub metrics_address: Option<String>,
+    /// Don't access this property directly, but rather use the getter with the same name.
+    ///
+    /// If this is set, the metrics are additionally served via HTTP on a unix socket at this
+    /// path.
+    #[cfg(not(target_os = "windows"))]
+    #[serde(default = "Default::default")]
+    pub metrics_socket_path: Option<PathBuf>,
+}
+
+impl Daemon {
+    /// The limits for the size of task log files, if any have been configured.
+    pub fn log_rotation(&self) -> Option<LogRotation> {
+        self.max_log_size.map(|max_size| LogRotation {
+            max_size,
+            count: self.log_rotation_count,
+        })
+    }
+
+    /// The retention policy that applies to the finished tasks of a group.
+    pub fn retention_policy(&self, group: &str) -> &RetentionPolicy {
+        self.group_retention.get(group).unwrap_or(&self.retention)
+    }
+
+    /// The location of the token file, if one has been configured.
+    pub fn tokens_path(&self) -> Option<PathBuf> {
+        self.tokens_path.as_deref().map(expand_home)
+    }
+
+    /// The location of the metrics socket, if one has been configured.
+    #[cfg(not(target_os = "windows"))]
+    pub fn metrics_socket_path(&self) -> Option<PathBuf> {
+        self.metrics_socket_path.as_deref().map(expand_home)
+    }
+}
+
+/// The storage backends for the daemon's state.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StateBackend {
+    /// The whole state is written to `state.json` on every change.
+    #[default]
+    Json,
+    /// The state is stored in the `state.sqlite` database.
+    /// Only those tasks that changed are written.
+    Sqlite,
+}
+
+/// Rules for automatically removing finished tasks.
+/// Tasks that are removed due to these rules are handled just like `pueue clean` does.
+#[derive(PartialEq, Eq, Clone, Debug, Default, Deserialize, Serialize)]
+pub struct RetentionPolicy {
+    /// Remove finished tasks, once they finished more than this amount of days ago.
+    #[serde(default = "Default::default")]
+    pub max_age_days: Option<u64>,
+    /// Keep at most this amount of finished tasks per group.
+    /// The tasks that finished first are removed first.
+    #[serde(default = "Default::default")]
+    pub max_finished_tasks: Option<usize>,
+    /// Only remove successfully finished tasks.
+    /// Other finished tasks are neither removed nor counted towards `max_finished_tasks`.
+    #[serde(default = "Default::default")]
+    pub successful_only: bool,
+}
+
+impl RetentionPolicy {
+    /// Whether any rule is set at all.
+    pub fn is_empty(&self) -> bool {
+        self.max_age_days.is_none() && self.max_finished_tasks.is_none()
+    }
+}
+
+/// Thresholds for the system's load, which prevent new tasks from being started.
+/// This is currently only supported on Linux.
+#[derive(PartialEq, Clone, Debug, Default, Deserialize, Serialize)]
+pub struct SystemLimits {
+    /// Don't start new tasks, while the one-minute load average is above this value.
+    #[serde(default = "Default::default")]
+    pub max_load_average: Option<f64>,
+    /// Don't start new tasks, while less than this amount of memory is available, e.g. `"2G"`.
+    #[serde(
+        default = "Default::default",
+        deserialize_with = "deserialize_optional_amount"
+    )]
+    pub min_free_memory: Option<u64>,
+}
+
+impl SystemLimits {
+    /// Whether any threshold is set at all.
+    pub fn is_empty(&self) -> bool {
+        self.max_load_average.is_none() && self.min_free_memory.is_none()
+    }
+
+    /// Check the current load average and available memory against these limits.
+    /// Returns the reason why no tasks should be started, if any limit is exceeded.
+    /// Values that couldn't be determined are ignored.
+    pub fn exceeded(
+        &self,
+        load_average: Option<f64>,
+        available_memory: Option<u64>,
+    ) -> Option<String> {
+        if let (Some(max), Some(load_average)) = (self.max_load_average, load_average) {
+            if load_average > max {
+                return Some(format!("load average {load_average:.2} is above {max:.2}"));
+            }
+        }
+
+        if let (Some(min), Some(available)) = (self.min_free_memory, available_memory) {
+            if available < min {
+                return Some(format!(
+                    "free memory {}M is below {}",
+                    available / 1024 / 1024,
+                    format_resource_amount(min)
+                ));
+            }
+        }
+
+        None
+    }
 }
 
 impl Default for Shared {
@@ -147,6 +373,10 @@ impl Default for Shared {
             unix_socket_path: None,
             #[cfg(not(target_os = "windows"))]
             use_unix_socket: true,
+            #[cfg(not(target_os = "windows"))]
+            unix_socket_allowed_uids: BTreeMap::new(),
+            #[cfg(not(target_os = "windows"))]
+            unix_socket_allowed_gids: BTreeMap::new(),
             host: default_host(),
             port: default_port(),
 
@@ -154,6 +384,8 @@ impl Default for Shared {
             daemon_cert: None,
             daemon_key: None,
             shared_secret_path: None,
+            http_api_address: None,
+            http_api_token: None,
         }
     }
 }
@@ -182,13 +414,33 @@ impl Default for Daemon {
             callback_log_lines: default_callback_log_lines(),
             shell_command: None,
             env_vars: HashMap::new(),
+            timeout_grace_period: default_timeout_grace_period(),
+            reattach_tasks: false,
+            resources: Resources::new(),
+            max_parallel_tasks: None,
+            separate_output: false,
+            log_timestamps: false,
+            max_log_size: None,
+            log_rotation_count: 0,
+            compress_finished_logs: false,
+            system_limits: SystemLimits::default(),
+            group_system_limits: BTreeMap::new(),
+            retention: RetentionPolicy::default(),
+            group_retention: BTreeMap::new(),
+            archive_removed_tasks: false,
+            state_backend: StateBackend::default(),
+            audit_log: false,
+            tokens_path: None,
+            metrics_address: None,
+            #[cfg(not(target_os = "windows"))]
+            metrics_socket_path: None,
         }
     }
 }
 
 /// The parent settings struct. \
 /// This contains all other setting structs.
-#[derive(PartialEq, Eq, Clone, Default, Debug, Deserialize, Serialize)]
+#[derive(PartialEq, Clone, Default, Debug, Deserialize, Serialize)]
 pub struct Settings {
     #[serde(default = "Default::default")]
     pub client: Client,
@@ -202,7 +454,7 @@ pub struct Settings {
 /// The nested settings struct for profiles. \
 /// In contrast to the normal `Settings` struct, this struct doesn't allow profiles.
 /// That way we prevent nested profiles and problems with self-referencing structs.
-#[derive(PartialEq, Eq, Clone, Debug, Deserialize, Serialize)]
+#[derive(PartialEq, Clone, Debug, Deserialize, Serialize)]
 pub struct NestedSettings {
     #[serde(default = "Default::default")]
     pub client: Client,
@@ -478,4 +730,42 @@ mod test {
 
         panic!("Got unexpected result when expecting missing profile error: {result:?}");
     }
+
+    /// System limits are read from the daemon's config and compared against the system's load.
+    #[test]
+    fn test_system_limits() {
+        let daemon: Daemon = serde_yaml::from_str(
+            "max_load_average: 4.5\ngroup_system_limits:\n  heavy:\n    min_free_memory: 2G\n",
+        )
+        .unwrap();
+        assert_eq!(daemon.system_limits.max_load_average, Some(4.5));
+        let heavy = &daemon.group_system_limits["heavy"];
+        assert_eq!(heavy.min_free_memory, Some(2 * 1024 * 1024 * 1024));
+
+        assert_eq!(daemon.system_limits.exceeded(Some(4.0), None), None);
+        assert_eq!(
+            daemon.system_limits.exceeded(Some(5.0), None),
+            Some("load average 5.00 is above 4.50".into())
+        );
+        assert_eq!(
+            heavy.exceeded(Some(100.0), Some(1024 * 1024 * 1024)),
+            Some("free memory 1024M is below 2G".into())
+        );
+        assert_eq!(heavy.exceeded(None, None), None);
+    }
+
+    /// Group retention policies replace the global one.
+    #[test]
+    fn test_retention_policy() {
+        let daemon: Daemon = serde_yaml::from_str(
+            "retention:\n  max_age_days: 7\ngroup_retention:\n  builds:\n    max_finished_tasks: 10\n    successful_only: true\n",
+        )
+        .unwrap();
+        assert_eq!(daemon.retention_policy("default").max_age_days, Some(7));
+
+        let builds = daemon.retention_policy("builds");
+        assert_eq!(builds.max_age_days, None);
+        assert_eq!(builds.max_finished_tasks, Some(10));
+        assert!(builds.successful_only);
+    }
 }
@@ -4,6 +4,8 @@ use std::sync::{Arc, Mutex};
 use serde_derive::{Deserialize, Serialize};
 
 use crate::error::Error;
+use crate::resources::Resources;
+use crate::schedule::Schedule;
 use crate::task::{Task, TaskStatus};
 
 pub const PUEUE_DEFAULT_GROUP: &str = "default";
@@ -23,6 +25,18 @@ pub enum GroupStatus {
 pub struct Group {
     pub status: GroupStatus,
     pub parallel_tasks: usize,
+    /// The resources that're available to the tasks of this group.
+    #[serde(default = "Default::default")]
+    pub resources: Resources,
+    /// The reason why the daemon currently doesn't start any tasks in this group, even though
+    /// it's running, e.g. due to a high system load.
+    #[serde(default = "Default::default")]
+    pub blocked: Option<String>,
+    /// Groups with a higher priority start their tasks first.
+    /// This decides which group gets the next free slot, once the daemon-wide limit of
+    /// parallel tasks has been reached.
+    #[serde(default = "Default::default")]
+    pub priority: i32,
 }
 
 /// This is the full representation of the current state of the Pueue daemon.
@@ -54,6 +68,13 @@ pub struct State {
     pub tasks: BTreeMap<usize, Task>,
     /// All groups with their current state a configuration.
     pub groups: BTreeMap<String, Group>,
+    /// All recurring schedules, which periodically create new tasks.
+    #[serde(default = "Default::default")]
+    pub schedules: BTreeMap<usize, Schedule>,
+    /// The maximum amount of tasks that may run at the same time across all groups.
+    /// This is set to the `max_parallel_tasks` setting whenever the daemon starts.
+    #[serde(default = "Default::default")]
+    pub max_parallel_tasks: Option<usize>,
 }
 
 impl Default for State {
@@ -76,6 +97,8 @@ impl State {
         let mut state = State {
             tasks: BTreeMap::new(),
             groups: BTreeMap::new(),
+            schedules: BTreeMap::new(),
+            max_parallel_tasks: None,
         };
         state.create_group(PUEUE_DEFAULT_GROUP);
         state
@@ -93,6 +116,18 @@ impl State {
         next_id
     }
 
+    /// Add a new schedule
+    pub fn add_schedule(&mut self, mut schedule: Schedule) -> usize {
+        let next_id = match self.schedules.keys().max() {
+            None => 0,
+            Some(id) => id + 1,
+        };
+        schedule.id = next_id;
+        self.schedules.insert(next_id, schedule);
+
+        next_id
+    }
+
     /// A small helper to change the status of a specific task.
     pub fn change_status(&mut self, id: usize, new_status: TaskStatus) {
         if let Some(ref mut task) = self.tasks.get_mut(&id) {
@@ -107,6 +142,9 @@ impl State {
         self.groups.entry(name.into()).or_insert(Group {
             status: GroupStatus::Running,
             parallel_tasks: 1,
+            resources: Resources::new(),
+            blocked: None,
+            priority: 0,
         })
     }
 
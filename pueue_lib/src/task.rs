@@ -1,10 +1,13 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
 
 use chrono::prelude::*;
 use serde_derive::{Deserialize, Serialize};
 use strum_macros::Display;
 
+use crate::resources::Resources;
 use crate::state::PUEUE_DEFAULT_GROUP;
+use crate::workflow::WorkflowRef;
 
 /// This enum represents the status of the internal task handling of Pueue.
 /// They basically represent the internal task life-cycle.
@@ -40,6 +43,102 @@ pub enum TaskResult {
     Errored,
     /// A dependency of the task failed.
     DependencyFailed,
+    /// The task exceeded its timeout and has been terminated by the daemon.
+    TimedOut,
+}
+
+/// The condition under which a dependency is fulfilled, so the dependent task may start.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub enum DependencyCondition {
+    /// The dependency has to succeed.
+    #[default]
+    Success,
+    /// The dependency has to fail in any kind of way.
+    Failure,
+    /// The dependency only has to finish, no matter its result.
+    Any,
+    /// The dependency has to exit with the given exit code.
+    ExitCode(i32),
+}
+
+impl DependencyCondition {
+    /// Check whether a dependency with the given status fulfills this condition.
+    pub fn is_fulfilled(&self, status: &TaskStatus) -> bool {
+        let TaskStatus::Done(result) = status else {
+            return false;
+        };
+
+        match self {
+            DependencyCondition::Success => matches!(result, TaskResult::Success),
+            DependencyCondition::Failure => !matches!(result, TaskResult::Success),
+            DependencyCondition::Any => true,
+            DependencyCondition::ExitCode(code) => match result {
+                TaskResult::Success => *code == 0,
+                TaskResult::Failed(exit_code) => exit_code == code,
+                _ => false,
+            },
+        }
+    }
+
+    /// Check whether a dependency with the given status finished without fulfilling this
+    /// condition, i.e. it never will.
+    pub fn is_violated(&self, status: &TaskStatus) -> bool {
+        matches!(status, TaskStatus::Done(_)) && !self.is_fulfilled(status)
+    }
+}
+
+/// Determines how the delay between two retries of a failed task evolves.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default, Display, Serialize, Deserialize)]
+pub enum RetryBackoff {
+    /// Always wait the same amount of time between two attempts.
+    #[default]
+    Fixed,
+    /// Double the delay after each failed attempt.
+    Exponential,
+}
+
+/// Describes whether and how a task should be retried, if it fails.
+#[derive(PartialEq, Eq, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// The maximum amount of retries after the initial attempt.
+    pub max_retries: usize,
+    /// The delay in seconds before the first retry is enqueued.
+    pub delay: u64,
+    pub backoff: RetryBackoff,
+}
+
+impl RetryPolicy {
+    /// Get the delay in seconds that should be waited before the given retry is enqueued.
+    /// `retry` is the 1-based number of the retry that's about to be scheduled.
+    pub fn delay_for_retry(&self, retry: usize) -> u64 {
+        match self.backoff {
+            RetryBackoff::Fixed => self.delay,
+            RetryBackoff::Exponential => {
+                // Cap the exponent to prevent overflows on absurdly high retry counts.
+                let exponent = retry.saturating_sub(1).min(32) as u32;
+                self.delay.saturating_mul(2u64.saturating_pow(exponent))
+            }
+        }
+    }
+}
+
+/// A previous, finished run of a task that has been retried afterwards.
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+pub struct TaskAttempt {
+    pub result: TaskResult,
+    pub start: Option<DateTime<Local>>,
+    pub end: Option<DateTime<Local>>,
+}
+
+/// Marks a task as a member of a task array, which has been added with `add --array`.
+#[derive(PartialEq, Eq, Clone, Debug, Deserialize, Serialize)]
+pub struct TaskArrayRef {
+    /// Arrays are identified by the id of their first task.
+    pub id: usize,
+    /// The position of the task's parameter.
+    pub index: usize,
+    /// The parameter the task's command has been rendered with.
+    pub value: String,
 }
 
 /// Representation of a task.
@@ -58,6 +157,10 @@ pub struct Task {
     pub envs: HashMap<String, String>,
     pub group: String,
     pub dependencies: Vec<usize>,
+    /// The conditions of those dependencies that don't have to succeed.
+    /// Dependencies without an entry have to succeed.
+    #[serde(default = "Default::default")]
+    pub dependency_conditions: BTreeMap<usize, DependencyCondition>,
     #[serde(default = "Default::default")]
     pub priority: i32,
     pub label: Option<String>,
@@ -70,6 +173,38 @@ pub struct Task {
     pub prev_status: TaskStatus,
     pub start: Option<DateTime<Local>>,
     pub end: Option<DateTime<Local>>,
+    /// If set, the task will automatically be re-run if it fails.
+    #[serde(default = "Default::default")]
+    pub retry_policy: Option<RetryPolicy>,
+    /// All previous runs of this task, if it has been automatically retried.
+    /// The current run is represented by `status`, `start` and `end`.
+    #[serde(default = "Default::default")]
+    pub attempts: Vec<TaskAttempt>,
+    /// The maximum runtime of the task in seconds.
+    /// Tasks exceeding it are terminated by the daemon.
+    #[serde(default = "Default::default")]
+    pub timeout: Option<u64>,
+    /// The process id of the task's process, which is also the id of its process group.
+    /// This is only set while the task is running and allows the daemon to reattach to the
+    /// process after a restart.
+    #[serde(default = "Default::default")]
+    pub pid: Option<u32>,
+    /// The resources this task needs while it's running.
+    #[serde(default = "Default::default")]
+    pub resources: Resources,
+    /// Whether stdout and stderr are written to separate log files.
+    #[serde(default = "Default::default")]
+    pub separate_output: bool,
+    /// The name of the principal that added this task.
+    /// This is unset for tasks of clients that authenticated via the shared secret.
+    #[serde(default = "Default::default")]
+    pub owner: Option<String>,
+    /// The workflow step, this task has been created for.
+    #[serde(default = "Default::default")]
+    pub workflow: Option<WorkflowRef>,
+    /// The task array this task is a member of.
+    #[serde(default = "Default::default")]
+    pub array: Option<TaskArrayRef>,
 }
 
 impl Task {
@@ -94,12 +229,22 @@ impl Task {
             envs,
             group,
             dependencies,
+            dependency_conditions: BTreeMap::new(),
             priority,
             label,
             status: starting_status.clone(),
             prev_status: starting_status,
             start: None,
             end: None,
+            retry_policy: None,
+            attempts: Vec::new(),
+            timeout: None,
+            pid: None,
+            resources: Resources::new(),
+            separate_output: false,
+            owner: None,
+            workflow: None,
+            array: None,
         }
     }
 
@@ -115,12 +260,22 @@ impl Task {
             envs: task.envs.clone(),
             group: task.group.clone(),
             dependencies: Vec::new(),
+            dependency_conditions: BTreeMap::new(),
             priority: 0,
             label: task.label.clone(),
             status: TaskStatus::Queued,
             prev_status: TaskStatus::Queued,
             start: None,
             end: None,
+            retry_policy: task.retry_policy.clone(),
+            attempts: Vec::new(),
+            timeout: task.timeout,
+            pid: None,
+            resources: task.resources.clone(),
+            separate_output: task.separate_output,
+            owner: task.owner.clone(),
+            workflow: None,
+            array: task.array.clone(),
         }
     }
 
@@ -145,6 +300,14 @@ impl Task {
         }
     }
 
+    /// Get the condition under which the given dependency is fulfilled.
+    pub fn dependency_condition(&self, task_id: usize) -> DependencyCondition {
+        self.dependency_conditions
+            .get(&task_id)
+            .copied()
+            .unwrap_or_default()
+    }
+
     /// Convenience helper on whether a task is stashed
     pub fn is_stashed(&self) -> bool {
         matches!(self.status, TaskStatus::Stashed { .. })
@@ -169,6 +332,17 @@ impl Task {
     pub fn is_in_default_group(&self) -> bool {
         self.group.eq(PUEUE_DEFAULT_GROUP)
     }
+
+    /// Check whether this task should be retried after finishing with the given result.
+    /// Only failed or errored runs are retried, as long as there are retries left.
+    pub fn should_retry(&self, result: &TaskResult) -> bool {
+        let Some(policy) = &self.retry_policy else {
+            return false;
+        };
+
+        matches!(result, TaskResult::Failed(_) | TaskResult::Errored)
+            && self.attempts.len() < policy.max_retries
+    }
 }
 
 /// We use a custom `Debug` implementation for [Task], as the `envs` field just has too much
@@ -186,11 +360,18 @@ impl std::fmt::Debug for Task {
             .field("envs", &"hidden")
             .field("group", &self.group)
             .field("dependencies", &self.dependencies)
+            .field("dependency_conditions", &self.dependency_conditions)
             .field("label", &self.label)
             .field("status", &self.status)
             .field("prev_status", &self.prev_status)
             .field("start", &self.start)
             .field("end", &self.end)
+            .field("retry_policy", &self.retry_policy)
+            .field("attempts", &self.attempts)
+            .field("timeout", &self.timeout)
+            .field("pid", &self.pid)
+            .field("resources", &self.resources)
+            .field("separate_output", &self.separate_output)
             .finish()
     }
 }
@@ -0,0 +1,169 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+/// A workflow consists of named steps, which may depend on each other.
+///
+/// Workflows are read from YAML files, e.g.:
+/// ```yaml
+/// name: release
+/// steps:
+///   build:
+///     command: cargo build --release
+///   test:
+///     command: cargo test
+///   publish:
+///     command: cargo publish
+///     group: deploy
+///     needs: [build, test]
+/// ```
+#[derive(PartialEq, Eq, Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Workflow {
+    pub name: String,
+    pub steps: BTreeMap<String, WorkflowStep>,
+}
+
+/// A single step of a [Workflow], which results in a task.
+#[derive(PartialEq, Eq, Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorkflowStep {
+    pub command: String,
+    /// The group of the step's task. Defaults to the default group.
+    #[serde(default = "Default::default")]
+    pub group: Option<String>,
+    /// The working directory of the step's task.
+    /// Relative paths are relative to the working directory of the client.
+    #[serde(default = "Default::default")]
+    pub path: Option<PathBuf>,
+    /// The names of the steps that have to finish successfully, before this step is started.
+    #[serde(default = "Default::default")]
+    pub needs: Vec<String>,
+}
+
+/// Marks a task as the step of a workflow.
+#[derive(PartialEq, Eq, Clone, Debug, Deserialize, Serialize)]
+pub struct WorkflowRef {
+    /// The name of the workflow.
+    pub workflow: String,
+    /// Workflows may be run several times. Runs are identified by the id of their first task.
+    pub run: usize,
+    /// The name of the step.
+    pub step: String,
+}
+
+impl Workflow {
+    /// Read a workflow from a YAML file.
+    pub fn read(path: &Path) -> Result<Workflow, Error> {
+        let file = File::open(path)
+            .map_err(|err| Error::IoPathError(path.to_path_buf(), "opening workflow file", err))?;
+
+        serde_yaml::from_reader(BufReader::new(file))
+            .map_err(|err| Error::InvalidWorkflow(err.to_string()))
+    }
+
+    /// Order the steps, so that each step comes after all steps it needs.
+    /// Steps that don't depend on each other are ordered by their name.
+    ///
+    /// Fails, if a step needs an unknown step or if the steps depend on each other in a cycle.
+    pub fn ordered_steps(&self) -> Result<Vec<(&String, &WorkflowStep)>, Error> {
+        if self.steps.is_empty() {
+            return Err(Error::InvalidWorkflow("The workflow has no steps".into()));
+        }
+
+        for (name, step) in &self.steps {
+            if let Some(unknown) = step
+                .needs
+                .iter()
+                .find(|need| !self.steps.contains_key(*need))
+            {
+                return Err(Error::InvalidWorkflow(format!(
+                    "Step '{name}' needs the unknown step '{unknown}'"
+                )));
+            }
+        }
+
+        let mut ordered: Vec<(&String, &WorkflowStep)> = Vec::new();
+        while ordered.len() < self.steps.len() {
+            // Take the first step, whose needed steps have all been ordered already.
+            let next = self.steps.iter().find(|(name, step)| {
+                !ordered.iter().any(|(ordered, _)| ordered == name)
+                    && step
+                        .needs
+                        .iter()
+                        .all(|need| ordered.iter().any(|(ordered, _)| *ordered == need))
+            });
+
+            match next {
+                Some(next) => ordered.push(next),
+                None => {
+                    let remaining: Vec<&String> = self
+                        .steps
+                        .keys()
+                        .filter(|name| !ordered.iter().any(|(ordered, _)| ordered == name))
+                        .collect();
+                    return Err(Error::InvalidWorkflow(format!(
+                        "The steps {remaining:?} depend on each other in a cycle"
+                    )));
+                }
+            }
+        }
+
+        Ok(ordered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn workflow(steps: &[(&str, &[&str])]) -> Workflow {
+        Workflow {
+            name: "test".into(),
+            steps: steps
+                .iter()
+                .map(|(name, needs)| {
+                    let step = WorkflowStep {
+                        command: "ls".into(),
+                        group: None,
+                        path: None,
+                        needs: needs.iter().map(|need| need.to_string()).collect(),
+                    };
+                    (name.to_string(), step)
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn ordered_steps() {
+        let workflow = workflow(&[("a", &["c"]), ("b", &[]), ("c", &["b"]), ("d", &[])]);
+        let names: Vec<&String> = workflow
+            .ordered_steps()
+            .unwrap()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+
+        assert_eq!(names, vec!["b", "c", "a", "d"]);
+    }
+
+    #[test]
+    fn unknown_step() {
+        let workflow = workflow(&[("a", &["b"])]);
+        assert!(workflow.ordered_steps().is_err());
+    }
+
+    #[test]
+    fn cycle() {
+        let workflow = workflow(&[("a", &["b"]), ("b", &["a"]), ("c", &[])]);
+        assert!(workflow.ordered_steps().is_err());
+    }
+}
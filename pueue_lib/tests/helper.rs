@@ -20,6 +20,10 @@ pub fn get_shared_settings(
         use_unix_socket,
         #[cfg(not(target_os = "windows"))]
         unix_socket_path: None,
+        #[cfg(not(target_os = "windows"))]
+        unix_socket_allowed_uids: Default::default(),
+        #[cfg(not(target_os = "windows"))]
+        unix_socket_allowed_gids: Default::default(),
         pid_path: None,
         host: "localhost".to_string(),
         port: pick_unused_port()
@@ -28,6 +32,8 @@ pub fn get_shared_settings(
         daemon_cert: Some(tempdir_path.join("certs").join("daemon.cert")),
         daemon_key: Some(tempdir_path.join("certs").join("daemon.key")),
         shared_secret_path: Some(tempdir_path.join("secret")),
+        http_api_address: None,
+        http_api_token: None,
     };
 
     (shared_settings, tempdir)
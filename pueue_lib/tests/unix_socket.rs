@@ -48,4 +48,28 @@ mod tests {
 
         Ok(())
     }
+
+    /// Payloads that exceed the maximum size are rejected based on their size header.
+    #[tokio::test]
+    async fn test_max_size() -> Result<()> {
+        better_panic::install();
+        let (shared_settings, _tempdir) = helper::get_shared_settings(true);
+
+        let listener = get_listener(&shared_settings).await?;
+        let handle = task::spawn(async move {
+            let mut stream = listener.accept().await.unwrap();
+            receive_bytes_with_max_size(&mut stream, Some(16)).await
+        });
+
+        let mut client = get_client_stream(&shared_settings).await?;
+        send_bytes(&[0; 32], &mut client).await?;
+
+        let result = handle.await?;
+        assert!(matches!(
+            result,
+            Err(pueue_lib::error::Error::MessageTooBig(32, 16))
+        ));
+
+        Ok(())
+    }
 }